@@ -0,0 +1,65 @@
+//! Pulsing dot in the bottom left corner, its four frames drawn into a sprite sheet here.
+
+use unnamed_engine::{
+    app::{App, Scene, State},
+    graphics::{
+        AnimationClip, Animator, GraphicsContext, PlaybackMode, RenderObject, SpriteSheet,
+        Transform,
+    },
+};
+
+const FRAMES: u32 = 4;
+const CELL: u32 = 16;
+
+struct Pulse;
+impl Scene for Pulse {
+    fn setup(
+        &mut self,
+        graphics_context: &mut GraphicsContext,
+        state: &mut State,
+    ) -> anyhow::Result<()> {
+        let (width, height) = (CELL * FRAMES, CELL);
+        let mut pixels = Vec::with_capacity((width * height * 4) as usize);
+        for y in 0..height {
+            for x in 0..width {
+                let frame = x / CELL;
+                let center = CELL as f32 / 2.0;
+                let (dx, dy) = ((x % CELL) as f32 + 0.5 - center, y as f32 + 0.5 - center);
+                let radius = center * (frame + 1) as f32 / FRAMES as f32;
+                let alpha = if dx.hypot(dy) <= radius { u8::MAX } else { 0 };
+                pixels.extend([255, 220, 120, alpha]);
+            }
+        }
+        let texture = graphics_context.create_texture(width, height, pixels)?;
+
+        let animator = Animator::new(SpriteSheet::grid(texture, FRAMES, 1)).with_clip(
+            AnimationClip::new("pulse", 0..FRAMES as usize, 0.12)
+                .with_mode(PlaybackMode::PingPong)
+                .with_frame_duration(FRAMES as usize - 1, 0.4)
+                .with_event(FRAMES as usize - 1, "peak"),
+        );
+        let mut sprite = RenderObject::sprite(
+            animator,
+            (0.2, 0.2),
+            Some("Pulse"),
+            Transform::builder().position(-0.8, -0.8).build(),
+        );
+        if let Some(animator) = &mut sprite.animator {
+            animator.play("pulse");
+        }
+        state.add_object(sprite);
+
+        Ok(())
+    }
+
+    fn update(&mut self, _: &mut GraphicsContext, state: &mut State, _: f32) {
+        for (id, event) in state.drain_animation_events() {
+            log::info!("Animation event {} of {id:?}", event.event);
+        }
+    }
+}
+
+fn main() -> anyhow::Result<()> {
+    env_logger::init();
+    App::run_with(Pulse)
+}
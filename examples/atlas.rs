@@ -0,0 +1,47 @@
+//! First sprite of an atlas written by `pack-atlas`, passed as the only argument.
+
+use std::path::PathBuf;
+
+use unnamed_engine::{
+    app::{App, Scene, State},
+    graphics::{GraphicsContext, SpriteAtlas, Transform},
+};
+
+struct AtlasSprite {
+    path: PathBuf,
+}
+impl Scene for AtlasSprite {
+    fn setup(
+        &mut self,
+        graphics_context: &mut GraphicsContext,
+        state: &mut State,
+    ) -> anyhow::Result<()> {
+        let atlas = SpriteAtlas::load(graphics_context, &self.path)?;
+        let Some(name) = atlas.names().min() else {
+            return Ok(());
+        };
+        let Some(sprite) = atlas.sprite(name) else {
+            return Ok(());
+        };
+        let [width, height] = sprite.source_size.map(|size| size as f32);
+        let scale = 0.3 / width.max(height).max(1.0);
+        if let Some(object) = atlas.object(
+            name,
+            (width * scale, height * scale),
+            Some(name),
+            Transform::builder().position(-0.5, 0.4).build(),
+        ) {
+            state.add_object(object);
+        }
+
+        Ok(())
+    }
+}
+
+fn main() -> anyhow::Result<()> {
+    env_logger::init();
+    let Some(path) = std::env::args_os().nth(1) else {
+        anyhow::bail!("Usage: atlas <atlas.json>");
+    };
+    App::run_with(AtlasSprite { path: path.into() })
+}
@@ -0,0 +1,147 @@
+//! Game of Life run by a compute shader into a storage texture, shown in the middle of the
+//! window. Every few generations the cells are read back to log the population.
+
+use unnamed_engine::{
+    app::{App, Scene, State},
+    graphics::{
+        Color, Dispatch, GraphicsContext, Readback, RenderObject, StorageBufferId, Transform,
+        primitives,
+    },
+};
+
+/// Cells along each side of the grid.
+const SIZE: u32 = 64;
+/// Seconds between generations.
+const GENERATION: f32 = 0.1;
+/// Generations between population counts.
+const CENSUS: u64 = 50;
+
+/// `None` until set up, and where compute shaders aren't supported.
+struct Life(Option<Grid>);
+impl Scene for Life {
+    fn setup(
+        &mut self,
+        graphics_context: &mut GraphicsContext,
+        state: &mut State,
+    ) -> anyhow::Result<()> {
+        self.0 = Some(Grid::new(graphics_context, state)?);
+        Ok(())
+    }
+
+    fn update(&mut self, graphics_context: &mut GraphicsContext, _: &mut State, time: f32) {
+        if let Some(grid) = &mut self.0 {
+            grid.update(graphics_context, time);
+        }
+    }
+
+    /// The cells come back dead, the soup starts over.
+    fn device_recreated(
+        &mut self,
+        graphics_context: &mut GraphicsContext,
+        _: &mut State,
+    ) -> anyhow::Result<()> {
+        let Some(grid) = &mut self.0 else {
+            return Ok(());
+        };
+        for cells in grid.cells {
+            graphics_context.write_storage_buffer(cells, 0, &soup())?;
+        }
+        grid.generation = 0;
+        grid.census = None;
+        Ok(())
+    }
+}
+
+/// A fixed soup of cells, a fifth of it alive.
+fn soup() -> Vec<u8> {
+    let mut seed = 0x2545_f491_u32;
+    (0..SIZE * SIZE)
+        .flat_map(|_| {
+            seed = seed.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+            u32::from(seed >> 24 < 52).to_le_bytes()
+        })
+        .collect()
+}
+
+struct Grid {
+    cells: [StorageBufferId; 2],
+    /// Generation `n` reads `cells[n % 2]` and writes the other.
+    dispatches: [Dispatch; 2],
+    generation: u64,
+    last_step: f32,
+    census: Option<Readback>,
+}
+impl Grid {
+    fn new(graphics_context: &mut GraphicsContext, state: &mut State) -> anyhow::Result<Self> {
+        let pipeline =
+            graphics_context.create_compute_pipeline(include_str!("life.wgsl"), "cs_step")?;
+        let texture = graphics_context.create_storage_texture(SIZE, SIZE)?;
+
+        let cells = soup();
+        let front = graphics_context.create_storage_buffer(&cells)?;
+        let back = graphics_context.create_storage_buffer(&cells)?;
+
+        let workgroups = [SIZE.div_ceil(8), SIZE.div_ceil(8), 1];
+        let dispatch = |from, to| {
+            Dispatch::new(pipeline, workgroups)
+                .with_buffer(0, 0, from)
+                .with_buffer(0, 1, to)
+                .with_texture(0, 2, texture)
+        };
+        state.add_object(
+            RenderObject::new(
+                primitives::rectangle(0.35, 0.35, Color::WHITE),
+                Some("Life"),
+                Transform::builder().position(0.0, 0.35).build(),
+            )
+            .with_texture(texture)
+            .with_lit(false),
+        );
+
+        Ok(Self {
+            cells: [front, back],
+            dispatches: [dispatch(front, back), dispatch(back, front)],
+            generation: 0,
+            last_step: f32::NEG_INFINITY,
+            census: None,
+        })
+    }
+
+    /// Steps a generation if it's due at `time`, in seconds.
+    fn update(&mut self, graphics_context: &mut GraphicsContext, time: f32) {
+        if let Some(result) = self.census.as_mut().and_then(Readback::try_take) {
+            self.census = None;
+            match result {
+                Ok(bytes) => {
+                    let alive = bytes.chunks_exact(4).filter(|cell| cell[0] != 0).count();
+                    log::info!("Life: {alive} cells alive");
+                }
+                Err(err) => log::warn!("Unable to count the cells: {err}"),
+            }
+        }
+        if time - self.last_step < GENERATION {
+            return;
+        }
+        self.last_step = time;
+
+        let dispatch = &self.dispatches[(self.generation % 2) as usize];
+        if let Err(err) = graphics_context.dispatch(dispatch) {
+            log::warn!("Unable to step the Game of Life: {err}");
+            return;
+        }
+        self.generation += 1;
+
+        if self.generation.is_multiple_of(CENSUS) && self.census.is_none() {
+            let latest = self.cells[(self.generation % 2) as usize];
+            match graphics_context.read_storage_buffer(latest) {
+                Ok(readback) => self.census = Some(readback),
+                Err(err) => log::warn!("Unable to count the cells: {err}"),
+            }
+        }
+    }
+}
+
+fn main() -> anyhow::Result<()> {
+    env_logger::init();
+    App::run_with(Life(None))
+}
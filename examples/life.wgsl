@@ -0,0 +1,36 @@
+// One generation of Conway's Game of Life on a wrapping grid, the size of `image`. Cells are
+// 0 or 1, the new ones are also painted into `image`.
+
+@group(0) @binding(0)
+var<storage, read> cells: array<u32>;
+
+@group(0) @binding(1)
+var<storage, read_write> next_cells: array<u32>;
+
+@group(0) @binding(2)
+var image: texture_storage_2d<rgba8unorm, write>;
+
+@compute @workgroup_size(8, 8)
+fn cs_step(@builtin(global_invocation_id) id: vec3u) {
+    let size = vec2i(textureDimensions(image));
+    let cell = vec2i(id.xy);
+    if any(cell >= size) {
+        return;
+    }
+
+    var neighbors = 0u;
+    for (var dy = -1; dy <= 1; dy++) {
+        for (var dx = -1; dx <= 1; dx++) {
+            if dx == 0 && dy == 0 {
+                continue;
+            }
+            let neighbor = (cell + vec2i(dx, dy) + size) % size;
+            neighbors += cells[neighbor.y * size.x + neighbor.x];
+        }
+    }
+
+    let index = cell.y * size.x + cell.x;
+    let alive = neighbors == 3u || (neighbors == 2u && cells[index] == 1u);
+    next_cells[index] = u32(alive);
+    textureStore(image, cell, select(vec4f(0.05, 0.08, 0.1, 1.0), vec4f(0.4, 1.0, 0.6, 1.0), alive));
+}
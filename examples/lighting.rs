@@ -0,0 +1,134 @@
+//! Dark scene lit by a torch following the cursor and a spot light from the top left, over a
+//! bumpy dome on the right whose normal map is computed here. Clicks drop triangles casting
+//! shadows, `L` switches the lights.
+
+use unnamed_engine::{
+    app::{App, Scene, State},
+    graphics::{Color, GraphicsContext, Light, Lighting, RenderObject, Transform, primitives},
+};
+use winit::{
+    event::{ElementState, KeyEvent, MouseButton, WindowEvent},
+    keyboard::{KeyCode, PhysicalKey},
+};
+
+const SIZE: u32 = 32;
+
+/// The first light is the torch.
+fn lighting() -> Lighting {
+    Lighting::new(Color::srgb(0.12, 0.12, 0.18))
+        .with_light(Light::point([0.0, 0.0], 0.9, Color::srgb(1.0, 0.75, 0.45)).with_intensity(1.6))
+        .with_light(Light::spot(
+            [-0.9, 0.9],
+            2.2,
+            Color::srgb(0.5, 0.7, 1.0),
+            -std::f32::consts::FRAC_PI_4,
+            0.6,
+        ))
+}
+
+struct Lights;
+impl Scene for Lights {
+    fn setup(
+        &mut self,
+        graphics_context: &mut GraphicsContext,
+        state: &mut State,
+    ) -> anyhow::Result<()> {
+        let mut colors = Vec::with_capacity((SIZE * SIZE * 4) as usize);
+        let mut normals = Vec::with_capacity((SIZE * SIZE * 4) as usize);
+        for y in 0..SIZE {
+            for x in 0..SIZE {
+                // texture rows go down, normals point up the image
+                let center = SIZE as f32 / 2.0;
+                let (dx, dy) = (x as f32 + 0.5 - center, center - y as f32 - 0.5);
+                let [nx, ny] = [dx / center, dy / center];
+                let inside = nx.hypot(ny) < 1.0;
+                let nz = (1.0 - nx * nx - ny * ny).max(0.0).sqrt();
+                colors.extend([200, 190, 170, if inside { u8::MAX } else { 0 }]);
+                normals.extend([nx, ny, nz].map(|n| ((n * 0.5 + 0.5) * 255.0).round() as u8));
+                normals.push(u8::MAX);
+            }
+        }
+        let texture = graphics_context.create_texture(SIZE, SIZE, colors)?;
+        let normal_map = graphics_context.create_data_texture(SIZE, SIZE, normals)?;
+
+        state.add_object(
+            RenderObject::new(
+                primitives::rectangle(0.3, 0.3, Color::WHITE),
+                Some("Dome"),
+                Transform::builder().position(0.75, 0.0).build(),
+            )
+            .with_texture(texture)
+            .with_normal_map(normal_map),
+        );
+        state.add_object(
+            RenderObject::new(
+                primitives::rectangle(0.2, 0.2, Color::BLACK),
+                Some("Pillar"),
+                Transform::builder().position(-0.3, 0.3).build(),
+            )
+            .with_occluder(true),
+        );
+        state.lighting = Some(lighting());
+
+        Ok(())
+    }
+
+    fn update(&mut self, graphics_context: &mut GraphicsContext, state: &mut State, _time: f32) {
+        let cursor = graphics_context.screen_to_world(state.cursor_position, &state.camera);
+        if let Some(torch) = state
+            .lighting
+            .as_mut()
+            .and_then(|lighting| lighting.lights.first_mut())
+        {
+            torch.position = cursor;
+        }
+    }
+
+    fn window_event(
+        &mut self,
+        graphics_context: &mut GraphicsContext,
+        state: &mut State,
+        event: &WindowEvent,
+    ) -> bool {
+        match event {
+            WindowEvent::KeyboardInput {
+                event:
+                    KeyEvent {
+                        physical_key: PhysicalKey::Code(KeyCode::KeyL),
+                        state: ElementState::Pressed,
+                        ..
+                    },
+                ..
+            } => {
+                state.lighting = match state.lighting {
+                    Some(_) => None,
+                    None => Some(lighting()),
+                };
+                log::info!("Lighting: {}", state.lighting.is_some());
+                true
+            }
+            WindowEvent::MouseInput {
+                state: ElementState::Pressed,
+                button: MouseButton::Left,
+                ..
+            } => {
+                let [x, y] = graphics_context.screen_to_world(state.cursor_position, &state.camera);
+                state.add_object(
+                    RenderObject::new(
+                        primitives::triangle(0.1, Color::BLACK),
+                        Some("Occluder"),
+                        Transform::builder().position(x, y).build(),
+                    )
+                    .with_occluder(true),
+                );
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+fn main() -> anyhow::Result<()> {
+    env_logger::init();
+    App::run_with(Lights)
+}
@@ -0,0 +1,53 @@
+//! Framed panel at the bottom, its 12 pixel frame texture stretched by nine-slicing.
+
+use unnamed_engine::{
+    app::{App, Scene, State},
+    graphics::{
+        Color, GraphicsContext, RenderObject, Transform,
+        primitives::{self, NineSlice, SliceFill},
+    },
+};
+
+const SIZE: u32 = 12;
+const BORDER: u32 = 4;
+
+struct Panel;
+impl Scene for Panel {
+    fn setup(
+        &mut self,
+        graphics_context: &mut GraphicsContext,
+        state: &mut State,
+    ) -> anyhow::Result<()> {
+        let mut pixels = Vec::with_capacity((SIZE * SIZE * 4) as usize);
+        for y in 0..SIZE {
+            for x in 0..SIZE {
+                let edge = x.min(y).min(SIZE - 1 - x).min(SIZE - 1 - y);
+                pixels.extend(match edge {
+                    0 => [20, 20, 30, 255],
+                    edge if edge < BORDER => [140, 150, 190, 255],
+                    _ if (x + y) % 2 == 0 => [50, 55, 80, 230],
+                    _ => [60, 65, 95, 230],
+                });
+            }
+        }
+        let texture = graphics_context.create_texture(SIZE, SIZE, pixels)?;
+
+        let slice = NineSlice::new((SIZE, SIZE), [BORDER; 4], 0.01).with_fill(SliceFill::Tile);
+        state.add_object(
+            RenderObject::new(
+                primitives::nine_slice(&slice, 0.7, 0.2, Color::WHITE),
+                Some("Panel"),
+                Transform::builder().position(0.0, -0.8).build(),
+            )
+            .with_texture(texture)
+            .with_lit(false),
+        );
+
+        Ok(())
+    }
+}
+
+fn main() -> anyhow::Result<()> {
+    env_logger::init();
+    App::run_with(Panel)
+}
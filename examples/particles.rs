@@ -0,0 +1,79 @@
+//! Campfire on the left, sparks flying off it and smoke rising, on a soft 16 pixel dot.
+
+use std::f32::consts::FRAC_PI_2;
+
+use unnamed_engine::{
+    app::{App, Scene, State},
+    graphics::{
+        Color, Curve, Emission, Emitter, EmitterShape, GraphicsContext, ParticleSystem,
+        RenderObject, Transform,
+    },
+};
+
+const SIZE: u32 = 16;
+
+struct Campfire;
+impl Scene for Campfire {
+    fn setup(
+        &mut self,
+        graphics_context: &mut GraphicsContext,
+        state: &mut State,
+    ) -> anyhow::Result<()> {
+        let mut pixels = Vec::with_capacity((SIZE * SIZE * 4) as usize);
+        for y in 0..SIZE {
+            for x in 0..SIZE {
+                let center = SIZE as f32 / 2.0;
+                let distance = (x as f32 + 0.5 - center).hypot(y as f32 + 0.5 - center) / center;
+                let alpha = (1.0 - distance).clamp(0.0, 1.0).powi(2);
+                pixels.extend([u8::MAX, u8::MAX, u8::MAX, (alpha * 255.0).round() as u8]);
+            }
+        }
+        let texture = graphics_context.create_texture(SIZE, SIZE, pixels)?;
+
+        let smoke = Emitter::new(
+            EmitterShape::Circle { radius: 0.04 },
+            Emission::Continuous(12.0),
+        )
+        .with_position([0.0, 0.05])
+        .with_lifetime(2.0, 3.0)
+        .with_speed(0.08, 0.15)
+        .with_direction(FRAC_PI_2, 0.6)
+        .with_gravity([0.03, 0.0])
+        .with_size(Curve::linear(0.08, 0.3))
+        .with_color(
+            Curve::constant(Color::srgba(0.4, 0.4, 0.45, 0.0))
+                .with_key(0.2, Color::srgba(0.4, 0.4, 0.45, 0.5))
+                .with_key(1.0, Color::srgba(0.3, 0.3, 0.35, 0.0)),
+        );
+        let sparks = Emitter::new(EmitterShape::Point, Emission::Continuous(60.0))
+            .with_lifetime(0.5, 1.2)
+            .with_speed(0.3, 0.7)
+            .with_direction(FRAC_PI_2, 0.8)
+            .with_angular_velocity(-4.0, 4.0)
+            .with_gravity([0.0, -0.6])
+            .with_drag(0.5)
+            .with_size(Curve::linear(0.04, 0.01))
+            .with_color(Curve::linear(
+                Color::srgb(1.0, 0.9, 0.4),
+                Color::srgba(0.9, 0.2, 0.05, 0.0),
+            ));
+
+        state.add_object(
+            RenderObject::particles(
+                ParticleSystem::new()
+                    .with_emitter(smoke)
+                    .with_emitter(sparks),
+                Some("Campfire"),
+                Transform::builder().position(-0.75, -0.45).build(),
+            )
+            .with_texture(texture),
+        );
+
+        Ok(())
+    }
+}
+
+fn main() -> anyhow::Result<()> {
+    env_logger::init();
+    App::run_with(Campfire)
+}
@@ -0,0 +1,42 @@
+//! Zoomed in view of the app's triangle, rendered offscreen and shown in the top right corner.
+
+use unnamed_engine::{
+    app::{App, Scene, State},
+    graphics::{Camera, Color, GraphicsContext, RenderObject, Transform, primitives},
+};
+
+struct Preview;
+impl Scene for Preview {
+    fn setup(
+        &mut self,
+        graphics_context: &mut GraphicsContext,
+        state: &mut State,
+    ) -> anyhow::Result<()> {
+        let Some((first_id, first)) = state.objects().next() else {
+            return Ok(());
+        };
+        let [x, y] = first.transform.position;
+
+        let preview = graphics_context.create_render_target(256, 256)?;
+        if let Some(target) = graphics_context.render_target_mut(preview) {
+            target.camera = Camera::new().with_position(x, y).with_zoom(2.0);
+            target.clear_color = Color::srgb(0.1, 0.1, 0.2);
+            target.objects = vec![first_id];
+        }
+        state.add_object(
+            RenderObject::new(
+                primitives::rectangle(0.5, 0.5, Color::WHITE),
+                Some("Preview"),
+                Transform::builder().position(0.75, 0.65).build(),
+            )
+            .with_texture(preview),
+        );
+
+        Ok(())
+    }
+}
+
+fn main() -> anyhow::Result<()> {
+    env_logger::init();
+    App::run_with(Preview)
+}
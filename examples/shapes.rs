@@ -0,0 +1,31 @@
+//! Outlined rounded box drawn from its signed distance field instead of a tessellated mesh.
+
+use unnamed_engine::{
+    app::{App, Scene, State},
+    graphics::{Color, GraphicsContext, Outline, RenderObject, SdfShape, Transform},
+};
+
+struct RoundedBox;
+impl Scene for RoundedBox {
+    fn setup(&mut self, _: &mut GraphicsContext, state: &mut State) -> anyhow::Result<()> {
+        state.add_object(
+            RenderObject::shape(
+                SdfShape::RoundedBox {
+                    half_size: [0.25, 0.15],
+                    corner_radius: 0.05,
+                },
+                Color::srgb(0.9, 0.6, 0.2),
+                Some("Rounded Box"),
+                Transform::builder().position(0.5, -0.5).build(),
+            )
+            .with_outline(Outline::new(0.02, Color::BLACK)),
+        );
+
+        Ok(())
+    }
+}
+
+fn main() -> anyhow::Result<()> {
+    env_logger::init();
+    App::run_with(RoundedBox)
+}
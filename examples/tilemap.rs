@@ -0,0 +1,32 @@
+//! Tiled or LDtk map passed as the only argument, its top left corner in the window's.
+
+use std::path::PathBuf;
+
+use unnamed_engine::{
+    app::{App, Scene, State},
+    graphics::{GraphicsContext, Tilemap},
+};
+
+struct Map {
+    path: PathBuf,
+}
+impl Scene for Map {
+    fn setup(
+        &mut self,
+        graphics_context: &mut GraphicsContext,
+        state: &mut State,
+    ) -> anyhow::Result<()> {
+        let tilemap = Tilemap::load(graphics_context, &self.path, 0.1)?.with_position([-1.7, 1.0]);
+        state.add_tilemap(tilemap);
+
+        Ok(())
+    }
+}
+
+fn main() -> anyhow::Result<()> {
+    env_logger::init();
+    let Some(path) = std::env::args_os().nth(1) else {
+        anyhow::bail!("Usage: tilemap <map.tmx | map.ldtk>");
+    };
+    App::run_with(Map { path: path.into() })
+}
@@ -1,63 +1,69 @@
-use std::{sync::Arc, time::Instant};
+use std::sync::Arc;
 
 use winit::{
     application::ApplicationHandler,
-    dpi::{PhysicalPosition, PhysicalSize},
-    event::{ElementState, WindowEvent},
+    dpi::PhysicalSize,
+    event::{ElementState, MouseButton, WindowEvent},
     event_loop::{ActiveEventLoop, ControlFlow, EventLoop},
     window::{Window, WindowAttributes, WindowId},
 };
 
 use crate::{
-    app::{State, events},
+    app::{Scene, State, events},
     graphics::{
-        GraphicsContext, RenderObject, Transform, primitives,
-        uniforms::{SurfaceSizeUniform, TimeUniform, UniformKind},
+        self, Color, CullStats, FrameStatus, GraphicsConfig, GraphicsContext, RenderObject, Shadow,
+        SurfaceStatus, Text, TextAlign, TextRendering, Transform, primitives,
+        uniforms::{TimeUniform, UniformKind},
     },
 };
 
 const WINDOW_TITLE: &str = "unnamed-engine";
+const HELP: &str = "A: anti-aliasing   E: post effects   F: FPS cap   P: present mode   \
+    S: scale mode   V: vsync";
 
 pub struct App {
     window: Option<Arc<Window>>,
     graphics_context: Option<GraphicsContext>,
     state: State,
-    rendering_active: bool,
+    scene: Box<dyn Scene>,
+    /// Of the previous frame, logged when it changes.
+    cull_stats: CullStats,
+    graphics_config: GraphicsConfig,
 }
 impl App {
     pub fn run() -> anyhow::Result<()> {
+        Self::run_with(())
+    }
+
+    /// Runs the app with `scene` set up on top of what it shows by itself.
+    pub fn run_with(scene: impl Scene + 'static) -> anyhow::Result<()> {
         let event_loop = EventLoop::new()?;
         event_loop.set_control_flow(ControlFlow::Poll);
-        event_loop.run_app(&mut Self::new())?;
+        event_loop.run_app(&mut Self::new(GraphicsConfig::from_env(), Box::new(scene)))?;
 
         Ok(())
     }
 
-    fn new() -> Self {
+    fn new(graphics_config: GraphicsConfig, scene: Box<dyn Scene>) -> Self {
+        let mut state = State::new(Color::srgb(0.25, 0.25, 0.25));
+        state.add_object(RenderObject::new(
+            primitives::regular_polygon(3, 0.7, Color::BLACK),
+            Some("The Square"),
+            // TODO: send transform to gpu?
+            Transform::builder().position(-0.5, -0.5).build(), // currently does NOTHING
+        ));
+
         Self {
             window: None,
             graphics_context: None,
-            state: State {
-                render_objects: vec![RenderObject::new(
-                    primitives::regular_polygon(3, 0.7, wgpu::Color::BLACK),
-                    Some("The Square"),
-                    // TODO: send transform to gpu?
-                    Transform::builder().position(-0.5, -0.5).build(), // currently does NOTHING
-                )],
-                cursor_position: PhysicalPosition::default(),
-                clear_color: wgpu::Color {
-                    g: 0.25,
-                    r: 0.25,
-                    b: 0.25,
-                    a: 1.0,
-                },
-                timer: Instant::now(),
-            },
-            rendering_active: false,
+            state,
+            scene,
+            cull_stats: CullStats::default(),
+            graphics_config,
         }
     }
 }
-fn create_window(event_loop: &ActiveEventLoop) -> anyhow::Result<Arc<Window>> {
+fn create_window(event_loop: &ActiveEventLoop, transparent: bool) -> anyhow::Result<Arc<Window>> {
     Ok(Arc::new(
         event_loop.create_window(
             WindowAttributes::default()
@@ -66,15 +72,40 @@ fn create_window(event_loop: &ActiveEventLoop) -> anyhow::Result<Arc<Window>> {
                     width: 1280,
                     height: 720,
                 })
-                .with_resizable(true),
+                .with_resizable(true)
+                .with_transparent(transparent),
         )?,
     ))
 }
+
+/// Key help at the top of the window, drawn with the font at `UNNAMED_ENGINE_FONT` if set.
+fn add_help_text(graphics_context: &mut GraphicsContext, state: &mut State) -> anyhow::Result<()> {
+    let Some(path) = std::env::var_os("UNNAMED_ENGINE_FONT") else {
+        return Ok(());
+    };
+    let font = graphics_context.load_font(std::fs::read(path)?)?;
+
+    state.add_object(
+        RenderObject::text(
+            Text::new(HELP, font, 0.06)
+                .with_align(TextAlign::Center)
+                .with_max_width(1.6)
+                .with_rendering(TextRendering::Msdf)
+                .with_shadow(Shadow::new([0.004, -0.004], Color::BLACK).with_softness(0.004)),
+            Some("Help"),
+            Transform::builder().position(0.0, 0.95).build(),
+        )
+        .with_lit(false),
+    );
+
+    Ok(())
+}
+
 impl ApplicationHandler for App {
     fn resumed(&mut self, event_loop: &ActiveEventLoop) {
         log::debug!("Application resumed");
 
-        let window = match create_window(event_loop) {
+        let window = match create_window(event_loop, self.graphics_config.transparent) {
             Ok(window) => window,
             Err(err) => {
                 log::error!("Unable to create window: {err}");
@@ -83,8 +114,16 @@ impl ApplicationHandler for App {
         };
 
         if self.graphics_context.is_none() {
-            match GraphicsContext::setup(&window, &mut self.state) {
-                Ok(graphics_context) => self.graphics_context = Some(graphics_context),
+            match GraphicsContext::setup(&window, &mut self.state, self.graphics_config.clone()) {
+                Ok(mut graphics_context) => {
+                    if let Err(err) = self.scene.setup(&mut graphics_context, &mut self.state) {
+                        log::error!("Unable to set up the scene: {err}");
+                    }
+                    if let Err(err) = add_help_text(&mut graphics_context, &mut self.state) {
+                        log::warn!("Unable to add the help text: {err}");
+                    }
+                    self.graphics_context = Some(graphics_context);
+                }
                 Err(err) => log::error!("Unable to set up graphics: {err}"),
             }
         }
@@ -99,6 +138,13 @@ impl ApplicationHandler for App {
         _window_id: WindowId,
         event: WindowEvent,
     ) {
+        if let Some(graphics_context) = &mut self.graphics_context
+            && self
+                .scene
+                .window_event(graphics_context, &mut self.state, &event)
+        {
+            return;
+        }
         match event {
             WindowEvent::CloseRequested => events::exit(event_loop),
             WindowEvent::CursorMoved { position, .. } => self.state.cursor_position = position,
@@ -110,15 +156,23 @@ impl ApplicationHandler for App {
                     self.state.cursor_position
                 );
 
-                if state == ElementState::Pressed {
-                    let surface_size = self.window.as_ref().unwrap().inner_size();
+                if state == ElementState::Pressed && button == MouseButton::Right {
+                    if let Some(id) = self.state.last_object_id() {
+                        self.state.despawn(id);
+                        log::debug!(
+                            "Despawned {id:?}, live GPU buffers: {}",
+                            graphics::live_buffer_count()
+                        );
+                    }
+                } else if state == ElementState::Pressed
+                    && let Some(graphics_context) = &self.graphics_context
+                {
+                    let [x, y] = graphics_context
+                        .screen_to_world(self.state.cursor_position, &self.state.camera);
                     self.state.add_object(RenderObject::new(
-                        primitives::triangle(0.1, wgpu::Color::BLACK),
+                        primitives::triangle(0.1, Color::BLACK),
                         Some("TestTriangle"),
-                        Transform::builder()
-                            .physical_position(self.state.cursor_position, surface_size)
-                            // .position(x, y)
-                            .build(),
+                        Transform::builder().position(x, y).build(),
                     ));
                 }
             }
@@ -134,20 +188,50 @@ impl ApplicationHandler for App {
                 event: key_event, ..
             } => {
                 let window = self.window.as_ref().unwrap();
-                events::handle_key_event(key_event, event_loop, window);
+                let graphics_context = self.graphics_context.as_mut().unwrap();
+                events::handle_key_event(key_event, event_loop, window, graphics_context);
             }
             WindowEvent::RedrawRequested => {
                 let window = self.window.as_ref().unwrap();
 
                 let graphics_context = self.graphics_context.as_mut().unwrap();
-                graphics_context.update_uniform(UniformKind::Time(TimeUniform::new(
-                    self.state.timer.elapsed().as_secs_f32(),
-                )));
-                if let Err(err) = graphics_context.render(&mut self.state) {
-                    log::error!("Unable to render: {err}");
+                let time = self.state.tick();
+                self.scene.update(graphics_context, &mut self.state, time);
+                // whatever the scene left
+                for (id, event) in self.state.drain_animation_events() {
+                    log::trace!("Animation event {} of {id:?}", event.event);
+                }
+                graphics_context.update_uniform(UniformKind::Time(TimeUniform::new(time)));
+                match graphics_context.render(&mut self.state) {
+                    Ok(FrameStatus::Presented) => {
+                        let cull_stats = graphics_context.cull_stats();
+                        if cull_stats != self.cull_stats {
+                            log::debug!(
+                                "Drawing {} objects, culled {}",
+                                cull_stats.drawn,
+                                cull_stats.culled
+                            );
+                            self.cull_stats = cull_stats;
+                        }
+                    }
+                    Ok(FrameStatus::Skipped) => log::debug!("Frame skipped"),
+                    Ok(FrameStatus::DeviceRecreated) => {
+                        if let Err(err) = self
+                            .scene
+                            .device_recreated(graphics_context, &mut self.state)
+                        {
+                            log::error!("Unable to restore the scene on the new device: {err}");
+                        }
+                    }
+                    Err(err) => {
+                        log::error!("Unable to render, exiting: {err}");
+                        events::exit(event_loop);
+                        return;
+                    }
                 }
 
-                if self.rendering_active {
+                // a minimized window gets a Resized event when restored, which resumes redraws
+                if graphics_context.surface_status() != SurfaceStatus::Minimized {
                     window.request_redraw()
                 };
             }
@@ -163,21 +247,11 @@ impl ApplicationHandler for App {
                         size.height
                     );
                 }
-                match graphics_context.resize_surface(size.width, size.height) {
-                    Ok(_) => {
-                        self.rendering_active = true;
-
-                        graphics_context.update_uniform(UniformKind::Surface(
-                            SurfaceSizeUniform::new(size.width as f32, size.height as f32),
-                        ));
-                        log::debug!("Window resized");
-                    }
-                    Err(err) => {
-                        // TODO: be aware that rendering stops when we minimise the window
-                        self.rendering_active = false;
-                        log::warn!("{err}");
-                    }
-                };
+                graphics_context.resize_surface(size.width, size.height);
+                if graphics_context.surface_status() != SurfaceStatus::Minimized {
+                    self.window.as_ref().unwrap().request_redraw();
+                }
+                log::debug!("Window resized");
             }
             _ => {}
         }
@@ -7,7 +7,44 @@ use winit::{
     window::Window,
 };
 
-pub fn handle_key_event(key_event: KeyEvent, event_loop: &ActiveEventLoop, window: &Arc<Window>) {
+use crate::graphics::{
+    AntiAliasing, EffectParam, GraphicsContext, Lut3d, PostEffect, Resolution, ScaleMode,
+};
+
+/// Frame rate caps cycled through with `F`.
+const FPS_CAPS: &[Option<f64>] = &[None, Some(30.0), Some(60.0), Some(144.0)];
+
+/// Anti-aliasing modes cycled through with `A`.
+const ANTI_ALIASING_MODES: &[AntiAliasing] = &[
+    AntiAliasing::None,
+    AntiAliasing::Fxaa,
+    AntiAliasing::Msaa(2),
+    AntiAliasing::Msaa(4),
+    AntiAliasing::Msaa(8),
+];
+
+/// Virtual resolution every mode of `S` lays the world out for.
+const VIRTUAL_RESOLUTION: (u32, u32) = (640, 360);
+
+/// Coarser resolution for the pixel art mode, so its pixels are visibly chunky.
+const PIXEL_ART_RESOLUTION: (u32, u32) = (320, 180);
+
+/// Scale modes cycled through with `S`, `None` following the window.
+const SCALE_MODES: &[Option<ScaleMode>] = &[
+    None,
+    Some(ScaleMode::Expand),
+    Some(ScaleMode::Letterbox),
+    Some(ScaleMode::Stretch),
+    Some(ScaleMode::Integer),
+    Some(ScaleMode::PixelArt),
+];
+
+pub fn handle_key_event(
+    key_event: KeyEvent,
+    event_loop: &ActiveEventLoop,
+    window: &Arc<Window>,
+    graphics_context: &mut GraphicsContext,
+) {
     let KeyEvent {
         physical_key: PhysicalKey::Code(key_code),
         state,
@@ -22,6 +59,12 @@ pub fn handle_key_event(key_event: KeyEvent, event_loop: &ActiveEventLoop, windo
     match (key_code, state) {
         (KeyCode::KeyR, ElementState::Pressed) => request_redraw(window),
         (KeyCode::KeyT, ElementState::Pressed) => toggle_control_flow(event_loop),
+        (KeyCode::KeyV, ElementState::Pressed) => toggle_vsync(graphics_context),
+        (KeyCode::KeyP, ElementState::Pressed) => cycle_present_mode(graphics_context),
+        (KeyCode::KeyF, ElementState::Pressed) => cycle_fps_cap(graphics_context),
+        (KeyCode::KeyA, ElementState::Pressed) => cycle_anti_aliasing(graphics_context),
+        (KeyCode::KeyE, ElementState::Pressed) => cycle_post_effects(graphics_context),
+        (KeyCode::KeyS, ElementState::Pressed) => cycle_scale_mode(graphics_context),
         (KeyCode::Escape, ElementState::Pressed) | (KeyCode::KeyQ, ElementState::Pressed) => {
             exit(event_loop)
         }
@@ -45,6 +88,112 @@ fn toggle_control_flow(event_loop: &ActiveEventLoop) {
     log::info!("Control flow changed: {previous_flow:?} -> {new_flow:?}");
 }
 
+fn toggle_vsync(graphics_context: &mut GraphicsContext) {
+    graphics_context.set_vsync(!graphics_context.vsync());
+}
+
+fn cycle_present_mode(graphics_context: &mut GraphicsContext) {
+    let supported = graphics_context.supported_present_modes();
+    if supported.is_empty() {
+        return;
+    }
+    let next = supported
+        .iter()
+        .position(|mode| *mode == graphics_context.present_mode())
+        .map_or(0, |index| (index + 1) % supported.len());
+    graphics_context.set_present_mode(supported[next]);
+}
+
+fn cycle_fps_cap(graphics_context: &mut GraphicsContext) {
+    let next = FPS_CAPS
+        .iter()
+        .position(|cap| *cap == graphics_context.target_fps())
+        .map_or(0, |index| (index + 1) % FPS_CAPS.len());
+
+    graphics_context.set_target_fps(FPS_CAPS[next]);
+}
+
+/// Unsupported modes fall back to one already in the list, those are skipped.
+fn cycle_anti_aliasing(graphics_context: &mut GraphicsContext) {
+    let current = graphics_context.anti_aliasing();
+    let start = ANTI_ALIASING_MODES
+        .iter()
+        .position(|mode| *mode == current)
+        .unwrap_or(0);
+
+    for offset in 1..ANTI_ALIASING_MODES.len() {
+        let next = ANTI_ALIASING_MODES[(start + offset) % ANTI_ALIASING_MODES.len()];
+        if graphics_context.set_anti_aliasing(next) != current {
+            return;
+        }
+    }
+}
+
+fn cycle_scale_mode(graphics_context: &mut GraphicsContext) {
+    let current = graphics_context.resolution();
+    let start = SCALE_MODES
+        .iter()
+        .position(|mode| *mode == current.size.map(|_| current.mode))
+        .unwrap_or(0);
+    let resolution = match SCALE_MODES[(start + 1) % SCALE_MODES.len()] {
+        Some(ScaleMode::PixelArt) => {
+            Resolution::pixel_art(PIXEL_ART_RESOLUTION.0, PIXEL_ART_RESOLUTION.1)
+        }
+        Some(mode) => Resolution::fixed(VIRTUAL_RESOLUTION.0, VIRTUAL_RESOLUTION.1, mode),
+        None => Resolution::window(),
+    };
+    log::info!("Resolution: {resolution:?}");
+    graphics_context.set_resolution(resolution);
+}
+
+/// Post-processing presets cycled through with `E`.
+fn post_effect_presets() -> Vec<Vec<PostEffect>> {
+    let grayscale = PostEffect::custom(
+        "Grayscale",
+        "@fragment
+        fn fs_main(in: FullscreenOutput) -> @location(0) vec4f {
+            let color = sample_input(in.uv);
+            let luma = dot(color.rgb, vec3f(0.2126, 0.7152, 0.0722));
+            return vec4f(mix(color.rgb, vec3f(luma), param(0u)), color.a);
+        }",
+        vec![EffectParam::constant(0.5).oscillating(0.5, 0.25)],
+    );
+    let warm = Lut3d::from_fn(16, |[r, g, b]| [(r * 1.1).min(1.0), g, b * 0.85]);
+
+    vec![
+        vec![],
+        vec![PostEffect::Vignette {
+            intensity: EffectParam::constant(0.6).oscillating(0.2, 0.5),
+            radius: 0.75.into(),
+            smoothness: 0.45.into(),
+        }],
+        vec![PostEffect::bloom(), PostEffect::vignette()],
+        vec![PostEffect::chromatic_aberration(), PostEffect::crt()],
+        vec![PostEffect::color_grading(warm)],
+        vec![grayscale],
+    ]
+}
+
+fn cycle_post_effects(graphics_context: &mut GraphicsContext) {
+    let presets = post_effect_presets();
+    let current = effect_names(graphics_context.post_effects());
+    let next = presets
+        .iter()
+        .position(|preset| effect_names(preset) == current)
+        .map_or(0, |index| (index + 1) % presets.len());
+
+    let preset = presets[next].clone();
+    log::info!("Post effects: {:?}", effect_names(&preset));
+    *graphics_context.post_effects_mut() = preset;
+}
+
+fn effect_names(effects: &[PostEffect]) -> Vec<String> {
+    effects
+        .iter()
+        .map(|effect| effect.name().to_owned())
+        .collect()
+}
+
 fn request_redraw(window: &Arc<Window>) {
     log::info!("Manual redraw requested");
     window.request_redraw();
@@ -1,6 +1,8 @@
 mod app_struct;
 mod events;
+mod scene;
 mod state;
 
 pub use app_struct::App;
-pub use state::State;
+pub use scene::Scene;
+pub use state::{ObjectId, State};
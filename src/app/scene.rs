@@ -0,0 +1,46 @@
+use winit::event::WindowEvent;
+
+use crate::{app::State, graphics::GraphicsContext};
+
+/// What a game (or example) adds to the [`crate::app::App`] running it.
+pub trait Scene {
+    /// Called once the graphics are set up, to add objects and create GPU resources. An error
+    /// is logged, the app keeps running without the rest of the scene.
+    fn setup(
+        &mut self,
+        graphics_context: &mut GraphicsContext,
+        state: &mut State,
+    ) -> anyhow::Result<()>;
+
+    /// Called every frame before it's rendered, `time` being the engine clock returned by
+    /// [`State::tick`].
+    fn update(&mut self, _graphics_context: &mut GraphicsContext, _state: &mut State, _time: f32) {}
+
+    /// Called once the device was lost and rebuilt, to upload what the scene keeps on the GPU
+    /// again, see [`crate::graphics::FrameStatus::DeviceRecreated`]. An error is logged.
+    fn device_recreated(
+        &mut self,
+        _graphics_context: &mut GraphicsContext,
+        _state: &mut State,
+    ) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    /// Called with every window event before the app handles it. Returns whether the scene
+    /// handled `event`, which the app then ignores.
+    fn window_event(
+        &mut self,
+        _graphics_context: &mut GraphicsContext,
+        _state: &mut State,
+        _event: &WindowEvent,
+    ) -> bool {
+        false
+    }
+}
+
+/// Nothing beyond what the app shows by itself.
+impl Scene for () {
+    fn setup(&mut self, _: &mut GraphicsContext, _: &mut State) -> anyhow::Result<()> {
+        Ok(())
+    }
+}
@@ -1,23 +1,252 @@
-use std::time::Instant;
+use std::{collections::BTreeMap, time::Instant};
 
 use winit::dpi::PhysicalPosition;
 
-use crate::graphics::RenderObject;
+use crate::graphics::{
+    AnimationEvent, Camera, Color, Lighting, Mesh, MeshUsage, RenderData, RenderObject,
+    SharedGeometry, Tilemap, Transform,
+};
+
+/// Handle of an object added to [`State`], never reused within a session.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ObjectId(u64);
+
+/// Handle of a tilemap added to [`State`], never reused within a session.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct TilemapId(u64);
+
+struct TilemapEntry {
+    tilemap: Tilemap,
+    /// Object drawing each chunk, per layer.
+    chunk_objects: Vec<Vec<ObjectId>>,
+}
 
 pub struct State {
-    pub render_objects: Vec<RenderObject>,
+    // ids grow monotonically, so iterating the map keeps the order objects were added in
+    render_objects: BTreeMap<ObjectId, RenderObject>,
+    next_object_id: u64,
+    retired_render_data: Vec<RenderData>,
     pub cursor_position: PhysicalPosition<f64>,
-    pub clear_color: wgpu::Color,
+    pub clear_color: Color,
+    /// View of the window, render targets have their own.
+    pub camera: Camera,
+    /// Lights the objects drawn in the window, `None` shows them as they are.
+    pub lighting: Option<Lighting>,
     pub timer: Instant,
+    /// Seconds since `timer` started, as of the last [`State::tick`].
+    clock: f32,
+    animation_events: Vec<(ObjectId, AnimationEvent)>,
+    tilemaps: BTreeMap<TilemapId, TilemapEntry>,
+    next_tilemap_id: u64,
 }
 impl State {
-    pub fn ensure_render_data(&mut self, device: &wgpu::Device) {
-        for obj in &mut self.render_objects {
-            obj.ensure_render_data(device);
+    pub fn new(clear_color: Color) -> Self {
+        Self {
+            render_objects: BTreeMap::new(),
+            next_object_id: 0,
+            retired_render_data: Vec::new(),
+            cursor_position: PhysicalPosition::default(),
+            clear_color,
+            camera: Camera::default(),
+            lighting: None,
+            timer: Instant::now(),
+            clock: 0.0,
+            animation_events: Vec::new(),
+            tilemaps: BTreeMap::new(),
+            next_tilemap_id: 0,
+        }
+    }
+
+    /// Advances the engine clock to now, and every [`crate::graphics::Animator`], particle
+    /// system and animated tile with it, then rebuilds the edited tilemap chunks. Returns the
+    /// seconds since `timer` started.
+    pub fn tick(&mut self) -> f32 {
+        let now = self.timer.elapsed().as_secs_f32();
+        let delta = (now - self.clock).max(0.0);
+        self.clock = now;
+
+        for (id, obj) in &mut self.render_objects {
+            if let Some(particles) = &mut obj.particles {
+                particles.advance(delta);
+            }
+            let Some(animator) = &mut obj.animator else {
+                continue;
+            };
+            animator.advance(delta);
+            self.animation_events
+                .extend(animator.drain_events().map(|event| (*id, event)));
+            animator.apply(&mut obj.mesh, &mut obj.texture);
+        }
+
+        let mut tilemaps = std::mem::take(&mut self.tilemaps);
+        for entry in tilemaps.values_mut() {
+            entry.tilemap.advance(delta);
+            self.sync_tilemap(entry);
+        }
+        self.tilemaps = tilemaps;
+
+        now
+    }
+
+    /// Frame events of the animators since the last call, oldest first.
+    pub fn drain_animation_events(
+        &mut self,
+    ) -> impl Iterator<Item = (ObjectId, AnimationEvent)> + '_ {
+        self.animation_events.drain(..)
+    }
+
+    /// Objects whose mesh can't be uploaded are logged and left out of the frame, see
+    /// [`RenderObject::ensure_render_data`].
+    pub fn ensure_render_data(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        shared: &mut SharedGeometry,
+    ) {
+        for (id, obj) in &mut self.render_objects {
+            if let Err(err) = obj.ensure_render_data(device, queue, shared) {
+                log::error!("Unable to upload object {id:?}, skipping it: {err:#}");
+            }
+        }
+    }
+
+    pub fn add_object(&mut self, object: RenderObject) -> ObjectId {
+        let id = ObjectId(self.next_object_id);
+        self.next_object_id += 1;
+        self.render_objects.insert(id, object);
+        id
+    }
+
+    /// Takes the object out of the scene. Its GPU resources are released once the frames in
+    /// flight are done with them; adding the object back uploads it again.
+    pub fn remove_object(&mut self, id: ObjectId) -> Option<RenderObject> {
+        let mut object = self.render_objects.remove(&id)?;
+        self.retired_render_data.extend(object.take_render_data());
+        Some(object)
+    }
+
+    /// Like [`State::remove_object`], but drops the object. Returns `false` for unknown ids.
+    pub fn despawn(&mut self, id: ObjectId) -> bool {
+        self.remove_object(id).is_some()
+    }
+
+    #[must_use]
+    pub fn object(&self, id: ObjectId) -> Option<&RenderObject> {
+        self.render_objects.get(&id)
+    }
+
+    #[must_use]
+    pub fn object_mut(&mut self, id: ObjectId) -> Option<&mut RenderObject> {
+        self.render_objects.get_mut(&id)
+    }
+
+    pub fn objects(&self) -> impl DoubleEndedIterator<Item = (ObjectId, &RenderObject)> {
+        self.render_objects.iter().map(|(id, obj)| (*id, obj))
+    }
+
+    pub fn objects_mut(
+        &mut self,
+    ) -> impl DoubleEndedIterator<Item = (ObjectId, &mut RenderObject)> {
+        self.render_objects.iter_mut().map(|(id, obj)| (*id, obj))
+    }
+
+    #[must_use]
+    pub fn last_object_id(&self) -> Option<ObjectId> {
+        self.render_objects.keys().next_back().copied()
+    }
+
+    #[must_use]
+    pub fn object_count(&self) -> usize {
+        self.render_objects.len()
+    }
+
+    /// Adds one object per chunk of every layer, drawn on top of the objects added before.
+    /// Layers added to the map later get their objects on top of everything else.
+    pub fn add_tilemap(&mut self, tilemap: Tilemap) -> TilemapId {
+        let id = TilemapId(self.next_tilemap_id);
+        self.next_tilemap_id += 1;
+        let mut entry = TilemapEntry {
+            tilemap,
+            chunk_objects: Vec::new(),
+        };
+        self.sync_tilemap(&mut entry);
+        self.tilemaps.insert(id, entry);
+        id
+    }
+
+    /// Takes the tilemap and its chunk objects out of the scene.
+    pub fn remove_tilemap(&mut self, id: TilemapId) -> Option<Tilemap> {
+        let entry = self.tilemaps.remove(&id)?;
+        for object in entry.chunk_objects.into_iter().flatten() {
+            self.remove_object(object);
+        }
+        Some(entry.tilemap)
+    }
+
+    #[must_use]
+    pub fn tilemap(&self, id: TilemapId) -> Option<&Tilemap> {
+        self.tilemaps.get(&id).map(|entry| &entry.tilemap)
+    }
+
+    /// Edits show up after the next [`State::tick`].
+    #[must_use]
+    pub fn tilemap_mut(&mut self, id: TilemapId) -> Option<&mut Tilemap> {
+        self.tilemaps.get_mut(&id).map(|entry| &mut entry.tilemap)
+    }
+
+    fn sync_tilemap(&mut self, entry: &mut TilemapEntry) {
+        let tilemap = &mut entry.tilemap;
+        let mut positions_dirty = tilemap.take_positions_dirty();
+        while entry.chunk_objects.len() < tilemap.layers().len() {
+            let layer = entry.chunk_objects.len();
+            let (columns, rows) = tilemap.chunk_grid(layer);
+            let name = format!("{} chunk", tilemap.layers()[layer].name);
+            let objects = (0..columns * rows)
+                .map(|_| {
+                    self.add_object(RenderObject::new(
+                        Mesh::new(Vec::new(), Vec::new()).with_usage(MeshUsage::Dynamic),
+                        Some(&name),
+                        Transform::new(),
+                    ))
+                })
+                .collect();
+            entry.chunk_objects.push(objects);
+            positions_dirty = true;
+        }
+
+        for (layer, chunk) in tilemap.take_dirty_chunks() {
+            let (vertices, indices, texture) = tilemap.build_chunk(layer, chunk);
+            if let Some(obj) = self
+                .render_objects
+                .get_mut(&entry.chunk_objects[layer][chunk])
+            {
+                obj.mesh.set_vertices(vertices);
+                obj.mesh.set_indices(indices);
+                obj.texture = texture;
+            }
+        }
+        if positions_dirty {
+            for (layer, objects) in entry.chunk_objects.iter().enumerate() {
+                for (chunk, id) in objects.iter().enumerate() {
+                    if let Some(obj) = self.render_objects.get_mut(id) {
+                        obj.transform.position = tilemap.chunk_position(layer, chunk);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Drops every object's GPU resources without destroying them, used when the device that
+    /// created them is gone.
+    pub fn forget_render_data(&mut self) {
+        for obj in self.render_objects.values_mut() {
+            obj.take_render_data();
         }
+        self.retired_render_data.clear();
     }
 
-    pub fn add_object(&mut self, object: RenderObject) {
-        self.render_objects.push(object);
+    /// GPU resources of removed objects, handed over to the renderer for deferred destruction.
+    pub fn drain_retired_render_data(&mut self) -> impl Iterator<Item = RenderData> + '_ {
+        self.retired_render_data.drain(..)
     }
 }
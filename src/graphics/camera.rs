@@ -0,0 +1,151 @@
+use bytemuck::{Pod, Zeroable};
+use wgpu::*;
+
+use crate::{graphics::renderer::buffer::TrackedBuffer, math::rotated_2d};
+
+/// View onto the scene, in the same units as [`crate::graphics::Transform::position`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Camera {
+    /// Point shown at the center of the view.
+    pub position: [f32; 2],
+    pub rotation: f32,
+    /// `2.0` shows everything twice as big.
+    pub zoom: f32,
+}
+impl Default for Camera {
+    fn default() -> Self {
+        Self {
+            position: [0.0, 0.0],
+            rotation: 0.0,
+            zoom: 1.0,
+        }
+    }
+}
+impl Camera {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_position(mut self, x: f32, y: f32) -> Self {
+        self.position = [x, y];
+        self
+    }
+
+    pub fn with_rotation(mut self, rad: f32) -> Self {
+        self.rotation = rad;
+        self
+    }
+
+    pub fn with_zoom(mut self, zoom: f32) -> Self {
+        self.zoom = zoom;
+        self
+    }
+
+    /// World point relative to the view, turned and zoomed like `to_view` in `basic.wgsl` does
+    /// before dividing by the extent.
+    #[must_use]
+    pub fn world_to_view(&self, [x, y]: [f32; 2]) -> [f32; 2] {
+        let relative = [x - self.position[0], y - self.position[1]];
+        rotated_2d(relative, -self.rotation).map(|side| side as f32 * self.zoom)
+    }
+
+    /// World point at `view`, see [`Camera::world_to_view`].
+    #[must_use]
+    pub fn view_to_world(&self, view: [f32; 2]) -> [f32; 2] {
+        let [x, y] = rotated_2d(view.map(|side| side / self.zoom), self.rotation);
+        [x as f32 + self.position[0], y as f32 + self.position[1]]
+    }
+
+    pub fn bind_group_layout(device: &Device) -> BindGroupLayout {
+        device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+            label: Some("Camera Uniform Bind Group Layout"),
+            entries: &[BindGroupLayoutEntry {
+                binding: 0,
+                visibility: ShaderStages::VERTEX,
+                ty: BindingType::Buffer {
+                    ty: BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: std::num::NonZeroU64::new(size_of::<CameraUniform>() as u64),
+                },
+                count: None,
+            }],
+        })
+    }
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, PartialEq, Pod, Zeroable)]
+pub struct CameraUniform {
+    /// Half the view in world units, see [`crate::graphics::Viewport::extent`].
+    extent: [f32; 2],
+    position: [f32; 2],
+    rotation: f32,
+    zoom: f32,
+    /// Half the size of the view in pixels while objects snap to them, zero otherwise.
+    pixels: [f32; 2],
+}
+impl CameraUniform {
+    pub fn new(camera: &Camera, extent: [f32; 2]) -> Self {
+        Self {
+            extent,
+            position: camera.position,
+            rotation: camera.rotation,
+            zoom: camera.zoom,
+            pixels: [0.0; 2],
+        }
+    }
+
+    /// Snaps the position of every object to the pixels of a `width` x `height` view.
+    #[must_use]
+    pub fn snapped(mut self, (width, height): (u32, u32)) -> Self {
+        self.pixels = [width as f32 * 0.5, height as f32 * 0.5];
+        self
+    }
+}
+
+/// GPU side of a [`Camera`], one per view being rendered.
+pub struct CameraBinding {
+    buffer: TrackedBuffer,
+    bind_group: BindGroup,
+    uploaded: Option<CameraUniform>,
+}
+impl CameraBinding {
+    pub fn new(device: &Device, label: &str) -> Self {
+        let buffer = TrackedBuffer::new(
+            device,
+            &BufferDescriptor {
+                label: Some(&format!("{label} Camera Buffer")),
+                size: size_of::<CameraUniform>() as u64,
+                usage: BufferUsages::UNIFORM | BufferUsages::COPY_DST,
+                mapped_at_creation: false,
+            },
+        );
+        let bind_group = device.create_bind_group(&BindGroupDescriptor {
+            label: Some(&format!("{label} Camera Bind Group")),
+            layout: &Camera::bind_group_layout(device),
+            entries: &[BindGroupEntry {
+                binding: 0,
+                resource: buffer.as_entire_binding(),
+            }],
+        });
+
+        Self {
+            buffer,
+            bind_group,
+            uploaded: None,
+        }
+    }
+
+    /// Writes the uniform if it differs from the last upload.
+    pub fn update(&mut self, queue: &Queue, uniform: CameraUniform) {
+        if self.uploaded != Some(uniform) {
+            queue.write_buffer(&self.buffer, 0, bytemuck::bytes_of(&uniform));
+            self.uploaded = Some(uniform);
+        }
+    }
+
+    #[must_use]
+    pub fn bind_group(&self) -> &BindGroup {
+        &self.bind_group
+    }
+}
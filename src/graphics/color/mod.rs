@@ -0,0 +1,315 @@
+mod named;
+
+use std::str::FromStr;
+
+use bytemuck::{Pod, Zeroable};
+
+/// RGBA color stored as linear, straight (non-premultiplied) alpha.
+///
+/// The surface is sRGB, so linear values are encoded by the hardware on write. Colors picked
+/// by designers (hex codes, CSS names, color pickers) are sRGB and should go through the
+/// `srgb*`, `hex`, `hsv`/`hsl` or `named` constructors.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Pod, Zeroable)]
+pub struct Color {
+    pub r: f32,
+    pub g: f32,
+    pub b: f32,
+    pub a: f32,
+}
+impl Color {
+    pub const TRANSPARENT: Self = Self::linear_rgba(0.0, 0.0, 0.0, 0.0);
+    pub const BLACK: Self = Self::linear_rgb(0.0, 0.0, 0.0);
+    pub const WHITE: Self = Self::linear_rgb(1.0, 1.0, 1.0);
+    pub const RED: Self = Self::linear_rgb(1.0, 0.0, 0.0);
+    pub const GREEN: Self = Self::linear_rgb(0.0, 1.0, 0.0);
+    pub const BLUE: Self = Self::linear_rgb(0.0, 0.0, 1.0);
+    pub const YELLOW: Self = Self::linear_rgb(1.0, 1.0, 0.0);
+    pub const CYAN: Self = Self::linear_rgb(0.0, 1.0, 1.0);
+    pub const MAGENTA: Self = Self::linear_rgb(1.0, 0.0, 1.0);
+
+    #[must_use]
+    pub const fn linear_rgb(r: f32, g: f32, b: f32) -> Self {
+        Self::linear_rgba(r, g, b, 1.0)
+    }
+
+    #[must_use]
+    pub const fn linear_rgba(r: f32, g: f32, b: f32, a: f32) -> Self {
+        Self { r, g, b, a }
+    }
+
+    #[must_use]
+    pub fn srgb(r: f32, g: f32, b: f32) -> Self {
+        Self::srgba(r, g, b, 1.0)
+    }
+
+    /// Alpha is never gamma encoded, so `a` is taken as is.
+    #[must_use]
+    pub fn srgba(r: f32, g: f32, b: f32, a: f32) -> Self {
+        Self {
+            r: srgb_to_linear(r),
+            g: srgb_to_linear(g),
+            b: srgb_to_linear(b),
+            a,
+        }
+    }
+
+    #[must_use]
+    pub fn srgb_u8(r: u8, g: u8, b: u8) -> Self {
+        Self::srgba_u8(r, g, b, u8::MAX)
+    }
+
+    #[must_use]
+    pub fn srgba_u8(r: u8, g: u8, b: u8, a: u8) -> Self {
+        Self::srgba(
+            f32::from(r) / 255.0,
+            f32::from(g) / 255.0,
+            f32::from(b) / 255.0,
+            f32::from(a) / 255.0,
+        )
+    }
+
+    /// `hue` in degrees, `saturation` and `value` in `0.0..=1.0`, evaluated in sRGB space.
+    #[must_use]
+    pub fn hsv(hue: f32, saturation: f32, value: f32) -> Self {
+        Self::hsva(hue, saturation, value, 1.0)
+    }
+
+    #[must_use]
+    pub fn hsva(hue: f32, saturation: f32, value: f32, alpha: f32) -> Self {
+        let chroma = value * saturation;
+        let [r, g, b] = hue_to_rgb(hue, chroma, value - chroma);
+        Self::srgba(r, g, b, alpha)
+    }
+
+    /// `hue` in degrees, `saturation` and `lightness` in `0.0..=1.0`, evaluated in sRGB space.
+    #[must_use]
+    pub fn hsl(hue: f32, saturation: f32, lightness: f32) -> Self {
+        Self::hsla(hue, saturation, lightness, 1.0)
+    }
+
+    #[must_use]
+    pub fn hsla(hue: f32, saturation: f32, lightness: f32, alpha: f32) -> Self {
+        let chroma = (1.0 - (2.0 * lightness - 1.0).abs()) * saturation;
+        let [r, g, b] = hue_to_rgb(hue, chroma, lightness - chroma * 0.5);
+        Self::srgba(r, g, b, alpha)
+    }
+
+    /// Parses `#rgb`, `#rgba`, `#rrggbb` and `#rrggbbaa`; the leading `#` is optional.
+    pub fn hex(hex: &str) -> anyhow::Result<Self> {
+        let digits = hex.trim().trim_start_matches('#');
+        // from_str_radix would take a leading `+`
+        if let Some(char) = digits.chars().find(|char| !char.is_ascii_hexdigit()) {
+            anyhow::bail!("Invalid hex color {hex:?}: {char:?} is not a hex digit");
+        }
+        let parse = |digits: &str| {
+            u8::from_str_radix(digits, 16)
+                .map_err(|err| anyhow::anyhow!("Invalid hex color {hex:?}: {err}"))
+        };
+        let short = |index: usize| parse(&digits[index..=index]).map(|value| value * 17);
+        let long = |index: usize| parse(&digits[index * 2..index * 2 + 2]);
+
+        let [r, g, b, a] = match digits.len() {
+            3 => [short(0)?, short(1)?, short(2)?, u8::MAX],
+            4 => [short(0)?, short(1)?, short(2)?, short(3)?],
+            6 => [long(0)?, long(1)?, long(2)?, u8::MAX],
+            8 => [long(0)?, long(1)?, long(2)?, long(3)?],
+            len => {
+                anyhow::bail!("Invalid hex color {hex:?}: expected 3, 4, 6 or 8 digits, got {len}")
+            }
+        };
+
+        Ok(Self::srgba_u8(r, g, b, a))
+    }
+
+    /// Looks up a CSS named color (case insensitive), e.g. `"cornflowerblue"`.
+    #[must_use]
+    pub fn named(name: &str) -> Option<Self> {
+        if name.trim().eq_ignore_ascii_case("transparent") {
+            return Some(Self::TRANSPARENT);
+        }
+        named::lookup(name).map(|rgb| {
+            let [_, r, g, b] = rgb.to_be_bytes();
+            Self::srgb_u8(r, g, b)
+        })
+    }
+
+    #[must_use]
+    pub fn with_alpha(self, a: f32) -> Self {
+        Self { a, ..self }
+    }
+
+    /// Interpolates in linear space, which is what blending on the GPU does as well.
+    #[must_use]
+    pub fn lerp(self, other: Self, t: f32) -> Self {
+        let mix = |from: f32, to: f32| from + (to - from) * t;
+        Self {
+            r: mix(self.r, other.r),
+            g: mix(self.g, other.g),
+            b: mix(self.b, other.b),
+            a: mix(self.a, other.a),
+        }
+    }
+
+    #[must_use]
+    pub fn to_srgba(self) -> [f32; 4] {
+        [
+            linear_to_srgb(self.r),
+            linear_to_srgb(self.g),
+            linear_to_srgb(self.b),
+            self.a,
+        ]
+    }
+
+    #[must_use]
+    pub fn to_srgba_u8(self) -> [u8; 4] {
+        self.to_srgba()
+            .map(|channel| (channel.clamp(0.0, 1.0) * 255.0).round() as u8)
+    }
+
+    #[must_use]
+    pub fn to_hex(self) -> String {
+        let [r, g, b, a] = self.to_srgba_u8();
+        if a == u8::MAX {
+            format!("#{r:02x}{g:02x}{b:02x}")
+        } else {
+            format!("#{r:02x}{g:02x}{b:02x}{a:02x}")
+        }
+    }
+
+    /// Returns `[hue, saturation, value]`, hue in degrees.
+    #[must_use]
+    pub fn to_hsv(self) -> [f32; 3] {
+        let [r, g, b, _] = self.to_srgba();
+        let max = r.max(g).max(b);
+        let chroma = max - r.min(g).min(b);
+        let saturation = if max > 0.0 { chroma / max } else { 0.0 };
+
+        [rgb_to_hue(r, g, b, max, chroma), saturation, max]
+    }
+
+    /// Returns `[hue, saturation, lightness]`, hue in degrees.
+    #[must_use]
+    pub fn to_hsl(self) -> [f32; 3] {
+        let [r, g, b, _] = self.to_srgba();
+        let max = r.max(g).max(b);
+        let min = r.min(g).min(b);
+        let chroma = max - min;
+        let lightness = (max + min) * 0.5;
+        let saturation = if lightness > 0.0 && lightness < 1.0 {
+            chroma / (1.0 - (2.0 * lightness - 1.0).abs())
+        } else {
+            0.0
+        };
+
+        [rgb_to_hue(r, g, b, max, chroma), saturation, lightness]
+    }
+}
+impl FromStr for Color {
+    type Err = anyhow::Error;
+
+    /// Accepts anything [`Color::hex`] or [`Color::named`] does.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.trim().starts_with('#') {
+            return Self::hex(s);
+        }
+        Self::named(s)
+            .map_or_else(|| Self::hex(s), Ok)
+            .map_err(|_| anyhow::anyhow!("Unknown color {s:?}"))
+    }
+}
+impl From<Color> for wgpu::Color {
+    fn from(color: Color) -> Self {
+        Self {
+            r: f64::from(color.r),
+            g: f64::from(color.g),
+            b: f64::from(color.b),
+            a: f64::from(color.a),
+        }
+    }
+}
+impl From<Color> for [f32; 4] {
+    fn from(color: Color) -> Self {
+        [color.r, color.g, color.b, color.a]
+    }
+}
+
+#[must_use]
+pub fn srgb_to_linear(channel: f32) -> f32 {
+    if channel <= 0.04045 {
+        channel / 12.92
+    } else {
+        ((channel + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+#[must_use]
+pub fn linear_to_srgb(channel: f32) -> f32 {
+    if channel <= 0.003_130_8 {
+        channel * 12.92
+    } else {
+        1.055 * channel.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+fn hue_to_rgb(hue: f32, chroma: f32, offset: f32) -> [f32; 3] {
+    let sector = hue.rem_euclid(360.0) / 60.0;
+    let x = chroma * (1.0 - (sector % 2.0 - 1.0).abs());
+    let [r, g, b] = match sector as u32 {
+        0 => [chroma, x, 0.0],
+        1 => [x, chroma, 0.0],
+        2 => [0.0, chroma, x],
+        3 => [0.0, x, chroma],
+        4 => [x, 0.0, chroma],
+        _ => [chroma, 0.0, x],
+    };
+
+    [r + offset, g + offset, b + offset]
+}
+
+fn rgb_to_hue(r: f32, g: f32, b: f32, max: f32, chroma: f32) -> f32 {
+    if chroma == 0.0 {
+        return 0.0;
+    }
+    let sector = if max == r {
+        ((g - b) / chroma).rem_euclid(6.0)
+    } else if max == g {
+        (b - r) / chroma + 2.0
+    } else {
+        (r - g) / chroma + 4.0
+    };
+
+    sector * 60.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hex_parses_every_length() {
+        assert_eq!(
+            Color::hex("#fff").unwrap(),
+            Color::srgba_u8(255, 255, 255, 255)
+        );
+        assert_eq!(Color::hex("0f08").unwrap(), Color::srgba_u8(0, 255, 0, 136));
+        assert_eq!(
+            Color::hex(" #1a2B3c ").unwrap(),
+            Color::srgba_u8(26, 43, 60, 255)
+        );
+        assert_eq!(
+            Color::hex("1a2b3c80").unwrap(),
+            Color::srgba_u8(26, 43, 60, 128)
+        );
+    }
+
+    #[test]
+    fn hex_rejects_signs_and_other_characters() {
+        assert!(Color::hex("#+f+f+f").is_err());
+        assert!(Color::hex("+ff").is_err());
+        assert!(Color::hex("-fff").is_err());
+        assert!(Color::hex("#ggg").is_err());
+        assert!(Color::hex("#ffé").is_err());
+        assert!(Color::hex("#ff").is_err());
+    }
+}
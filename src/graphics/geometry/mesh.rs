@@ -1,7 +1,202 @@
+use std::{borrow::Cow, collections::BTreeMap};
+
+use wgpu::IndexFormat;
+
 use crate::graphics::geometry::vertex::Vertex;
 
+/// How often the mesh data is expected to change, which decides how its GPU buffers are managed.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum MeshUsage {
+    /// Uploaded once; an edit reallocates the buffers.
+    #[default]
+    Static,
+    /// Edited occasionally; edits are written in place and buffers grow when needed.
+    Dynamic,
+    /// Rebuilt every frame; buffers keep spare capacity and are uploaded on every frame.
+    Streaming,
+}
+
 #[derive(Clone)]
 pub struct Mesh {
-    pub vertices: Vec<Vertex>,
-    pub indices: Vec<u16>,
+    vertices: Vec<Vertex>,
+    indices: Vec<u32>,
+    usage: MeshUsage,
+    vertices_revision: u64,
+    indices_revision: u64,
+}
+impl Mesh {
+    pub fn new(vertices: Vec<Vertex>, indices: Vec<u32>) -> Self {
+        Self {
+            vertices,
+            indices,
+            usage: MeshUsage::default(),
+            vertices_revision: 0,
+            indices_revision: 0,
+        }
+    }
+
+    pub fn with_usage(mut self, usage: MeshUsage) -> Self {
+        self.usage = usage;
+        self
+    }
+
+    #[must_use]
+    pub fn usage(&self) -> MeshUsage {
+        self.usage
+    }
+
+    pub fn set_usage(&mut self, usage: MeshUsage) {
+        self.usage = usage;
+    }
+
+    #[must_use]
+    pub fn vertices(&self) -> &[Vertex] {
+        &self.vertices
+    }
+
+    #[must_use]
+    pub fn indices(&self) -> &[u32] {
+        &self.indices
+    }
+
+    /// Marks the vertices as changed, they are re-uploaded before the next draw.
+    pub fn vertices_mut(&mut self) -> &mut Vec<Vertex> {
+        self.vertices_revision += 1;
+        &mut self.vertices
+    }
+
+    /// Marks the indices as changed, they are re-uploaded before the next draw.
+    pub fn indices_mut(&mut self) -> &mut Vec<u32> {
+        self.indices_revision += 1;
+        &mut self.indices
+    }
+
+    pub fn set_vertices(&mut self, vertices: Vec<Vertex>) {
+        *self.vertices_mut() = vertices;
+    }
+
+    pub fn set_indices(&mut self, indices: Vec<u32>) {
+        *self.indices_mut() = indices;
+    }
+
+    #[must_use]
+    pub fn vertices_revision(&self) -> u64 {
+        self.vertices_revision
+    }
+
+    #[must_use]
+    pub fn indices_revision(&self) -> u64 {
+        self.indices_revision
+    }
+
+    /// Smallest box around the vertices as `[min, max]`, `None` without vertices.
+    #[must_use]
+    pub fn bounds(&self) -> Option<[[f32; 2]; 2]> {
+        let mut positions = self.vertices.iter().map(Vertex::position);
+        let first = positions.next()?;
+        Some(positions.fold([first, first], |[min, max], [x, y]| {
+            [
+                [min[0].min(x), min[1].min(y)],
+                [max[0].max(x), max[1].max(y)],
+            ]
+        }))
+    }
+
+    /// Edges used by a single triangle, e.g. the outline of an occluder. Each runs
+    /// counterclockwise around the mesh, the outside is on its right. Triangles meet where
+    /// their vertices have equal positions, shared indices aren't needed.
+    #[must_use]
+    pub fn outline(&self) -> Vec<[[f32; 2]; 2]> {
+        let key = |position: [f32; 2]| position.map(f32::to_bits);
+        let mut edges = BTreeMap::new();
+        for triangle in self.indices.chunks_exact(3) {
+            let corner = |index: u32| self.vertices.get(index as usize).map(Vertex::position);
+            let (Some(a), Some(b), Some(c)) = (
+                corner(triangle[0]),
+                corner(triangle[1]),
+                corner(triangle[2]),
+            ) else {
+                continue;
+            };
+            let area = (b[0] - a[0]) * (c[1] - a[1]) - (b[1] - a[1]) * (c[0] - a[0]);
+            if area == 0.0 {
+                continue;
+            }
+            let corners = if area > 0.0 { [a, b, c] } else { [a, c, b] };
+            for side in 0..3 {
+                let (from, to) = (corners[side], corners[(side + 1) % 3]);
+                let ends = if key(from) < key(to) {
+                    (key(from), key(to))
+                } else {
+                    (key(to), key(from))
+                };
+                edges.entry(ends).or_insert((0_u32, [from, to])).0 += 1;
+            }
+        }
+
+        edges
+            .into_values()
+            .filter(|(count, _)| *count == 1)
+            .map(|(_, edge)| edge)
+            .collect()
+    }
+
+    /// Narrowest index width able to address every vertex of the mesh.
+    #[must_use]
+    pub fn index_format(&self) -> IndexFormat {
+        if self.vertices.len() <= u16::MAX as usize {
+            IndexFormat::Uint16
+        } else {
+            IndexFormat::Uint32
+        }
+    }
+
+    /// Validates the indices and packs them using [`Mesh::index_format`].
+    pub fn index_data(&self) -> anyhow::Result<IndexData<'_>> {
+        if self.vertices.len() > u32::MAX as usize {
+            anyhow::bail!(
+                "Mesh has {} vertices, more than a u32 index can address",
+                self.vertices.len()
+            );
+        }
+        if !self.indices.len().is_multiple_of(3) {
+            anyhow::bail!(
+                "Mesh index count {} is not a multiple of 3",
+                self.indices.len()
+            );
+        }
+        if let Some(index) = self
+            .indices
+            .iter()
+            .find(|&&index| index as usize >= self.vertices.len())
+        {
+            anyhow::bail!(
+                "Mesh index {index} is out of bounds for {} vertices",
+                self.vertices.len()
+            );
+        }
+
+        let format = self.index_format();
+        let bytes = match format {
+            IndexFormat::Uint16 => Cow::Owned(
+                self.indices
+                    .iter()
+                    .flat_map(|&index| (index as u16).to_ne_bytes())
+                    .collect(),
+            ),
+            IndexFormat::Uint32 => Cow::Borrowed(bytemuck::cast_slice(&self.indices)),
+        };
+
+        Ok(IndexData {
+            format,
+            count: self.indices.len() as u32,
+            bytes,
+        })
+    }
+}
+
+pub struct IndexData<'a> {
+    pub format: IndexFormat,
+    pub count: u32,
+    pub bytes: Cow<'a, [u8]>,
 }
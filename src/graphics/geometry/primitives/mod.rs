@@ -1,18 +1,60 @@
 mod ngon;
+mod nine_slice;
 
-use crate::graphics::Mesh;
+pub use nine_slice::{NineSlice, SliceFill};
 
-pub fn regular_polygon(vertices: u16, circumradius: f32, color: wgpu::Color) -> Mesh {
-    Mesh {
-        vertices: ngon::vertices(vertices, circumradius, color),
-        indices: ngon::indices(vertices),
-    }
+use crate::graphics::{Color, Mesh, Vertex};
+
+/// UVs map the circumscribed circle onto the texture, so textures are cropped, not squashed.
+pub fn regular_polygon(vertices: u32, circumradius: f32, color: Color) -> Mesh {
+    Mesh::new(
+        ngon::vertices(vertices, circumradius, color),
+        ngon::indices(vertices),
+    )
 }
 
-pub fn triangle(circumradius: f32, color: wgpu::Color) -> Mesh {
+pub fn triangle(circumradius: f32, color: Color) -> Mesh {
     regular_polygon(3, circumradius, color)
 }
 
-pub fn square(circumradius: f32, color: wgpu::Color) -> Mesh {
+pub fn square(circumradius: f32, color: Color) -> Mesh {
     regular_polygon(4, circumradius, color)
 }
+
+/// Axis aligned rectangle centered on the origin, with the whole texture mapped onto it.
+pub fn rectangle(width: f32, height: f32, color: Color) -> Mesh {
+    let (half_width, half_height) = (width * 0.5, height * 0.5);
+    Mesh::new(
+        vec![
+            Vertex::new([-half_width, half_height], color).with_uv([0.0, 0.0]),
+            Vertex::new([-half_width, -half_height], color).with_uv([0.0, 1.0]),
+            Vertex::new([half_width, -half_height], color).with_uv([1.0, 1.0]),
+            Vertex::new([half_width, half_height], color).with_uv([1.0, 0.0]),
+        ],
+        vec![0, 1, 2, 0, 2, 3],
+    )
+}
+
+/// Quad reaching `half_extent` from the origin, with the local positions as uvs for shapes
+/// evaluated in the fragment shader.
+pub fn sdf_quad(half_extent: [f32; 2], color: Color) -> Mesh {
+    let [x, y] = half_extent;
+    let corner = |position: [f32; 2]| Vertex::new(position, color).with_uv(position);
+    Mesh::new(
+        vec![
+            corner([-x, y]),
+            corner([-x, -y]),
+            corner([x, -y]),
+            corner([x, y]),
+        ],
+        vec![0, 1, 2, 0, 2, 3],
+    )
+}
+
+/// `width` x `height` rectangle centered on the origin showing `slice` of the texture, with
+/// unscaled corners and the edges and center stretched or tiled in between. Corners that
+/// don't fit are scaled down.
+pub fn nine_slice(slice: &NineSlice, width: f32, height: f32, color: Color) -> Mesh {
+    let (vertices, indices) = nine_slice::mesh(slice, (width, height), color);
+    Mesh::new(vertices, indices)
+}
@@ -1,21 +1,26 @@
 use std::f64::consts::TAU;
 
-use crate::{graphics::Vertex, math};
+use crate::{
+    graphics::{Color, Vertex},
+    math,
+};
 
-pub fn vertices(n: u16, circumradius: f32, color: wgpu::Color) -> Vec<Vertex> {
+pub fn vertices(n: u32, circumradius: f32, color: Color) -> Vec<Vertex> {
     let mut vertices = Vec::new();
 
     for vertex_nr in 0..n {
-        vertices.push(Vertex::new(
-            ngon_vertex_pos(vertex_nr, n, circumradius),
-            [color.r as f32, color.g as f32, color.b as f32],
-        ));
+        let [x, y] = ngon_vertex_pos(vertex_nr, n, circumradius);
+        let uv = [
+            0.5 + x / (2.0 * circumradius),
+            0.5 - y / (2.0 * circumradius),
+        ];
+        vertices.push(Vertex::new([x, y], color).with_uv(uv));
     }
 
     vertices
 }
 
-pub fn indices(n: u16) -> Vec<u16> {
+pub fn indices(n: u32) -> Vec<u32> {
     let mut indices = Vec::new();
 
     for i in 1..n - 1 {
@@ -27,7 +32,7 @@ pub fn indices(n: u16) -> Vec<u16> {
     indices
 }
 
-fn ngon_vertex_pos(vertex_nr: u16, n: u16, circumradius: f32) -> [f32; 2] {
+fn ngon_vertex_pos(vertex_nr: u32, n: u32, circumradius: f32) -> [f32; 2] {
     let v0 = [0.0, circumradius as f64];
     let rotated = math::rotated_2d(v0, vertex_nr as f64 * TAU / (n as f64));
     [rotated[0] as f32, rotated[1] as f32]
@@ -0,0 +1,153 @@
+use crate::graphics::{Color, Vertex, sprite::UvRect};
+
+/// More copies than this along an axis are stretched instead, so a tiny image can't turn a
+/// large panel into millions of quads.
+const MAX_TILES: u32 = 256;
+
+/// How the edges and the center of a [`NineSlice`] fill the space between the corners.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SliceFill {
+    #[default]
+    Stretch,
+    /// Repeats them at the corners' scale, cutting off the last copy.
+    Tile,
+}
+
+/// Image split into unscaled corners, edges and a center by four insets, see
+/// [`super::nine_slice()`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct NineSlice {
+    /// Size of the sliced image in pixels, the whole texture or `source` of it.
+    pub image_size: (u32, u32),
+    /// Part of the texture holding the image.
+    pub source: UvRect,
+    /// Pixels of the image at the left, top, right and bottom that aren't stretched.
+    pub insets: [u32; 4],
+    /// World units an image pixel covers in the corners.
+    pub units_per_pixel: f32,
+    pub fill: SliceFill,
+}
+impl NineSlice {
+    #[must_use]
+    pub fn new(image_size: (u32, u32), insets: [u32; 4], units_per_pixel: f32) -> Self {
+        Self {
+            image_size,
+            source: UvRect {
+                min: [0.0, 0.0],
+                max: [1.0, 1.0],
+            },
+            insets,
+            units_per_pixel,
+            fill: SliceFill::default(),
+        }
+    }
+
+    /// Slices `source` of the texture instead of all of it, e.g. a sprite of an atlas.
+    #[must_use]
+    pub fn with_source(mut self, source: UvRect) -> Self {
+        self.source = source;
+        self
+    }
+
+    #[must_use]
+    pub fn with_fill(mut self, fill: SliceFill) -> Self {
+        self.fill = fill;
+        self
+    }
+}
+
+/// Stretch of one axis: where it goes in world units and which part of the image it shows,
+/// `0.0` to `1.0`.
+#[derive(Clone, Copy, Debug)]
+struct Span {
+    position: [f32; 2],
+    uv: [f32; 2],
+}
+
+/// Spans covering `size` world units along one axis of an `image` pixels long image with
+/// `start` and `end` pixel borders.
+fn spans(slice: &NineSlice, size: f32, image: u32, [start, end]: [u32; 2]) -> Vec<Span> {
+    let image = image.max(1) as f32;
+    let start = (start as f32).min(image);
+    let end = (end as f32).min(image - start);
+    let mut start_size = start * slice.units_per_pixel;
+    let mut end_size = end * slice.units_per_pixel;
+    // borders that don't fit are shrunk alike
+    if start_size + end_size > size && start_size + end_size > 0.0 {
+        let fit = size / (start_size + end_size);
+        start_size *= fit;
+        end_size *= fit;
+    }
+    let middle_uv = [start / image, 1.0 - end / image];
+
+    let mut spans = vec![Span {
+        position: [0.0, start_size],
+        uv: [0.0, middle_uv[0]],
+    }];
+    let (middle_start, middle_end) = (start_size, size - end_size);
+    let tile_size = (image - start - end) * slice.units_per_pixel;
+    let tile_count = if tile_size > 0.0 {
+        ((middle_end - middle_start) / tile_size).ceil()
+    } else {
+        f32::INFINITY
+    };
+    if slice.fill == SliceFill::Tile && tile_count <= MAX_TILES as f32 {
+        let mut position = middle_start;
+        while position < middle_end {
+            let length = tile_size.min(middle_end - position);
+            spans.push(Span {
+                position: [position, position + length],
+                uv: [
+                    middle_uv[0],
+                    middle_uv[0] + (middle_uv[1] - middle_uv[0]) * length / tile_size,
+                ],
+            });
+            position += tile_size;
+        }
+    } else {
+        spans.push(Span {
+            position: [middle_start, middle_end],
+            uv: middle_uv,
+        });
+    }
+    spans.push(Span {
+        position: [size - end_size, size],
+        uv: [middle_uv[1], 1.0],
+    });
+
+    spans.retain(|span| span.position[1] > span.position[0]);
+    spans
+}
+
+pub fn mesh(
+    slice: &NineSlice,
+    (width, height): (f32, f32),
+    color: Color,
+) -> (Vec<Vertex>, Vec<u32>) {
+    let [left, top, right, bottom] = slice.insets;
+    let columns = spans(slice, width, slice.image_size.0, [left, right]);
+    let rows = spans(slice, height, slice.image_size.1, [top, bottom]);
+
+    let mut vertices = Vec::with_capacity(columns.len() * rows.len() * 4);
+    let mut indices = Vec::with_capacity(columns.len() * rows.len() * 6);
+    for row in &rows {
+        for column in &columns {
+            // rows go down from the top edge, centered on the origin
+            let corner = |x: usize, y: usize| {
+                Vertex::new(
+                    [
+                        column.position[x] - width * 0.5,
+                        height * 0.5 - row.position[y],
+                    ],
+                    color,
+                )
+                .with_uv(slice.source.map([column.uv[x], row.uv[y]]))
+            };
+            let first = vertices.len() as u32;
+            vertices.extend([corner(0, 0), corner(0, 1), corner(1, 1), corner(1, 0)]);
+            indices.extend([0, 1, 2, 0, 2, 3].map(|index| first + index));
+        }
+    }
+
+    (vertices, indices)
+}
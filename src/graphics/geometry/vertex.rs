@@ -1,21 +1,51 @@
 use wgpu::{BufferAddress, VertexAttribute, VertexBufferLayout, VertexStepMode, vertex_attr_array};
 
+use crate::graphics::Color;
+
 #[repr(C)]
 #[derive(Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
 pub struct Vertex {
     position: [f32; 2],
-    color: [f32; 3],
+    color: Color,
+    /// Texture coordinates, `[0.0, 0.0]` is the top left corner.
+    uv: [f32; 2],
 }
 impl Vertex {
-    pub fn new(position: [f32; 2], color: [f32; 3]) -> Self {
-        Self { position, color }
+    pub fn new(position: [f32; 2], color: Color) -> Self {
+        Self {
+            position,
+            color,
+            uv: [0.0, 0.0],
+        }
     }
 
     pub fn pos(position: [f32; 2]) -> Self {
-        Self::new(position, [1.0, 1.0, 1.0])
+        Self::new(position, Color::WHITE)
+    }
+
+    #[must_use]
+    pub fn with_uv(mut self, uv: [f32; 2]) -> Self {
+        self.uv = uv;
+        self
+    }
+
+    #[must_use]
+    pub fn position(&self) -> [f32; 2] {
+        self.position
+    }
+
+    #[must_use]
+    pub fn color(&self) -> Color {
+        self.color
+    }
+
+    #[must_use]
+    pub fn uv(&self) -> [f32; 2] {
+        self.uv
     }
 
-    const ATTRIBUTES: &[VertexAttribute] = &vertex_attr_array![0 => Float32x2, 1 => Float32x3];
+    const ATTRIBUTES: &[VertexAttribute] =
+        &vertex_attr_array![0 => Float32x2, 1 => Float32x4, 2 => Float32x2];
     #[must_use]
     pub const fn vertex_buffer_layout() -> VertexBufferLayout<'static> {
         VertexBufferLayout {
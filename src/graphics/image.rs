@@ -0,0 +1,158 @@
+use std::{
+    fs::File,
+    io::{BufReader, BufWriter},
+    path::Path,
+};
+
+use anyhow::Context;
+
+/// 8 bit sRGB pixels with straight alpha, rows from the top.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Image {
+    width: u32,
+    height: u32,
+    pixels: Vec<u8>,
+}
+impl Image {
+    /// Fully transparent image.
+    #[must_use]
+    pub fn new(width: u32, height: u32) -> Self {
+        Self {
+            width,
+            height,
+            pixels: vec![0; width as usize * height as usize * 4],
+        }
+    }
+
+    /// Decodes any PNG color type and bit depth into RGBA.
+    pub fn load_png(path: &Path) -> anyhow::Result<Self> {
+        let file =
+            File::open(path).with_context(|| format!("Unable to open {}", path.display()))?;
+        let mut decoder = png::Decoder::new(BufReader::new(file));
+        decoder.set_transformations(png::Transformations::normalize_to_color8());
+        let mut reader = decoder
+            .read_info()
+            .with_context(|| format!("Unable to read {}", path.display()))?;
+        let mut buffer = vec![0; reader.output_buffer_size()];
+        let info = reader
+            .next_frame(&mut buffer)
+            .with_context(|| format!("Unable to decode {}", path.display()))?;
+        buffer.truncate(info.buffer_size());
+
+        let pixels = match info.color_type {
+            png::ColorType::Rgba => buffer,
+            png::ColorType::Rgb => buffer
+                .chunks_exact(3)
+                .flat_map(|rgb| [rgb[0], rgb[1], rgb[2], u8::MAX])
+                .collect(),
+            png::ColorType::GrayscaleAlpha => buffer
+                .chunks_exact(2)
+                .flat_map(|gray| [gray[0], gray[0], gray[0], gray[1]])
+                .collect(),
+            png::ColorType::Grayscale => buffer
+                .iter()
+                .flat_map(|&gray| [gray, gray, gray, u8::MAX])
+                .collect(),
+            png::ColorType::Indexed => {
+                anyhow::bail!("{} wasn't expanded from its palette", path.display())
+            }
+        };
+
+        Ok(Self {
+            width: info.width,
+            height: info.height,
+            pixels,
+        })
+    }
+
+    pub fn save_png(&self, path: &Path) -> anyhow::Result<()> {
+        let file =
+            File::create(path).with_context(|| format!("Unable to create {}", path.display()))?;
+        let mut encoder = png::Encoder::new(BufWriter::new(file), self.width, self.height);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+        encoder
+            .write_header()
+            .and_then(|mut writer| writer.write_image_data(&self.pixels))
+            .with_context(|| format!("Unable to write {}", path.display()))
+    }
+
+    #[must_use]
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    #[must_use]
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    #[must_use]
+    pub fn pixels(&self) -> &[u8] {
+        &self.pixels
+    }
+
+    #[must_use]
+    pub fn into_pixels(self) -> Vec<u8> {
+        self.pixels
+    }
+
+    #[must_use]
+    pub fn pixel(&self, x: u32, y: u32) -> [u8; 4] {
+        let start = (y as usize * self.width as usize + x as usize) * 4;
+        let mut pixel = [0; 4];
+        pixel.copy_from_slice(&self.pixels[start..start + 4]);
+        pixel
+    }
+
+    pub fn set_pixel(&mut self, x: u32, y: u32, pixel: [u8; 4]) {
+        let start = (y as usize * self.width as usize + x as usize) * 4;
+        self.pixels[start..start + 4].copy_from_slice(&pixel);
+    }
+
+    /// Smallest `[x, y, width, height]` holding every pixel that isn't fully transparent,
+    /// `None` if there are none.
+    #[must_use]
+    pub fn opaque_bounds(&self) -> Option<[u32; 4]> {
+        let mut bounds: Option<[u32; 4]> = None;
+        for y in 0..self.height {
+            for x in 0..self.width {
+                if self.pixel(x, y)[3] == 0 {
+                    continue;
+                }
+                let [min_x, min_y, max_x, max_y] = bounds.get_or_insert([x, y, x, y]);
+                *min_x = (*min_x).min(x);
+                *min_y = (*min_y).min(y);
+                *max_x = (*max_x).max(x);
+                *max_y = (*max_y).max(y);
+            }
+        }
+        bounds.map(|[min_x, min_y, max_x, max_y]| {
+            [min_x, min_y, max_x - min_x + 1, max_y - min_y + 1]
+        })
+    }
+
+    /// Copies `[x, y, width, height]` of `source` to `to` in this image. Edge pixels are
+    /// repeated `extrude` more times outwards, so filtering at the edge doesn't pick up what's
+    /// next to it.
+    pub fn blit(
+        &mut self,
+        source: &Image,
+        [x, y, width, height]: [u32; 4],
+        to: (u32, u32),
+        extrude: u32,
+    ) {
+        let extrude = extrude as i64;
+        for dy in -extrude..height as i64 + extrude {
+            for dx in -extrude..width as i64 + extrude {
+                let (to_x, to_y) = (to.0 as i64 + dx, to.1 as i64 + dy);
+                if to_x < 0 || to_y < 0 || to_x >= self.width as i64 || to_y >= self.height as i64 {
+                    continue;
+                }
+                let from_x = x + dx.clamp(0, width as i64 - 1) as u32;
+                let from_y = y + dy.clamp(0, height as i64 - 1) as u32;
+                self.set_pixel(to_x as u32, to_y as u32, source.pixel(from_x, from_y));
+            }
+        }
+    }
+}
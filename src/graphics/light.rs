@@ -0,0 +1,117 @@
+use std::f32::consts::FRAC_PI_4;
+
+use crate::graphics::Color;
+
+/// What a [`Light`] illuminates besides the falloff by distance.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum LightKind {
+    /// Shines in every direction.
+    Point,
+    /// Shines in a cone around `direction`, fading out between the inner and the outer angle.
+    Spot {
+        /// Radians, `0.0` points along x.
+        direction: f32,
+        /// Half angle of the fully lit part of the cone, radians.
+        inner_angle: f32,
+        /// Half angle where the cone ends, radians.
+        outer_angle: f32,
+    },
+}
+
+/// Light source of [`Lighting`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Light {
+    /// Same units as [`crate::graphics::Transform::position`].
+    pub position: [f32; 2],
+    pub kind: LightKind,
+    pub color: Color,
+    /// Multiplies `color`, above `1.0` overexposes what's lit.
+    pub intensity: f32,
+    /// Distance the light reaches, in world units: a circle of this radius drawn at `position`
+    /// covers what's lit.
+    pub radius: f32,
+    /// Exponent of the fade towards `radius`, `1.0` fades linearly.
+    pub falloff: f32,
+    /// Angle between the ground and the direction the light comes from, radians. Only shades
+    /// normal mapped objects, flat ones are lit alike from any angle.
+    pub elevation: f32,
+    /// Whether occluders block the light.
+    pub casts_shadows: bool,
+}
+impl Light {
+    #[must_use]
+    pub fn point(position: [f32; 2], radius: f32, color: Color) -> Self {
+        Self {
+            position,
+            kind: LightKind::Point,
+            color,
+            intensity: 1.0,
+            radius,
+            falloff: 2.0,
+            elevation: FRAC_PI_4,
+            casts_shadows: true,
+        }
+    }
+
+    /// Cone of `angle` radians around `direction`, with a soft edge.
+    #[must_use]
+    pub fn spot(position: [f32; 2], radius: f32, color: Color, direction: f32, angle: f32) -> Self {
+        Self {
+            kind: LightKind::Spot {
+                direction,
+                inner_angle: angle * 0.4,
+                outer_angle: angle * 0.5,
+            },
+            ..Self::point(position, radius, color)
+        }
+    }
+
+    #[must_use]
+    pub fn with_intensity(mut self, intensity: f32) -> Self {
+        self.intensity = intensity;
+        self
+    }
+
+    #[must_use]
+    pub fn with_falloff(mut self, falloff: f32) -> Self {
+        self.falloff = falloff;
+        self
+    }
+
+    #[must_use]
+    pub fn with_elevation(mut self, elevation: f32) -> Self {
+        self.elevation = elevation;
+        self
+    }
+
+    #[must_use]
+    pub fn with_shadows(mut self, casts_shadows: bool) -> Self {
+        self.casts_shadows = casts_shadows;
+        self
+    }
+}
+
+/// Light applied to the scene of the window: the ambient color everywhere, plus every light
+/// where it reaches. The sum multiplies the colors of the objects that are
+/// [`crate::graphics::RenderObject::lit`], so an ambient of [`Color::WHITE`] without lights
+/// changes nothing and [`Color::BLACK`] leaves only what the lights show.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Lighting {
+    pub ambient: Color,
+    pub lights: Vec<Light>,
+}
+impl Lighting {
+    #[must_use]
+    pub fn new(ambient: Color) -> Self {
+        Self {
+            ambient,
+            lights: Vec::new(),
+        }
+    }
+
+    #[must_use]
+    pub fn with_light(mut self, light: Light) -> Self {
+        self.lights.push(light);
+        self
+    }
+}
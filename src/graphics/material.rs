@@ -0,0 +1,153 @@
+use bytemuck::{Pod, Zeroable};
+
+use crate::graphics::Color;
+
+/// Line drawn around a shape or text, outside of its edge.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Outline {
+    pub width: f32,
+    pub color: Color,
+}
+impl Outline {
+    #[must_use]
+    pub fn new(width: f32, color: Color) -> Self {
+        Self { width, color }
+    }
+}
+
+/// Copy of a text's glyphs drawn below them.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Shadow {
+    /// Displacement, `y` up like positions.
+    pub offset: [f32; 2],
+    /// Width of the blurred edge, `0.0` is as sharp as the glyphs.
+    pub softness: f32,
+    pub color: Color,
+}
+impl Shadow {
+    #[must_use]
+    pub fn new(offset: [f32; 2], color: Color) -> Self {
+        Self {
+            offset,
+            softness: 0.0,
+            color,
+        }
+    }
+
+    #[must_use]
+    pub fn with_softness(mut self, softness: f32) -> Self {
+        self.softness = softness;
+        self
+    }
+}
+
+/// Shape evaluated per fragment as a signed distance, so its edge is smooth at any scale.
+/// Centered on the origin, sizes in mesh units.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SdfShape {
+    Circle {
+        radius: f32,
+    },
+    RoundedBox {
+        half_size: [f32; 2],
+        corner_radius: f32,
+    },
+    /// Two half circles joined by straight sides, lying along x.
+    Capsule {
+        half_length: f32,
+        radius: f32,
+    },
+}
+impl SdfShape {
+    /// Half extent of the box around the shape.
+    #[must_use]
+    pub fn half_extent(&self) -> [f32; 2] {
+        match *self {
+            Self::Circle { radius } => [radius, radius],
+            Self::RoundedBox { half_size, .. } => half_size,
+            Self::Capsule {
+                half_length,
+                radius,
+            } => [half_length + radius, radius],
+        }
+    }
+}
+
+/// How the fragments of an object are colored.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Material {
+    /// Vertex colors times the texture.
+    #[default]
+    Standard,
+    /// Vertex colors inside `shape`, which is evaluated at the mesh's uvs, see
+    /// [`crate::graphics::RenderObject::shape`].
+    Shape {
+        shape: SdfShape,
+        outline: Option<Outline>,
+    },
+    /// Glyphs from a multi-channel distance field texture, set by the text renderer. Sizes are
+    /// in texels of that texture, `range` being the distance the field spans.
+    MsdfText {
+        range: f32,
+        outline: Option<Outline>,
+        shadow: Option<Shadow>,
+    },
+}
+
+/// Laid out like the WGSL `MaterialUniform`. What the slots hold depends on the material, see
+/// `sdf.wgsl`.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Pod, Zeroable)]
+pub struct MaterialUniform {
+    params: [f32; 4],
+    extra: [f32; 4],
+    outline_color: Color,
+    shadow_color: Color,
+}
+impl MaterialUniform {
+    #[must_use]
+    pub fn new(material: &Material) -> Self {
+        match *material {
+            Material::Standard => Self::default(),
+            Material::Shape { shape, outline } => {
+                let params = match shape {
+                    SdfShape::Circle { radius } => [0.0, radius, 0.0, 0.0],
+                    SdfShape::RoundedBox {
+                        half_size,
+                        corner_radius,
+                    } => {
+                        let corner_radius =
+                            corner_radius.clamp(0.0, half_size[0].min(half_size[1]));
+                        [1.0, half_size[0], half_size[1], corner_radius]
+                    }
+                    SdfShape::Capsule {
+                        half_length,
+                        radius,
+                    } => [2.0, half_length, radius, 0.0],
+                };
+                let outline = outline.unwrap_or(Outline::new(0.0, Color::TRANSPARENT));
+                Self {
+                    params,
+                    extra: [outline.width.max(0.0), 0.0, 0.0, 0.0],
+                    outline_color: outline.color,
+                    shadow_color: Color::TRANSPARENT,
+                }
+            }
+            Material::MsdfText {
+                range,
+                outline,
+                shadow,
+            } => {
+                let outline = outline.unwrap_or(Outline::new(0.0, Color::TRANSPARENT));
+                let shadow = shadow.unwrap_or(Shadow::new([0.0, 0.0], Color::TRANSPARENT));
+                Self {
+                    params: [range, outline.width.max(0.0), shadow.softness.max(0.0), 0.0],
+                    // texture rows go down
+                    extra: [shadow.offset[0], -shadow.offset[1], 0.0, 0.0],
+                    outline_color: outline.color,
+                    shadow_color: shadow.color,
+                }
+            }
+        }
+    }
+}
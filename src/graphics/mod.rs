@@ -1,12 +1,46 @@
+mod camera;
+mod color;
 mod geometry;
+mod image;
+mod light;
+mod material;
+mod particles;
 mod render_object;
 mod renderer;
+mod sprite;
+mod text;
+mod tilemap;
 mod transform;
 
-pub use geometry::mesh::Mesh;
+pub use camera::Camera;
+pub use color::Color;
+pub use geometry::mesh::{Mesh, MeshUsage};
 pub use geometry::primitives;
 pub use geometry::vertex::Vertex;
-pub use render_object::RenderObject;
-pub use renderer::context::GraphicsContext;
+pub use light::{Light, LightKind, Lighting};
+pub use material::{Material, Outline, SdfShape, Shadow};
+pub use particles::{
+    ParticleSystem,
+    curve::Curve,
+    emitter::{Emission, Emitter, EmitterShape},
+};
+pub use render_object::{RenderData, RenderObject};
+pub use renderer::buffer::{live_buffer_bytes, live_buffer_count};
+pub use renderer::compute::{Dispatch, Readback, StorageBufferId};
+pub use renderer::config::{AntiAliasing, GraphicsConfig};
+pub use renderer::context::{FrameStatus, GraphicsContext, SurfaceStatus};
+pub use renderer::culling::CullStats;
+pub use renderer::post_effect::{EffectParam, Lut3d, PostEffect};
+pub use renderer::resolution::{Resolution, ScaleMode};
+pub use renderer::shared_geometry::SharedGeometry;
+pub use renderer::texture::TextureId;
 pub use renderer::uniforms;
+pub use sprite::{
+    SpriteSheet,
+    animation::{AnimationClip, AnimationEvent, Animator, PlaybackMode},
+    atlas::SpriteAtlas,
+    packer::pack_atlas,
+};
+pub use text::{FontId, Text, TextAlign, TextRendering};
+pub use tilemap::Tilemap;
 pub use transform::Transform;
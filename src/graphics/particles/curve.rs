@@ -0,0 +1,128 @@
+use crate::graphics::Color;
+
+/// Values a [`Curve`] can blend between.
+pub trait Lerp: Copy {
+    #[must_use]
+    fn lerp(self, other: Self, t: f32) -> Self;
+}
+impl Lerp for f32 {
+    fn lerp(self, other: Self, t: f32) -> Self {
+        self + (other - self) * t
+    }
+}
+impl Lerp for Color {
+    fn lerp(self, other: Self, t: f32) -> Self {
+        Color::lerp(self, other, t)
+    }
+}
+
+/// Value over a particle's life, keyed by the fraction of the lifetime that passed,
+/// `0.0..=1.0`. Blends linearly between keys and holds the first and last value before and
+/// after them.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Curve<T> {
+    /// Sorted by time.
+    keys: Vec<(f32, T)>,
+}
+impl<T: Lerp> Curve<T> {
+    #[must_use]
+    pub fn constant(value: T) -> Self {
+        Self {
+            keys: vec![(0.0, value)],
+        }
+    }
+
+    /// From `start` at birth to `end` at death.
+    #[must_use]
+    pub fn linear(start: T, end: T) -> Self {
+        Self {
+            keys: vec![(0.0, start), (1.0, end)],
+        }
+    }
+
+    /// Adds a key, replacing one at the same time.
+    #[must_use]
+    pub fn with_key(mut self, time: f32, value: T) -> Self {
+        let index = self.keys.partition_point(|(key, _)| *key < time);
+        match self.keys.get_mut(index) {
+            Some(key) if key.0 == time => key.1 = value,
+            _ => self.keys.insert(index, (time, value)),
+        }
+        self
+    }
+
+    #[must_use]
+    pub fn sample(&self, time: f32) -> T {
+        // keys are never empty
+        let index = self.keys.partition_point(|(key, _)| *key <= time);
+        let Some(&(end, to)) = self.keys.get(index) else {
+            return self.keys[self.keys.len() - 1].1;
+        };
+        if index == 0 {
+            return to;
+        }
+        let (start, from) = self.keys[index - 1];
+        from.lerp(to, (time - start) / (end - start))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn constant_holds_everywhere() {
+        let curve = Curve::constant(3.0);
+        for time in [-1.0, 0.0, 0.5, 1.0, 2.0] {
+            assert_eq!(curve.sample(time), 3.0);
+        }
+    }
+
+    #[test]
+    fn linear_blends_between_the_ends() {
+        let curve = Curve::linear(2.0, 6.0);
+        assert_eq!(curve.sample(0.0), 2.0);
+        assert_eq!(curve.sample(0.25), 3.0);
+        assert_eq!(curve.sample(0.5), 4.0);
+        assert_eq!(curve.sample(1.0), 6.0);
+    }
+
+    #[test]
+    fn holds_the_outer_keys_outside_them() {
+        let curve = Curve::constant(1.0).with_key(0.25, 1.0).with_key(0.75, 5.0);
+        assert_eq!(curve.sample(-0.5), 1.0);
+        assert_eq!(curve.sample(0.0), 1.0);
+        assert_eq!(curve.sample(0.5), 3.0);
+        assert_eq!(curve.sample(0.75), 5.0);
+        assert_eq!(curve.sample(1.0), 5.0);
+        assert_eq!(curve.sample(1.5), 5.0);
+    }
+
+    #[test]
+    fn keys_are_sorted_whatever_order_they_are_added_in() {
+        let curve = Curve::linear(0.0, 0.0)
+            .with_key(0.75, 4.0)
+            .with_key(0.25, 2.0)
+            .with_key(0.5, 8.0);
+        assert_eq!(curve.sample(0.25), 2.0);
+        assert_eq!(curve.sample(0.375), 5.0);
+        assert_eq!(curve.sample(0.5), 8.0);
+        assert_eq!(curve.sample(0.625), 6.0);
+        assert_eq!(curve.sample(0.875), 2.0);
+    }
+
+    #[test]
+    fn a_key_at_an_existing_time_replaces_it() {
+        let curve = Curve::linear(0.0, 4.0)
+            .with_key(1.0, 8.0)
+            .with_key(0.0, 2.0);
+        assert_eq!(curve, Curve::linear(2.0, 8.0));
+        assert_eq!(curve.sample(0.5), 5.0);
+    }
+
+    #[test]
+    fn colors_blend_per_channel() {
+        let curve = Curve::linear(Color::RED, Color::BLUE.with_alpha(0.0));
+        assert_eq!(curve.sample(0.5), Color::linear_rgba(0.5, 0.0, 0.5, 0.5));
+    }
+}
@@ -0,0 +1,272 @@
+use std::f32::consts::{FRAC_PI_2, TAU};
+
+use crate::graphics::{Color, Mesh, particles::curve::Curve};
+
+/// Where an [`Emitter`] spawns particles, relative to its position. `outward` is the direction
+/// [`Emitter::outward`] particles leave in.
+#[derive(Clone, Debug, PartialEq)]
+pub enum EmitterShape {
+    /// Outward is [`Emitter::direction`].
+    Point,
+    /// Anywhere inside, outward is away from the center.
+    Circle { radius: f32 },
+    /// Outward is to the right of `from` -> `to`.
+    Line { from: [f32; 2], to: [f32; 2] },
+    /// Anywhere along the edges, outward is to the right of each. See [`EmitterShape::mesh_edges`].
+    Edges(Vec<[[f32; 2]; 2]>),
+}
+impl EmitterShape {
+    /// Outline of `mesh`, so particles leave it like sparks off its rim.
+    #[must_use]
+    pub fn mesh_edges(mesh: &Mesh) -> Self {
+        Self::Edges(mesh.outline())
+    }
+
+    /// Point and outward angle for two random numbers in `0.0..1.0`.
+    pub(super) fn sample(&self, direction: f32, [u, v]: [f32; 2]) -> ([f32; 2], f32) {
+        match self {
+            Self::Point => ([0.0, 0.0], direction),
+            Self::Circle { radius } => {
+                let angle = u * TAU;
+                let distance = radius * v.sqrt();
+                ([angle.cos() * distance, angle.sin() * distance], angle)
+            }
+            Self::Line { from, to } => (lerp_point(*from, *to, u), right_of(*from, *to)),
+            Self::Edges(edges) => {
+                let length = |[from, to]: &[[f32; 2]; 2]| (to[0] - from[0]).hypot(to[1] - from[1]);
+                let mut remaining = u * edges.iter().map(length).sum::<f32>();
+                for edge in edges {
+                    let edge_length = length(edge);
+                    if remaining <= edge_length && edge_length > 0.0 {
+                        let [from, to] = *edge;
+                        return (
+                            lerp_point(from, to, remaining / edge_length),
+                            right_of(from, to),
+                        );
+                    }
+                    remaining -= edge_length;
+                }
+                ([0.0, 0.0], direction)
+            }
+        }
+    }
+}
+
+/// When an [`Emitter`] spawns particles.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Emission {
+    /// Particles per second, spread evenly over time.
+    Continuous(f32),
+    /// `count` particles at once, every `interval` seconds or only once when `None`.
+    Burst { count: u32, interval: Option<f32> },
+}
+
+/// Spawns particles and decides how they move and look over their life. Ranges are
+/// `[min, max]`, each particle picks a random value in between. Distances are in the units of
+/// the mesh of the particle system's object.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Emitter {
+    /// Position within the particle system. Moving it leaves the particles already spawned
+    /// where they are, moving the object moves them all.
+    pub position: [f32; 2],
+    pub shape: EmitterShape,
+    pub emission: Emission,
+    /// Stops spawning when `false`, particles alive keep going.
+    pub enabled: bool,
+    /// Seconds.
+    pub lifetime: [f32; 2],
+    /// Units per second.
+    pub speed: [f32; 2],
+    /// Radians, `0.0` points along x.
+    pub direction: f32,
+    /// Angle around the direction particles scatter in, [`TAU`] for every direction.
+    pub spread: f32,
+    /// Takes the direction from the shape instead, see [`EmitterShape`].
+    pub outward: bool,
+    /// Radians.
+    pub rotation: [f32; 2],
+    /// Radians per second.
+    pub angular_velocity: [f32; 2],
+    /// Units per second squared.
+    pub gravity: [f32; 2],
+    /// Fraction of the velocity lost per second, `0.0` keeps it.
+    pub drag: f32,
+    /// Width and height of the particle's quad.
+    pub size: Curve<f32>,
+    /// Multiplies the object's texture and vertex colors.
+    pub color: Curve<Color>,
+    /// Alive particles of this emitter, spawning waits for room.
+    pub max_particles: usize,
+    /// Particles owed by [`Emission::Continuous`], fractional ones carry over.
+    spawn_debt: f32,
+    /// Seconds since the last burst, `None` before the first one.
+    since_burst: Option<f32>,
+    /// Requested with [`Emitter::burst`].
+    pending: u32,
+}
+impl Emitter {
+    #[must_use]
+    pub fn new(shape: EmitterShape, emission: Emission) -> Self {
+        Self {
+            position: [0.0, 0.0],
+            shape,
+            emission,
+            enabled: true,
+            lifetime: [1.0, 1.0],
+            speed: [0.5, 0.5],
+            direction: FRAC_PI_2,
+            spread: TAU,
+            outward: false,
+            rotation: [0.0, 0.0],
+            angular_velocity: [0.0, 0.0],
+            gravity: [0.0, 0.0],
+            drag: 0.0,
+            size: Curve::constant(0.05),
+            color: Curve::constant(Color::WHITE),
+            max_particles: 1000,
+            spawn_debt: 0.0,
+            since_burst: None,
+            pending: 0,
+        }
+    }
+
+    #[must_use]
+    pub fn with_position(mut self, position: [f32; 2]) -> Self {
+        self.position = position;
+        self
+    }
+
+    #[must_use]
+    pub fn with_lifetime(mut self, min: f32, max: f32) -> Self {
+        self.lifetime = [min, max];
+        self
+    }
+
+    #[must_use]
+    pub fn with_speed(mut self, min: f32, max: f32) -> Self {
+        self.speed = [min, max];
+        self
+    }
+
+    /// Particles leave within `spread` radians around `direction`.
+    #[must_use]
+    pub fn with_direction(mut self, direction: f32, spread: f32) -> Self {
+        self.direction = direction;
+        self.spread = spread;
+        self
+    }
+
+    /// Particles leave within `spread` radians around the shape's outward direction.
+    #[must_use]
+    pub fn with_outward(mut self, spread: f32) -> Self {
+        self.outward = true;
+        self.spread = spread;
+        self
+    }
+
+    #[must_use]
+    pub fn with_rotation(mut self, min: f32, max: f32) -> Self {
+        self.rotation = [min, max];
+        self
+    }
+
+    #[must_use]
+    pub fn with_angular_velocity(mut self, min: f32, max: f32) -> Self {
+        self.angular_velocity = [min, max];
+        self
+    }
+
+    #[must_use]
+    pub fn with_gravity(mut self, gravity: [f32; 2]) -> Self {
+        self.gravity = gravity;
+        self
+    }
+
+    #[must_use]
+    pub fn with_drag(mut self, drag: f32) -> Self {
+        self.drag = drag;
+        self
+    }
+
+    #[must_use]
+    pub fn with_size(mut self, size: Curve<f32>) -> Self {
+        self.size = size;
+        self
+    }
+
+    #[must_use]
+    pub fn with_color(mut self, color: Curve<Color>) -> Self {
+        self.color = color;
+        self
+    }
+
+    #[must_use]
+    pub fn with_max_particles(mut self, max_particles: usize) -> Self {
+        self.max_particles = max_particles;
+        self
+    }
+
+    /// Spawns `count` extra particles on the next step, whatever the emission, e.g. an
+    /// explosion when something is hit.
+    pub fn burst(&mut self, count: u32) {
+        self.pending += count;
+    }
+
+    /// Whether the emitter will spawn anything without another [`Emitter::burst`].
+    #[must_use]
+    pub fn is_done(&self) -> bool {
+        self.pending == 0
+            && (!self.enabled
+                || matches!(self.emission, Emission::Burst { interval: None, .. })
+                    && self.since_burst.is_some())
+    }
+
+    /// Particles to spawn `seconds` later, each with the seconds it has already been alive.
+    pub(super) fn spawn_ages(&mut self, seconds: f32) -> Vec<f32> {
+        let mut ages = vec![seconds; std::mem::take(&mut self.pending) as usize];
+        if !self.enabled {
+            return ages;
+        }
+
+        match self.emission {
+            Emission::Continuous(rate) => {
+                self.spawn_debt += rate.max(0.0) * seconds;
+                let count = self.spawn_debt.floor();
+                self.spawn_debt -= count;
+                // spread over the step instead of spawning in clumps
+                let count = count as usize;
+                ages.extend((0..count).map(|index| seconds * (index as f32 + 0.5) / count as f32));
+            }
+            Emission::Burst { count, interval } => {
+                let due = match (self.since_burst, interval) {
+                    (None, _) => Some(seconds),
+                    (Some(since), Some(interval)) if since + seconds >= interval.max(1e-3) => {
+                        Some(since + seconds - interval.max(1e-3))
+                    }
+                    _ => None,
+                };
+                match due {
+                    Some(age) => {
+                        ages.extend(std::iter::repeat_n(age.min(seconds), count as usize));
+                        self.since_burst = Some(age);
+                    }
+                    None => *self.since_burst.get_or_insert(0.0) += seconds,
+                }
+            }
+        }
+
+        ages
+    }
+}
+
+fn lerp_point(from: [f32; 2], to: [f32; 2], t: f32) -> [f32; 2] {
+    [
+        from[0] + (to[0] - from[0]) * t,
+        from[1] + (to[1] - from[1]) * t,
+    ]
+}
+
+/// Angle of the normal to the right of `from` -> `to`.
+fn right_of(from: [f32; 2], to: [f32; 2]) -> f32 {
+    (to[1] - from[1]).atan2(to[0] - from[0]) - FRAC_PI_2
+}
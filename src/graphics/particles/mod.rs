@@ -0,0 +1,230 @@
+pub mod curve;
+pub mod emitter;
+
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use bytemuck::{Pod, Zeroable};
+use wgpu::{BufferAddress, VertexAttribute, VertexBufferLayout, VertexStepMode, vertex_attr_array};
+
+use crate::graphics::{Color, particles::emitter::Emitter};
+
+/// Longest step particles move in at once, so a hitch doesn't fling them through walls of
+/// gravity or drag.
+const MAX_STEP: f32 = 0.1;
+
+/// Seeds systems that weren't given one, so two systems built alike don't move alike.
+static NEXT_SEED: AtomicU64 = AtomicU64::new(0x9e37_79b9_7f4a_7c15);
+
+struct Particle {
+    /// Index into [`ParticleSystem::emitters`].
+    emitter: usize,
+    position: [f32; 2],
+    velocity: [f32; 2],
+    rotation: f32,
+    angular_velocity: f32,
+    age: f32,
+    lifetime: f32,
+}
+
+/// Particles simulated on the CPU and drawn by their [`crate::graphics::RenderObject`] in a
+/// single instanced draw, one copy of its mesh per particle. Positions are in the units of
+/// that mesh, around the object's transform.
+pub struct ParticleSystem {
+    pub emitters: Vec<Emitter>,
+    particles: Vec<Particle>,
+    rng: Rng,
+}
+impl ParticleSystem {
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            emitters: Vec::new(),
+            particles: Vec::new(),
+            rng: Rng::new(NEXT_SEED.fetch_add(0x6a09_e667_f3bc_c909, Ordering::Relaxed)),
+        }
+    }
+
+    #[must_use]
+    pub fn with_emitter(mut self, emitter: Emitter) -> Self {
+        self.emitters.push(emitter);
+        self
+    }
+
+    /// Same seed, same particles.
+    #[must_use]
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.rng = Rng::new(seed);
+        self
+    }
+
+    #[must_use]
+    pub fn particle_count(&self) -> usize {
+        self.particles.len()
+    }
+
+    /// No particles left and none coming, e.g. to despawn a finished explosion.
+    #[must_use]
+    pub fn is_finished(&self) -> bool {
+        self.particles.is_empty() && self.emitters.iter().all(Emitter::is_done)
+    }
+
+    /// Spawns, moves and retires particles `seconds` later.
+    pub fn advance(&mut self, seconds: f32) {
+        let mut remaining = seconds.max(0.0);
+        while remaining > 0.0 {
+            let step = remaining.min(MAX_STEP);
+            self.step(step);
+            remaining -= step;
+        }
+    }
+
+    fn step(&mut self, seconds: f32) {
+        let emitters = &self.emitters;
+        self.particles.retain_mut(|particle| {
+            let Some(emitter) = emitters.get(particle.emitter) else {
+                return false;
+            };
+            particle.age += seconds;
+            if particle.age >= particle.lifetime {
+                return false;
+            }
+            particle.simulate(emitter, seconds);
+            true
+        });
+
+        let mut alive = vec![0; self.emitters.len()];
+        for particle in &self.particles {
+            alive[particle.emitter] += 1;
+        }
+        for (index, emitter) in self.emitters.iter_mut().enumerate() {
+            let room = emitter.max_particles.saturating_sub(alive[index]);
+            for age in emitter.spawn_ages(seconds).into_iter().take(room) {
+                let mut particle = spawn(&mut self.rng, emitter, index);
+                // born during the step, it catches up on the part it missed
+                if age >= particle.lifetime {
+                    continue;
+                }
+                particle.age = age;
+                particle.simulate(emitter, age);
+                self.particles.push(particle);
+            }
+        }
+    }
+
+    /// Where and how to draw every particle, oldest first.
+    pub(crate) fn instances(&self) -> Vec<ParticleInstance> {
+        self.particles
+            .iter()
+            .map(|particle| {
+                let emitter = &self.emitters[particle.emitter];
+                let life = particle.age / particle.lifetime;
+                ParticleInstance {
+                    position: particle.position,
+                    rotation: particle.rotation,
+                    size: emitter.size.sample(life),
+                    color: emitter.color.sample(life),
+                }
+            })
+            .collect()
+    }
+}
+impl Default for ParticleSystem {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Particle {
+    fn simulate(&mut self, emitter: &Emitter, seconds: f32) {
+        let damping = (1.0 - emitter.drag).clamp(0.0, 1.0).powf(seconds);
+        for axis in 0..2 {
+            self.velocity[axis] = (self.velocity[axis] + emitter.gravity[axis] * seconds) * damping;
+            self.position[axis] += self.velocity[axis] * seconds;
+        }
+        self.rotation += self.angular_velocity * seconds;
+    }
+}
+
+fn spawn(rng: &mut Rng, emitter: &Emitter, index: usize) -> Particle {
+    let ([x, y], outward) = emitter
+        .shape
+        .sample(emitter.direction, [rng.next(), rng.next()]);
+    let base = if emitter.outward {
+        outward
+    } else {
+        emitter.direction
+    };
+    let direction = base + (rng.next() - 0.5) * emitter.spread;
+    let speed = rng.range(emitter.speed);
+
+    Particle {
+        emitter: index,
+        position: [emitter.position[0] + x, emitter.position[1] + y],
+        velocity: [direction.cos() * speed, direction.sin() * speed],
+        rotation: rng.range(emitter.rotation),
+        angular_velocity: rng.range(emitter.angular_velocity),
+        age: 0.0,
+        lifetime: rng.range(emitter.lifetime).max(f32::EPSILON),
+    }
+}
+
+/// Laid out like `ParticleInput` in `particles.wgsl`, the second vertex buffer of the
+/// particle pipeline.
+#[repr(C)]
+#[derive(Clone, Copy, Pod, Zeroable)]
+pub struct ParticleInstance {
+    position: [f32; 2],
+    rotation: f32,
+    size: f32,
+    color: Color,
+}
+impl ParticleInstance {
+    const ATTRIBUTES: &[VertexAttribute] = &vertex_attr_array![
+        3 => Float32x2,
+        4 => Float32,
+        5 => Float32,
+        6 => Float32x4,
+    ];
+    /// Box around this particle's copy of a mesh within `mesh_bounds`, whichever way it's
+    /// rotated.
+    #[must_use]
+    pub fn bounds(&self, [min, max]: [[f32; 2]; 2]) -> [[f32; 2]; 2] {
+        let radius = [min, max, [min[0], max[1]], [max[0], min[1]]]
+            .iter()
+            .map(|[x, y]| x.hypot(*y))
+            .fold(0.0, f32::max)
+            * self.size.abs();
+        let [x, y] = self.position;
+        [[x - radius, y - radius], [x + radius, y + radius]]
+    }
+
+    #[must_use]
+    pub const fn vertex_buffer_layout() -> VertexBufferLayout<'static> {
+        VertexBufferLayout {
+            array_stride: std::mem::size_of::<Self>() as BufferAddress,
+            step_mode: VertexStepMode::Instance,
+            attributes: Self::ATTRIBUTES,
+        }
+    }
+}
+
+/// xorshift64*, plenty for scattering particles.
+struct Rng(u64);
+impl Rng {
+    fn new(seed: u64) -> Self {
+        Self(seed.max(1))
+    }
+
+    /// In `0.0..1.0`.
+    fn next(&mut self) -> f32 {
+        self.0 ^= self.0 >> 12;
+        self.0 ^= self.0 << 25;
+        self.0 ^= self.0 >> 27;
+        let bits = self.0.wrapping_mul(0x2545_f491_4f6c_dd1d) >> 40;
+        bits as f32 / (1_u64 << 24) as f32
+    }
+
+    fn range(&mut self, [min, max]: [f32; 2]) -> f32 {
+        min + (max - min) * self.next()
+    }
+}
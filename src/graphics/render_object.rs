@@ -1,17 +1,73 @@
+use std::ops::Range;
+
 use wgpu::{
-    BindGroup, BindGroupDescriptor, BindGroupEntry, Buffer, BufferDescriptor, BufferUsages,
-    util::DeviceExt,
+    BindGroup, BindGroupDescriptor, BindGroupEntry, BindGroupLayout, BindGroupLayoutDescriptor,
+    BindGroupLayoutEntry, BindingType, Buffer, BufferBindingType, BufferDescriptor, BufferUsages,
+    IndexFormat, RenderPass, ShaderStages,
+};
+
+use crate::graphics::{
+    Animator, Color, Material, Mesh, MeshUsage, Outline, SdfShape, Text, TextureId, Transform,
+    geometry::mesh::IndexData,
+    material::MaterialUniform,
+    particles::ParticleSystem,
+    primitives,
+    renderer::{
+        buffer::{GrowableBuffer, TrackedBuffer},
+        culling,
+        shared_geometry::{SharedGeometry, SharedMesh},
+        text_renderer::TextLayoutKey,
+    },
 };
 
-use crate::graphics::{Mesh, Transform};
+/// Where the vertices and indices of an object live on the GPU.
+enum Geometry {
+    Own {
+        vertex_buffer: GrowableBuffer,
+        index_buffer: GrowableBuffer,
+        index_format: IndexFormat,
+    },
+    /// Static meshes, drawn from [`SharedGeometry`] with 32 bit indices.
+    Shared(SharedMesh),
+}
 
-struct RenderData {
-    vertex_buffer: Buffer,
-    index_buffer: Buffer,
-    index_count: u16,
+pub struct RenderData {
+    geometry: Geometry,
+    vertices_revision: u64,
+    indices_revision: u64,
+    index_count: u32,
+    /// Of the uploaded vertices, see [`Mesh::bounds`].
+    mesh_bounds: Option<[[f32; 2]; 2]>,
 
-    transform_uniform_buffer: Buffer,
+    transform_uniform_buffer: TrackedBuffer,
+    material_uniform_buffer: TrackedBuffer,
     transform_bind_group: BindGroup,
+    uploaded_transform: Transform,
+    uploaded_material: MaterialUniform,
+
+    /// Per particle placement and color, rewritten every frame.
+    instance_buffer: Option<GrowableBuffer>,
+    instance_count: u32,
+    particle_bounds: Option<[[f32; 2]; 2]>,
+}
+impl RenderData {
+    /// Frees the GPU memory right away, the caller has to make sure no frame in flight uses it.
+    pub fn destroy(self) {
+        if let Geometry::Own {
+            vertex_buffer,
+            index_buffer,
+            ..
+        } = &self.geometry
+        {
+            vertex_buffer.buffer().destroy();
+            index_buffer.buffer().destroy();
+        }
+        self.transform_uniform_buffer.destroy();
+        self.material_uniform_buffer.destroy();
+        if let Some(instance_buffer) = self.instance_buffer {
+            instance_buffer.buffer().destroy();
+        }
+    }
 }
 
 pub struct RenderObject {
@@ -23,7 +79,26 @@ pub struct RenderObject {
     pub mesh: Mesh,
     pub name: Option<String>,
     pub transform: Transform,
+    /// Multiplied with the vertex colors, `None` draws the vertex colors alone.
+    pub texture: Option<TextureId>,
+    pub material: Material,
+    /// Replaces `mesh` and `texture` with the laid out glyphs whenever it changes.
+    pub text: Option<Text>,
+    /// Picks `texture` and the uvs of the mesh every [`crate::app::State::tick`].
+    pub animator: Option<Animator>,
+    /// Normals for [`crate::app::State::lighting`], laid out like `texture`. Made with
+    /// [`crate::graphics::GraphicsContext::create_data_texture`], `None` is flat.
+    pub normal_map: Option<TextureId>,
+    /// Whether [`crate::app::State::lighting`] applies, off for UI.
+    pub lit: bool,
+    /// Blocks lights with the outline of the mesh, see [`Mesh::outline`].
+    pub occluder: bool,
+    /// Draws `mesh` once per particle instead, advanced every [`crate::app::State::tick`].
+    pub particles: Option<ParticleSystem>,
+    text_layout_key: Option<TextLayoutKey>,
     render_data: Option<RenderData>,
+    /// Mesh revisions that failed to upload, not retried until the mesh is edited again.
+    rejected_revisions: Option<(u64, u64)>,
 }
 impl RenderObject {
     pub fn new(mesh: Mesh, name: Option<&str>, transform: Transform) -> Self {
@@ -31,84 +106,442 @@ impl RenderObject {
             mesh,
             name: name.map(|name| name.to_string()),
             transform,
+            texture: None,
+            material: Material::Standard,
+            text: None,
+            animator: None,
+            normal_map: None,
+            lit: true,
+            occluder: false,
+            particles: None,
+            text_layout_key: None,
             render_data: None,
+            rejected_revisions: None,
         }
     }
 
-    pub fn ensure_render_data(&mut self, device: &wgpu::Device) {
-        if self.render_data.is_some() {
-            return;
-        }
+    /// Object drawing `text`, its mesh is built before the first frame it's drawn in.
+    pub fn text(text: Text, name: Option<&str>, transform: Transform) -> Self {
+        let mut object = Self::new(
+            Mesh::new(Vec::new(), Vec::new()).with_usage(MeshUsage::Dynamic),
+            name,
+            transform,
+        );
+        object.text = Some(text);
+        object
+    }
 
+    /// `width` x `height` rectangle showing the sprites `animator` plays.
+    pub fn sprite(
+        mut animator: Animator,
+        (width, height): (f32, f32),
+        name: Option<&str>,
+        transform: Transform,
+    ) -> Self {
+        let mut mesh =
+            primitives::rectangle(width, height, Color::WHITE).with_usage(MeshUsage::Dynamic);
+        let mut texture = None;
+        animator.apply(&mut mesh, &mut texture);
+        let mut object = Self::new(mesh, name, transform);
+        object.texture = texture;
+        object.animator = Some(animator);
+        object
+    }
+
+    /// Object drawing the particles of `system` as unit squares, sized by its emitters and
+    /// tinted by `texture` when one is set.
+    pub fn particles(system: ParticleSystem, name: Option<&str>, transform: Transform) -> Self {
+        let mut object = Self::new(
+            primitives::rectangle(1.0, 1.0, Color::WHITE),
+            name,
+            transform,
+        );
+        object.particles = Some(system);
+        object
+    }
+
+    /// Object drawing `shape` filled with `color`, on a quad with the shape's local
+    /// coordinates as uvs. [`RenderObject::material`] can be edited afterwards, as long as the
+    /// shape doesn't outgrow the quad.
+    pub fn shape(shape: SdfShape, color: Color, name: Option<&str>, transform: Transform) -> Self {
+        let mut object = Self::new(shape_quad(shape, 0.0, color), name, transform);
+        object.material = Material::Shape {
+            shape,
+            outline: None,
+        };
+        object
+    }
+
+    pub fn with_texture(mut self, texture: TextureId) -> Self {
+        self.texture = Some(texture);
+        self
+    }
+
+    pub fn with_normal_map(mut self, normal_map: TextureId) -> Self {
+        self.normal_map = Some(normal_map);
+        self
+    }
+
+    pub fn with_lit(mut self, lit: bool) -> Self {
+        self.lit = lit;
+        self
+    }
+
+    pub fn with_occluder(mut self, occluder: bool) -> Self {
+        self.occluder = occluder;
+        self
+    }
+
+    /// Outlines a [`RenderObject::shape`], widening its quad to make room. Text is outlined
+    /// with [`Text::with_outline`] instead.
+    pub fn with_outline(mut self, outline: Outline) -> Self {
+        let Material::Shape {
+            shape,
+            outline: current,
+        } = &mut self.material
+        else {
+            log::warn!("{:?} isn't a shape, it can't be outlined", self.name);
+            return self;
+        };
+        *current = Some(outline);
+        let color = self
+            .mesh
+            .vertices()
+            .first()
+            .map_or(Color::WHITE, |vertex| vertex.color());
+        self.mesh = shape_quad(*shape, outline.width.max(0.0), color);
+        self
+    }
+
+    /// Group 1 of the object pipelines: the transform and the material uniforms.
+    pub fn bind_group_layout(device: &wgpu::Device) -> BindGroupLayout {
+        let uniform = |binding, visibility, size: usize| BindGroupLayoutEntry {
+            binding,
+            visibility,
+            ty: BindingType::Buffer {
+                ty: BufferBindingType::Uniform,
+                has_dynamic_offset: false,
+                min_binding_size: std::num::NonZeroU64::new(size as u64),
+            },
+            count: None,
+        };
+        device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+            label: Some("Object Uniform Bind Group Layout"),
+            entries: &[
+                uniform(0, ShaderStages::VERTEX, size_of::<Transform>()),
+                uniform(1, ShaderStages::FRAGMENT, size_of::<MaterialUniform>()),
+            ],
+        })
+    }
+
+    /// Creates the GPU buffers on first use and afterwards uploads whatever changed since the
+    /// previous call: mesh edits (tracked by [`Mesh`] revisions), the transform and the
+    /// material. Static meshes go into `shared` when it has room for them.
+    ///
+    /// A mesh that fails to upload is an error once, then left alone until it's edited: the
+    /// object keeps drawing the mesh uploaded before, or nothing without one.
+    pub fn ensure_render_data(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        shared: &mut SharedGeometry,
+    ) -> anyhow::Result<()> {
         let name_suffix = match &self.name {
             Some(name) => format!(": {name}"),
             None => "".to_owned(),
         };
 
-        let vertex_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-            label: Some(&format!("Vertex Buffer{name_suffix}")),
-            contents: bytemuck::cast_slice(&self.mesh.vertices),
-            usage: wgpu::BufferUsages::VERTEX,
-        });
+        let revisions = (self.mesh.vertices_revision(), self.mesh.indices_revision());
+        let rejected = self.rejected_revisions == Some(revisions);
 
-        let index_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-            label: Some(&format!("Index Buffer{name_suffix}")),
-            contents: bytemuck::cast_slice(&self.mesh.indices),
-            usage: wgpu::BufferUsages::INDEX,
-        });
+        let Some(render_data) = &mut self.render_data else {
+            if rejected {
+                return Ok(());
+            }
+            let mut render_data = match self.create_render_data(device, queue, shared, &name_suffix)
+            {
+                Ok(render_data) => render_data,
+                Err(err) => {
+                    self.rejected_revisions = Some(revisions);
+                    return Err(err);
+                }
+            };
+            upload_particles(
+                device,
+                queue,
+                &mut render_data,
+                &name_suffix,
+                self.particles.as_ref(),
+            );
+            self.render_data = Some(render_data);
+            return Ok(());
+        };
+        upload_particles(
+            device,
+            queue,
+            render_data,
+            &name_suffix,
+            self.particles.as_ref(),
+        );
 
-        let index_count = self.mesh.indices.len() as u16;
-
-        // TRANSFORM UNIFORM HARDCODED CODE. :|
-        let (transform_uniform_buffer, transform_bind_group) = {
-            let binding = 0;
-            let transform_uniform_buffer = device.create_buffer(&BufferDescriptor {
-                label: Some(&format!("Transform Uniform Buffer{name_suffix}")),
-                size: size_of::<Transform>() as u64,
-                usage: BufferUsages::UNIFORM | BufferUsages::COPY_DST,
-                mapped_at_creation: false,
-            });
-
-            let transform_bind_group_layout = Transform::bind_group_layout(device);
-
-            let transform_bind_group = device.create_bind_group(&BindGroupDescriptor {
-                label: Some(&format!("Transform Bind Group:{name_suffix}")),
-                layout: &transform_bind_group_layout,
-                entries: &[BindGroupEntry {
-                    binding,
-                    resource: transform_uniform_buffer.as_entire_binding(),
-                }],
-            });
-            (
-                transform_uniform_buffer,
-                // transform_bind_group_layout,
-                transform_bind_group,
-            )
+        let usage = self.mesh.usage();
+        let streaming = usage == MeshUsage::Streaming;
+        let vertices_changed = render_data.vertices_revision != self.mesh.vertices_revision();
+        let format_changed = matches!(
+            &render_data.geometry,
+            Geometry::Own { index_format, .. } if *index_format != self.mesh.index_format()
+        );
+        let indices_changed =
+            render_data.indices_revision != self.mesh.indices_revision() || format_changed;
+
+        if (streaming || vertices_changed || indices_changed) && !rejected {
+            let vertex_bytes: &[u8] = bytemuck::cast_slice(self.mesh.vertices());
+            let index_data = match self.mesh.index_data().and_then(|index_data| {
+                check_buffer_sizes(
+                    device,
+                    &name_suffix,
+                    vertex_bytes.len(),
+                    index_data.bytes.len(),
+                )?;
+                Ok(index_data)
+            }) {
+                Ok(index_data) => index_data,
+                Err(err) => {
+                    self.rejected_revisions = Some(revisions);
+                    return Err(err);
+                }
+            };
+
+            match &mut render_data.geometry {
+                Geometry::Shared(_) => {
+                    // moved as a whole, the old place is given back once replaced
+                    render_data.geometry = create_geometry(
+                        device,
+                        queue,
+                        shared,
+                        (&self.mesh, &index_data, self.particles.is_some()),
+                        &name_suffix,
+                    );
+                }
+                Geometry::Own {
+                    vertex_buffer,
+                    index_buffer,
+                    index_format,
+                } => {
+                    if streaming || vertices_changed {
+                        let regrown = vertex_buffer.write(
+                            device,
+                            queue,
+                            vertex_bytes,
+                            buffer_capacity(usage, vertex_bytes.len()),
+                        );
+                        if regrown {
+                            log::debug!("Vertex Buffer{name_suffix} regrown");
+                        }
+                    }
+
+                    if streaming || indices_changed {
+                        let regrown = index_buffer.write(
+                            device,
+                            queue,
+                            &index_data.bytes,
+                            buffer_capacity(usage, index_data.bytes.len()),
+                        );
+                        if regrown {
+                            log::debug!("Index Buffer{name_suffix} regrown");
+                        }
+                    }
+                    *index_format = index_data.format;
+                }
+            }
+
+            render_data.vertices_revision = self.mesh.vertices_revision();
+            render_data.indices_revision = self.mesh.indices_revision();
+            render_data.mesh_bounds = self.mesh.bounds();
+            render_data.index_count = index_data.count;
+        }
+
+        if bytemuck::bytes_of(&render_data.uploaded_transform)
+            != bytemuck::bytes_of(&self.transform)
+        {
+            queue.write_buffer(
+                &render_data.transform_uniform_buffer,
+                0,
+                bytemuck::bytes_of(&self.transform),
+            );
+            render_data.uploaded_transform = self.transform;
+        }
+
+        let material = MaterialUniform::new(&self.material);
+        if render_data.uploaded_material != material {
+            queue.write_buffer(
+                &render_data.material_uniform_buffer,
+                0,
+                bytemuck::bytes_of(&material),
+            );
+            render_data.uploaded_material = material;
+        }
+
+        Ok(())
+    }
+
+    fn create_render_data(
+        &self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        shared: &mut SharedGeometry,
+        name_suffix: &str,
+    ) -> anyhow::Result<RenderData> {
+        let index_data = self.mesh.index_data()?;
+        let vertex_bytes: &[u8] = bytemuck::cast_slice(self.mesh.vertices());
+        check_buffer_sizes(
+            device,
+            name_suffix,
+            vertex_bytes.len(),
+            index_data.bytes.len(),
+        )?;
+        let geometry = create_geometry(
+            device,
+            queue,
+            shared,
+            (&self.mesh, &index_data, self.particles.is_some()),
+            name_suffix,
+        );
+
+        let uniform_buffer = |label: &str, contents: &[u8]| {
+            let buffer = TrackedBuffer::new(
+                device,
+                &BufferDescriptor {
+                    label: Some(&format!("{label} Uniform Buffer{name_suffix}")),
+                    size: contents.len() as u64,
+                    usage: BufferUsages::UNIFORM | BufferUsages::COPY_DST,
+                    mapped_at_creation: true,
+                },
+            );
+            buffer
+                .slice(..)
+                .get_mapped_range_mut()
+                .copy_from_slice(contents);
+            buffer.unmap();
+            buffer
         };
+        let material = MaterialUniform::new(&self.material);
+        let transform_uniform_buffer =
+            uniform_buffer("Transform", bytemuck::bytes_of(&self.transform));
+        let material_uniform_buffer = uniform_buffer("Material", bytemuck::bytes_of(&material));
 
-        self.render_data = Some(RenderData {
-            vertex_buffer,
-            index_buffer,
-            index_count,
+        let transform_bind_group = device.create_bind_group(&BindGroupDescriptor {
+            label: Some(&format!("Object Bind Group{name_suffix}")),
+            layout: &Self::bind_group_layout(device),
+            entries: &[
+                BindGroupEntry {
+                    binding: 0,
+                    resource: transform_uniform_buffer.as_entire_binding(),
+                },
+                BindGroupEntry {
+                    binding: 1,
+                    resource: material_uniform_buffer.as_entire_binding(),
+                },
+            ],
+        });
+
+        Ok(RenderData {
+            geometry,
+            vertices_revision: self.mesh.vertices_revision(),
+            indices_revision: self.mesh.indices_revision(),
+            index_count: index_data.count,
+            mesh_bounds: self.mesh.bounds(),
             transform_uniform_buffer,
+            material_uniform_buffer,
             transform_bind_group,
-        });
+            uploaded_transform: self.transform,
+            uploaded_material: material,
+            instance_buffer: None,
+            instance_count: 0,
+            particle_bounds: None,
+        })
+    }
+
+    #[must_use]
+    pub fn text_layout_key(&self) -> Option<&TextLayoutKey> {
+        self.text_layout_key.as_ref()
+    }
+
+    pub fn set_text_layout_key(&mut self, key: TextLayoutKey) {
+        self.text_layout_key = Some(key);
     }
 
+    /// Detaches the GPU resources, the next [`RenderObject::ensure_render_data`] recreates them.
+    pub fn take_render_data(&mut self) -> Option<RenderData> {
+        self.render_data.take()
+    }
+
+    /// Buffers and range to draw the mesh from, `shared` being the one it was uploaded with.
     #[must_use]
-    pub fn vertex_buffer(&self) -> &Buffer {
-        &self.render_data.as_ref().unwrap().vertex_buffer
+    pub fn geometry<'a>(&'a self, shared: &'a SharedGeometry) -> MeshSlice<'a> {
+        let render_data = self.render_data.as_ref().unwrap();
+        match &render_data.geometry {
+            Geometry::Own {
+                vertex_buffer,
+                index_buffer,
+                index_format,
+            } => MeshSlice {
+                vertex_buffer: vertex_buffer.buffer(),
+                index_buffer: index_buffer.buffer(),
+                index_format: *index_format,
+                indices: 0..render_data.index_count,
+                base_vertex: 0,
+            },
+            Geometry::Shared(mesh) => MeshSlice {
+                vertex_buffer: shared.vertex_buffer(),
+                index_buffer: shared.index_buffer(),
+                index_format: IndexFormat::Uint32,
+                indices: mesh.indices(),
+                base_vertex: mesh.base_vertex(),
+            },
+        }
     }
 
+    /// Whether the mesh is drawn from [`SharedGeometry`], so draws of such objects can follow
+    /// each other without rebinding buffers.
     #[must_use]
-    pub fn index_buffer(&self) -> &Buffer {
-        &self.render_data.as_ref().unwrap().index_buffer
+    pub fn uses_shared_geometry(&self) -> bool {
+        matches!(
+            self.render_data.as_ref().unwrap().geometry,
+            Geometry::Shared(_)
+        )
     }
 
-    pub fn index_count(&self) -> u16 {
+    pub fn index_count(&self) -> u32 {
         self.render_data.as_ref().unwrap().index_count
     }
 
+    /// Box around everything the object draws as `[min, max]` in mesh units, `None` when it
+    /// draws nothing, e.g. without an uploaded mesh.
+    #[must_use]
+    pub fn bounds(&self) -> Option<[[f32; 2]; 2]> {
+        let render_data = self.render_data.as_ref()?;
+        match self.particles {
+            Some(_) => render_data.particle_bounds,
+            None => render_data.mesh_bounds,
+        }
+    }
+
+    /// Particles to draw, `None` for everything but particle systems.
+    #[must_use]
+    pub fn instance_buffer(&self) -> Option<&Buffer> {
+        let render_data = self.render_data.as_ref().unwrap();
+        render_data
+            .instance_buffer
+            .as_ref()
+            .map(GrowableBuffer::buffer)
+    }
+
+    #[must_use]
+    pub fn instance_count(&self) -> u32 {
+        self.render_data.as_ref().unwrap().instance_count
+    }
+
     pub fn transform_buffer(&self) -> &Buffer {
         &self.render_data.as_ref().unwrap().transform_uniform_buffer
     }
@@ -121,3 +554,145 @@ impl RenderObject {
         &self.render_data.as_ref().unwrap().transform_bind_group
     }
 }
+
+/// Part of a vertex and an index buffer holding one mesh.
+pub struct MeshSlice<'a> {
+    pub vertex_buffer: &'a Buffer,
+    pub index_buffer: &'a Buffer,
+    pub index_format: IndexFormat,
+    pub indices: Range<u32>,
+    pub base_vertex: i32,
+}
+impl<'a> MeshSlice<'a> {
+    /// Binds the buffers unless `bound` says they already are, then draws `instances`.
+    pub fn draw(
+        &self,
+        render_pass: &mut RenderPass<'_>,
+        bound: &mut Option<&'a Buffer>,
+        instances: Range<u32>,
+    ) {
+        if *bound != Some(self.vertex_buffer) {
+            render_pass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
+            render_pass.set_index_buffer(self.index_buffer.slice(..), self.index_format);
+            *bound = Some(self.vertex_buffer);
+        }
+        render_pass.draw_indexed(self.indices.clone(), self.base_vertex, instances);
+    }
+}
+
+/// Static meshes without particles are sub-allocated from `shared`, the rest and whatever
+/// doesn't fit get buffers of their own.
+fn create_geometry(
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    shared: &mut SharedGeometry,
+    (mesh, index_data, particles): (&Mesh, &IndexData, bool),
+    name_suffix: &str,
+) -> Geometry {
+    let usage = mesh.usage();
+    if usage == MeshUsage::Static
+        && !particles
+        && let Some(mesh) = shared.upload(device, queue, mesh.vertices(), mesh.indices())
+    {
+        return Geometry::Shared(mesh);
+    }
+
+    let vertex_bytes: &[u8] = bytemuck::cast_slice(mesh.vertices());
+    Geometry::Own {
+        vertex_buffer: GrowableBuffer::new(
+            device,
+            format!("Vertex Buffer{name_suffix}"),
+            buffer_usages(usage, BufferUsages::VERTEX),
+            vertex_bytes,
+            buffer_capacity(usage, vertex_bytes.len()),
+        ),
+        index_buffer: GrowableBuffer::new(
+            device,
+            format!("Index Buffer{name_suffix}"),
+            buffer_usages(usage, BufferUsages::INDEX),
+            &index_data.bytes,
+            buffer_capacity(usage, index_data.bytes.len()),
+        ),
+        index_format: index_data.format,
+    }
+}
+
+/// Quad around `shape` and `outline_width` past it, with room for the anti-aliased edge.
+fn shape_quad(shape: SdfShape, outline_width: f32, color: Color) -> Mesh {
+    let [half_width, half_height] = shape.half_extent();
+    let margin = half_width.max(half_height) * 0.1 + outline_width;
+    primitives::sdf_quad([half_width + margin, half_height + margin], color)
+}
+
+fn upload_particles(
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    render_data: &mut RenderData,
+    name_suffix: &str,
+    particles: Option<&ParticleSystem>,
+) {
+    let Some(particles) = particles else {
+        if let Some(instance_buffer) = render_data.instance_buffer.take() {
+            instance_buffer.buffer().destroy();
+        }
+        render_data.instance_count = 0;
+        render_data.particle_bounds = None;
+        return;
+    };
+
+    let instances = particles.instances();
+    let bytes: &[u8] = bytemuck::cast_slice(&instances);
+    let capacity = buffer_capacity(MeshUsage::Streaming, bytes.len());
+    match &mut render_data.instance_buffer {
+        Some(instance_buffer) => {
+            instance_buffer.write(device, queue, bytes, capacity);
+        }
+        None => {
+            render_data.instance_buffer = Some(GrowableBuffer::new(
+                device,
+                format!("Particle Instance Buffer{name_suffix}"),
+                BufferUsages::VERTEX | BufferUsages::COPY_DST,
+                bytes,
+                capacity,
+            ));
+        }
+    }
+    render_data.instance_count = instances.len() as u32;
+    render_data.particle_bounds = render_data.mesh_bounds.and_then(|mesh_bounds| {
+        instances.iter().fold(None, |bounds, instance| {
+            Some(culling::union(bounds, instance.bounds(mesh_bounds)))
+        })
+    });
+}
+
+fn check_buffer_sizes(
+    device: &wgpu::Device,
+    name_suffix: &str,
+    vertex_bytes: usize,
+    index_bytes: usize,
+) -> anyhow::Result<()> {
+    let max_buffer_size = device.limits().max_buffer_size;
+    if vertex_bytes.max(index_bytes) as u64 > max_buffer_size {
+        anyhow::bail!(
+            "Mesh{name_suffix} needs {vertex_bytes} vertex and {index_bytes} index bytes, device allows {max_buffer_size} per buffer"
+        );
+    }
+
+    Ok(())
+}
+
+fn buffer_usages(usage: MeshUsage, base: BufferUsages) -> BufferUsages {
+    match usage {
+        MeshUsage::Static => base,
+        MeshUsage::Dynamic | MeshUsage::Streaming => base | BufferUsages::COPY_DST,
+    }
+}
+
+/// Static meshes get exactly what they need, the others grow geometrically so that frequent
+/// edits don't reallocate every time.
+fn buffer_capacity(usage: MeshUsage, needed: usize) -> u64 {
+    match usage {
+        MeshUsage::Static => needed as u64,
+        MeshUsage::Dynamic | MeshUsage::Streaming => (needed as u64).next_power_of_two(),
+    }
+}
@@ -0,0 +1,92 @@
+use std::{fmt, sync::Arc};
+
+use wgpu::*;
+
+use crate::graphics::renderer::{config::GraphicsConfig, pipeline};
+
+/// What adapter the engine ended up on and with which features and limits.
+#[derive(Clone, Debug)]
+pub struct AdapterReport {
+    pub info: AdapterInfo,
+    /// `true` when a software adapter or a backend outside [`GraphicsConfig::backends`] was used.
+    pub fallback: bool,
+    pub features: Features,
+    pub limits: Limits,
+}
+impl AdapterReport {
+    #[must_use]
+    pub fn new(adapter: &Adapter, device: &Device, config: &GraphicsConfig) -> Self {
+        let info = adapter.get_info();
+        let fallback = info.device_type == DeviceType::Cpu
+            || !config.backends.contains(Backends::from(info.backend));
+
+        Self {
+            info,
+            fallback,
+            features: device.features(),
+            limits: device.limits(),
+        }
+    }
+
+    #[must_use]
+    pub fn is_software(&self) -> bool {
+        self.info.device_type == DeviceType::Cpu
+    }
+}
+impl fmt::Display for AdapterReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let AdapterInfo {
+            name,
+            device_type,
+            backend,
+            driver,
+            driver_info,
+            ..
+        } = &self.info;
+        write!(f, "{name} ({device_type:?}, {backend})")?;
+        if !driver.is_empty() {
+            write!(f, ", driver: {driver} {driver_info}")?;
+        }
+        if self.fallback {
+            write!(f, ", FALLBACK")?;
+        }
+        write!(
+            f,
+            ", max texture: {}, max buffer: {} bytes, features: {:?}",
+            self.limits.max_texture_dimension_2d, self.limits.max_buffer_size, self.features
+        )
+    }
+}
+
+/// Walks the fallback chain: the configured backends, then (if allowed) the GL backend. Every
+/// backend tries a hardware adapter first and a software one second.
+pub fn select_adapter(
+    window: &Arc<winit::window::Window>,
+    config: &GraphicsConfig,
+) -> anyhow::Result<(Instance, Surface<'static>, Adapter)> {
+    let mut candidates = vec![config.backends];
+    if config.allow_fallback && !config.backends.contains(Backends::GL) {
+        candidates.push(Backends::GL);
+    }
+
+    let mut failures = Vec::new();
+    for backends in candidates {
+        let instance = Instance::new(&InstanceDescriptor {
+            backends,
+            ..Default::default()
+        });
+        let surface = match instance.create_surface(window.clone()) {
+            Ok(surface) => surface,
+            Err(err) => {
+                failures.push(format!("{backends:?}: unable to create surface: {err}"));
+                continue;
+            }
+        };
+        match pipeline::request_adapter(&instance, &surface, config) {
+            Ok(adapter) => return Ok((instance, surface, adapter)),
+            Err(err) => failures.push(format!("{backends:?}: {err}")),
+        }
+    }
+
+    anyhow::bail!("No usable graphics adapter found. {}", failures.join("; "))
+}
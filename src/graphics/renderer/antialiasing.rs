@@ -0,0 +1,324 @@
+use wgpu::*;
+
+use crate::graphics::renderer::{
+    config::AntiAliasing,
+    render_graph::{RenderGraph, ResourceId, TransientDesc},
+};
+
+const MSAA_SAMPLE_COUNTS: [u32; 3] = [8, 4, 2];
+
+/// Renders the scene the way the active [`AntiAliasing`] mode needs: through a multisampled
+/// transient resolved into the output for MSAA, or through a transient the FXAA pass reads.
+pub struct AntiAliasingPass {
+    mode: AntiAliasing,
+    format: TextureFormat,
+    fxaa: Option<Fxaa>,
+}
+impl AntiAliasingPass {
+    /// `format` is the format of the view the scene ends up in.
+    pub fn new(
+        device: &Device,
+        adapter: &Adapter,
+        format: TextureFormat,
+        requested: AntiAliasing,
+    ) -> Self {
+        let mode = supported_mode(device, adapter, format, requested);
+        log::info!("Anti-aliasing: {mode:?}");
+
+        let fxaa = (mode == AntiAliasing::Fxaa).then(|| Fxaa::new(device, format));
+        Self { mode, format, fxaa }
+    }
+
+    #[must_use]
+    pub fn mode(&self) -> AntiAliasing {
+        self.mode
+    }
+
+    /// Sample count the scene pipeline has to be created with.
+    #[must_use]
+    pub fn sample_count(&self) -> u32 {
+        match self.mode {
+            AntiAliasing::Msaa(samples) => samples,
+            AntiAliasing::None | AntiAliasing::Fxaa => 1,
+        }
+    }
+
+    /// Adds the passes drawing the scene with `draw` into `output`, a `width` x `height`
+    /// texture of the format given to [`AntiAliasingPass::new`]. `reads` are the textures
+    /// the scene samples.
+    pub fn add_scene_passes<'a>(
+        &'a self,
+        graph: &mut RenderGraph<'a>,
+        output: ResourceId,
+        (width, height): (u32, u32),
+        reads: Vec<ResourceId>,
+        clear: wgpu::Color,
+        draw: impl FnOnce(&mut RenderPass<'_>) + 'a,
+    ) {
+        match (self.mode, &self.fxaa) {
+            (AntiAliasing::Msaa(samples), _) => {
+                let target = graph.create_transient(
+                    "MSAA Color Target",
+                    TransientDesc {
+                        width,
+                        height,
+                        format: self.format,
+                        sample_count: samples,
+                        usage: TextureUsages::RENDER_ATTACHMENT,
+                    },
+                );
+                // the multisampled texture is only needed until it's resolved
+                graph
+                    .add_pass("Scene Pass")
+                    .reads(reads)
+                    .write(target)
+                    .write(output)
+                    .record(move |ctx| {
+                        let (view, resolve_target) = (ctx.view(target), ctx.view(output));
+                        scene_pass(ctx.encoder, view, Some(resolve_target), clear, draw);
+                    });
+            }
+            (AntiAliasing::Fxaa, Some(fxaa)) => {
+                let scene = graph.create_transient(
+                    "FXAA Scene Target",
+                    TransientDesc::color(width, height, self.format),
+                );
+                graph
+                    .add_pass("Scene Pass")
+                    .reads(reads)
+                    .write(scene)
+                    .record(move |ctx| {
+                        let view = ctx.view(scene);
+                        scene_pass(ctx.encoder, view, None, clear, draw);
+                    });
+                graph
+                    .add_pass("FXAA Pass")
+                    .read(scene)
+                    .write(output)
+                    .record(move |ctx| {
+                        let (scene, output) = (ctx.view(scene), ctx.view(output));
+                        fxaa.draw(ctx.device, ctx.encoder, scene, output);
+                    });
+            }
+            _ => graph
+                .add_pass("Scene Pass")
+                .reads(reads)
+                .write(output)
+                .record(move |ctx| {
+                    let view = ctx.view(output);
+                    scene_pass(ctx.encoder, view, None, clear, draw);
+                }),
+        }
+    }
+}
+
+fn scene_pass(
+    encoder: &mut CommandEncoder,
+    view: &TextureView,
+    resolve_target: Option<&TextureView>,
+    clear: wgpu::Color,
+    draw: impl FnOnce(&mut RenderPass<'_>),
+) {
+    let store = if resolve_target.is_some() {
+        StoreOp::Discard
+    } else {
+        StoreOp::Store
+    };
+    let mut render_pass = encoder.begin_render_pass(&RenderPassDescriptor {
+        label: Some("Scene Pass"),
+        color_attachments: &[Some(RenderPassColorAttachment {
+            view,
+            resolve_target,
+            ops: Operations {
+                load: LoadOp::Clear(clear),
+                store,
+            },
+            depth_slice: None,
+        })],
+        ..Default::default()
+    });
+    draw(&mut render_pass);
+}
+
+/// `requested`, or the closest mode `device` can do with `format`. Sample counts other than
+/// 4 need [`Features::TEXTURE_ADAPTER_SPECIFIC_FORMAT_FEATURES`], which
+/// [`crate::graphics::renderer::pipeline::request_device`] enables where the adapter has it.
+#[must_use]
+pub fn supported_mode(
+    device: &Device,
+    adapter: &Adapter,
+    format: TextureFormat,
+    requested: AntiAliasing,
+) -> AntiAliasing {
+    let AntiAliasing::Msaa(requested_samples) = requested else {
+        return requested;
+    };
+    if requested_samples <= 1 {
+        return AntiAliasing::None;
+    }
+
+    let features = if device
+        .features()
+        .contains(Features::TEXTURE_ADAPTER_SPECIFIC_FORMAT_FEATURES)
+    {
+        adapter.get_texture_format_features(format)
+    } else {
+        format.guaranteed_format_features(device.features())
+    };
+    if !features
+        .flags
+        .contains(TextureFormatFeatureFlags::MULTISAMPLE_RESOLVE)
+    {
+        log::warn!("{format:?} can't be multisampled, falling back to FXAA");
+        return AntiAliasing::Fxaa;
+    }
+
+    let supported = |&samples: &u32| features.flags.sample_count_supported(samples);
+    // the most samples up to the requested count, else the fewest above it
+    match MSAA_SAMPLE_COUNTS
+        .into_iter()
+        .filter(|&samples| samples <= requested_samples)
+        .find(supported)
+        .or_else(|| MSAA_SAMPLE_COUNTS.into_iter().rev().find(supported))
+    {
+        Some(samples) => {
+            if samples != requested_samples {
+                log::warn!("{requested_samples}x MSAA not supported, using {samples}x");
+            }
+            AntiAliasing::Msaa(samples)
+        }
+        None => {
+            log::warn!("{format:?} doesn't support multisampling, falling back to FXAA");
+            AntiAliasing::Fxaa
+        }
+    }
+}
+
+struct Fxaa {
+    layout: BindGroupLayout,
+    sampler: Sampler,
+    pipeline: RenderPipeline,
+}
+impl Fxaa {
+    fn new(device: &Device, format: TextureFormat) -> Self {
+        let shader_module = device.create_shader_module(ShaderModuleDescriptor {
+            label: Some("FXAA Shader"),
+            source: ShaderSource::Wgsl(
+                concat!(
+                    include_str!("../shaders/fullscreen.wgsl"),
+                    include_str!("../shaders/fxaa.wgsl")
+                )
+                .into(),
+            ),
+        });
+
+        let layout = device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+            label: Some("FXAA Bind Group Layout"),
+            entries: &[
+                BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: ShaderStages::FRAGMENT,
+                    ty: BindingType::Texture {
+                        sample_type: TextureSampleType::Float { filterable: true },
+                        view_dimension: TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: ShaderStages::FRAGMENT,
+                    ty: BindingType::Sampler(SamplerBindingType::Filtering),
+                    count: None,
+                },
+            ],
+        });
+
+        let sampler = device.create_sampler(&SamplerDescriptor {
+            label: Some("FXAA Sampler"),
+            mag_filter: FilterMode::Linear,
+            min_filter: FilterMode::Linear,
+            ..Default::default()
+        });
+
+        let pipeline_layout = device.create_pipeline_layout(&PipelineLayoutDescriptor {
+            label: Some("FXAA Pipeline Layout"),
+            bind_group_layouts: &[&layout],
+            push_constant_ranges: &[],
+        });
+
+        let pipeline = device.create_render_pipeline(&RenderPipelineDescriptor {
+            label: Some("FXAA Pipeline"),
+            layout: Some(&pipeline_layout),
+            vertex: VertexState {
+                module: &shader_module,
+                entry_point: Some("vs_fullscreen"),
+                compilation_options: PipelineCompilationOptions::default(),
+                buffers: &[],
+            },
+            primitive: PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: MultisampleState::default(),
+            // the scene texture has the output format, so its values are already encoded the
+            // way the output expects and are copied over as they are
+            fragment: Some(FragmentState {
+                module: &shader_module,
+                entry_point: Some("fs_fxaa"),
+                compilation_options: PipelineCompilationOptions::default(),
+                targets: &[Some(ColorTargetState {
+                    format,
+                    blend: None,
+                    write_mask: ColorWrites::ALL,
+                })],
+            }),
+            multiview: None,
+            cache: None,
+        });
+
+        Self {
+            layout,
+            sampler,
+            pipeline,
+        }
+    }
+
+    fn draw(
+        &self,
+        device: &Device,
+        encoder: &mut CommandEncoder,
+        scene: &TextureView,
+        output: &TextureView,
+    ) {
+        let bind_group = device.create_bind_group(&BindGroupDescriptor {
+            label: Some("FXAA Bind Group"),
+            layout: &self.layout,
+            entries: &[
+                BindGroupEntry {
+                    binding: 0,
+                    resource: BindingResource::TextureView(scene),
+                },
+                BindGroupEntry {
+                    binding: 1,
+                    resource: BindingResource::Sampler(&self.sampler),
+                },
+            ],
+        });
+
+        let mut render_pass = encoder.begin_render_pass(&RenderPassDescriptor {
+            label: Some("FXAA Pass"),
+            color_attachments: &[Some(RenderPassColorAttachment {
+                view: output,
+                resolve_target: None,
+                ops: Operations {
+                    load: LoadOp::Clear(wgpu::Color::TRANSPARENT),
+                    store: StoreOp::Store,
+                },
+                depth_slice: None,
+            })],
+            ..Default::default()
+        });
+        render_pass.set_pipeline(&self.pipeline);
+        render_pass.set_bind_group(0, &bind_group, &[]);
+        render_pass.draw(0..3, 0..1);
+    }
+}
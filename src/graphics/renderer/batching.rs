@@ -0,0 +1,73 @@
+use std::mem::Discriminant;
+
+use crate::graphics::{Material, RenderObject, TextureId, renderer::culling};
+
+/// Batches an object looks back through for one it can join, bounding the cost per object.
+const LOOKBACK: usize = 32;
+
+/// State a draw binds besides the object's own uniforms: the pipeline, the texture and the
+/// buffers, which only shared meshes have in common.
+#[derive(PartialEq, Eq)]
+struct DrawKey {
+    particles: bool,
+    material: Discriminant<Material>,
+    texture: Option<TextureId>,
+    shared_geometry: bool,
+}
+impl DrawKey {
+    fn new(obj: &RenderObject) -> Self {
+        Self {
+            particles: obj.particles.is_some(),
+            material: std::mem::discriminant(&obj.material),
+            texture: obj.texture,
+            shared_geometry: obj.uses_shared_geometry(),
+        }
+    }
+}
+
+/// Run of objects drawn with the same [`DrawKey`].
+struct Batch<'a> {
+    key: DrawKey,
+    /// Around everything in the batch, in view space.
+    bounds: [[f32; 2]; 2],
+    objects: Vec<&'a RenderObject>,
+}
+
+/// Groups `objects`, in drawing order with their [`culling::view_bounds`], into runs that
+/// share pipeline, texture and buffers. An object only moves ahead of objects it doesn't
+/// overlap, so the frame looks the same as drawn in order.
+#[must_use]
+pub fn sort<'a>(objects: Vec<(&'a RenderObject, [[f32; 2]; 2])>) -> Vec<&'a RenderObject> {
+    let mut batches: Vec<Batch<'a>> = Vec::new();
+    for (obj, bounds) in objects {
+        let key = DrawKey::new(obj);
+        let mut joined = None;
+        for (index, batch) in batches.iter().enumerate().rev().take(LOOKBACK) {
+            if batch.key == key {
+                joined = Some(index);
+                break;
+            }
+            if culling::overlaps(batch.bounds, bounds) {
+                break;
+            }
+        }
+
+        match joined {
+            Some(index) => {
+                let batch = &mut batches[index];
+                batch.bounds = culling::union(Some(batch.bounds), bounds);
+                batch.objects.push(obj);
+            }
+            None => batches.push(Batch {
+                key,
+                bounds,
+                objects: vec![obj],
+            }),
+        }
+    }
+
+    batches
+        .into_iter()
+        .flat_map(|batch| batch.objects)
+        .collect()
+}
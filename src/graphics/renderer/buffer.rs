@@ -0,0 +1,133 @@
+use std::{
+    borrow::Cow,
+    ops::Deref,
+    sync::atomic::{AtomicU64, AtomicUsize, Ordering},
+};
+
+use wgpu::*;
+
+static LIVE_BUFFERS: AtomicUsize = AtomicUsize::new(0);
+static LIVE_BUFFER_BYTES: AtomicU64 = AtomicU64::new(0);
+
+/// Number of engine-created GPU buffers that haven't been dropped yet.
+#[must_use]
+pub fn live_buffer_count() -> usize {
+    LIVE_BUFFERS.load(Ordering::Relaxed)
+}
+
+#[must_use]
+pub fn live_buffer_bytes() -> u64 {
+    LIVE_BUFFER_BYTES.load(Ordering::Relaxed)
+}
+
+/// [`Buffer`] counted in [`live_buffer_count`] for as long as it lives.
+pub struct TrackedBuffer(Buffer);
+impl TrackedBuffer {
+    #[must_use]
+    pub fn new(device: &Device, desc: &BufferDescriptor) -> Self {
+        LIVE_BUFFERS.fetch_add(1, Ordering::Relaxed);
+        LIVE_BUFFER_BYTES.fetch_add(desc.size, Ordering::Relaxed);
+        Self(device.create_buffer(desc))
+    }
+}
+impl Deref for TrackedBuffer {
+    type Target = Buffer;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+impl Drop for TrackedBuffer {
+    fn drop(&mut self) {
+        LIVE_BUFFERS.fetch_sub(1, Ordering::Relaxed);
+        LIVE_BUFFER_BYTES.fetch_sub(self.0.size(), Ordering::Relaxed);
+    }
+}
+
+/// GPU buffer that is rewritten in place while the data fits and reallocated when it doesn't.
+pub struct GrowableBuffer {
+    buffer: TrackedBuffer,
+    label: String,
+    usage: BufferUsages,
+}
+impl GrowableBuffer {
+    #[must_use]
+    pub fn new(
+        device: &Device,
+        label: String,
+        usage: BufferUsages,
+        contents: &[u8],
+        capacity: u64,
+    ) -> Self {
+        let buffer = TrackedBuffer::new(
+            device,
+            &BufferDescriptor {
+                label: Some(&label),
+                size: aligned_size(capacity.max(contents.len() as u64)),
+                usage,
+                mapped_at_creation: true,
+            },
+        );
+        buffer.slice(..).get_mapped_range_mut()[..contents.len()].copy_from_slice(contents);
+        buffer.unmap();
+
+        Self {
+            buffer,
+            label,
+            usage,
+        }
+    }
+
+    #[must_use]
+    pub fn buffer(&self) -> &Buffer {
+        &self.buffer
+    }
+
+    #[must_use]
+    pub fn capacity(&self) -> u64 {
+        self.buffer.size()
+    }
+
+    /// Uploads `contents`, reallocating with `capacity` bytes if the current buffer is too small
+    /// or can't be written to. Returns `true` when the buffer was reallocated.
+    pub fn write(
+        &mut self,
+        device: &Device,
+        queue: &Queue,
+        contents: &[u8],
+        capacity: u64,
+    ) -> bool {
+        if !self.usage.contains(BufferUsages::COPY_DST) || contents.len() as u64 > self.capacity() {
+            *self = Self::new(
+                device,
+                std::mem::take(&mut self.label),
+                self.usage,
+                contents,
+                capacity,
+            );
+            return true;
+        }
+
+        if !contents.is_empty() {
+            queue.write_buffer(&self.buffer, 0, &padded(contents));
+        }
+        false
+    }
+}
+
+#[must_use]
+fn aligned_size(size: u64) -> u64 {
+    size.max(COPY_BUFFER_ALIGNMENT)
+        .next_multiple_of(COPY_BUFFER_ALIGNMENT)
+}
+
+fn padded(contents: &[u8]) -> Cow<'_, [u8]> {
+    let size = aligned_size(contents.len() as u64) as usize;
+    if size == contents.len() {
+        return Cow::Borrowed(contents);
+    }
+
+    let mut padded = contents.to_vec();
+    padded.resize(size, 0);
+    Cow::Owned(padded)
+}
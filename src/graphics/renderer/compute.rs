@@ -0,0 +1,464 @@
+use std::{
+    collections::BTreeMap,
+    sync::{Arc, Mutex},
+};
+
+use wgpu::*;
+
+use crate::graphics::renderer::{
+    buffer::TrackedBuffer,
+    texture::{TextureId, TextureRegistry},
+};
+
+/// Handle of a compute pipeline owned by [`ComputeRegistry`], never reused within a session.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ComputePipelineId(u64);
+
+/// Handle of a storage buffer owned by [`ComputeRegistry`], never reused within a session.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct StorageBufferId(u64);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum ComputeResource {
+    Buffer(StorageBufferId),
+    Texture(TextureId),
+}
+
+/// One run of a compute pipeline. Resources are bound at `(group, binding)` as declared in the
+/// shader, which decides whether a texture is sampled or used for storage.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Dispatch {
+    pipeline: ComputePipelineId,
+    workgroups: [u32; 3],
+    bindings: Vec<(u32, u32, ComputeResource)>,
+}
+impl Dispatch {
+    #[must_use]
+    pub fn new(pipeline: ComputePipelineId, workgroups: [u32; 3]) -> Self {
+        Self {
+            pipeline,
+            workgroups,
+            bindings: Vec::new(),
+        }
+    }
+
+    /// Storage or uniform buffer.
+    #[must_use]
+    pub fn with_buffer(mut self, group: u32, binding: u32, buffer: StorageBufferId) -> Self {
+        self.bindings
+            .push((group, binding, ComputeResource::Buffer(buffer)));
+        self
+    }
+
+    /// Sampled texture, or storage texture if it was made with
+    /// [`crate::graphics::GraphicsContext::create_storage_texture`].
+    #[must_use]
+    pub fn with_texture(mut self, group: u32, binding: u32, texture: TextureId) -> Self {
+        self.bindings
+            .push((group, binding, ComputeResource::Texture(texture)));
+        self
+    }
+}
+
+/// Copy of a buffer or texture on its way back from the GPU. The copy lands once the device is
+/// polled, which every [`crate::graphics::GraphicsContext::render`] does.
+pub struct Readback {
+    buffer: TrackedBuffer,
+    status: Arc<Mutex<Option<Result<(), BufferAsyncError>>>>,
+    /// Texture rows as `(bytes, padded bytes)`, copies pad them to `COPY_BYTES_PER_ROW_ALIGNMENT`.
+    rows: Option<(u32, u32)>,
+}
+impl Readback {
+    fn new(buffer: TrackedBuffer, rows: Option<(u32, u32)>) -> Self {
+        let status = Arc::new(Mutex::new(None));
+        let done = status.clone();
+        buffer.slice(..).map_async(MapMode::Read, move |result| {
+            *done.lock().unwrap() = Some(result);
+        });
+
+        Self {
+            buffer,
+            status,
+            rows,
+        }
+    }
+
+    #[must_use]
+    pub fn is_ready(&self) -> bool {
+        self.status.lock().unwrap().is_some()
+    }
+
+    /// The bytes once they arrived, texture rows without padding. Only the first call after
+    /// arrival returns them.
+    pub fn try_take(&mut self) -> Option<anyhow::Result<Vec<u8>>> {
+        let status = self.status.lock().unwrap().take()?;
+        if let Err(err) = status {
+            return Some(Err(anyhow::anyhow!("Unable to read back GPU data: {err}")));
+        }
+
+        let bytes = {
+            let mapped = self.buffer.slice(..).get_mapped_range();
+            match self.rows {
+                Some((bytes, padded)) => mapped
+                    .chunks(padded as usize)
+                    .flat_map(|row| &row[..bytes as usize])
+                    .copied()
+                    .collect(),
+                None => mapped.to_vec(),
+            }
+        };
+        self.buffer.unmap();
+        self.buffer.destroy();
+
+        Some(Ok(bytes))
+    }
+}
+
+struct PipelineEntry {
+    source: String,
+    entry_point: String,
+    pipeline: ComputePipeline,
+}
+
+/// Compute pipelines and the storage buffers they work on.
+#[derive(Default)]
+pub struct ComputeRegistry {
+    pipelines: BTreeMap<ComputePipelineId, PipelineEntry>,
+    buffers: BTreeMap<StorageBufferId, TrackedBuffer>,
+    next_id: u64,
+}
+impl ComputeRegistry {
+    /// Compiles `entry_point` of the WGSL `source`, its bind group layouts are derived from
+    /// the shader.
+    pub fn create_pipeline(
+        &mut self,
+        device: &Device,
+        source: &str,
+        entry_point: &str,
+    ) -> anyhow::Result<ComputePipelineId> {
+        let id = ComputePipelineId(self.next_id());
+        let pipeline = create_pipeline(device, &pipeline_label(id), source, entry_point)?;
+        self.pipelines.insert(
+            id,
+            PipelineEntry {
+                source: source.to_owned(),
+                entry_point: entry_point.to_owned(),
+                pipeline,
+            },
+        );
+
+        Ok(id)
+    }
+
+    pub fn remove_pipeline(&mut self, id: ComputePipelineId) -> bool {
+        self.pipelines.remove(&id).is_some()
+    }
+
+    /// Buffer starting out with `contents`, usable as storage or uniform buffer.
+    pub fn create_buffer(
+        &mut self,
+        device: &Device,
+        contents: &[u8],
+    ) -> anyhow::Result<StorageBufferId> {
+        let max = device.limits().max_buffer_size;
+        if contents.is_empty() || contents.len() as u64 > max {
+            anyhow::bail!(
+                "Storage buffer of {} bytes out of range, device allows 1..={max}",
+                contents.len()
+            );
+        }
+
+        let id = StorageBufferId(self.next_id());
+        let buffer = create_buffer(device, &buffer_label(id), contents.len() as u64);
+        buffer
+            .slice(..)
+            .get_mapped_range_mut()
+            .copy_from_slice(&padded(contents));
+        buffer.unmap();
+        self.buffers.insert(id, buffer);
+
+        Ok(id)
+    }
+
+    /// Overwrites the buffer from `offset` on, both have to be multiples of 4 bytes.
+    pub fn write_buffer(
+        &self,
+        queue: &Queue,
+        id: StorageBufferId,
+        offset: u64,
+        data: &[u8],
+    ) -> anyhow::Result<()> {
+        let buffer = self.buffer(id)?;
+        let end = offset + data.len() as u64;
+        if !offset.is_multiple_of(COPY_BUFFER_ALIGNMENT)
+            || !(data.len() as u64).is_multiple_of(COPY_BUFFER_ALIGNMENT)
+        {
+            anyhow::bail!("Writes to {id:?} have to be aligned to {COPY_BUFFER_ALIGNMENT} bytes");
+        }
+        if end > buffer.size() {
+            anyhow::bail!(
+                "Write of {offset}..{end} is out of bounds of {id:?} ({} bytes)",
+                buffer.size()
+            );
+        }
+
+        queue.write_buffer(buffer, offset, data);
+        Ok(())
+    }
+
+    /// The buffer is dropped once the work in flight is done with it.
+    pub fn remove_buffer(&mut self, id: StorageBufferId) -> bool {
+        self.buffers.remove(&id).is_some()
+    }
+
+    #[must_use]
+    pub fn buffer_size(&self, id: StorageBufferId) -> Option<u64> {
+        self.buffers.get(&id).map(|buffer| buffer.size())
+    }
+
+    /// Records and submits `dispatch` right away, so it runs before the next frame and sees
+    /// every write queued so far.
+    pub fn dispatch(
+        &self,
+        device: &Device,
+        queue: &Queue,
+        textures: &TextureRegistry,
+        dispatch: &Dispatch,
+    ) -> anyhow::Result<()> {
+        let Some(entry) = self.pipelines.get(&dispatch.pipeline) else {
+            anyhow::bail!("Unknown compute pipeline {:?}", dispatch.pipeline);
+        };
+        let max = device.limits().max_compute_workgroups_per_dimension;
+        if dispatch.workgroups.iter().any(|&count| count > max) {
+            anyhow::bail!(
+                "Dispatch of {:?} workgroups, device allows {max} per dimension",
+                dispatch.workgroups
+            );
+        }
+
+        let mut groups: BTreeMap<u32, Vec<BindGroupEntry>> = BTreeMap::new();
+        for &(group, binding, resource) in &dispatch.bindings {
+            let resource = match resource {
+                ComputeResource::Buffer(id) => self.buffer(id)?.as_entire_binding(),
+                ComputeResource::Texture(id) => match textures.view(id) {
+                    Some(view) => BindingResource::TextureView(view),
+                    None => anyhow::bail!("Unknown texture {id:?}"),
+                },
+            };
+            groups
+                .entry(group)
+                .or_default()
+                .push(BindGroupEntry { binding, resource });
+        }
+
+        let label = pipeline_label(dispatch.pipeline);
+        device.push_error_scope(ErrorFilter::Validation);
+        let bind_groups: Vec<(u32, BindGroup)> = groups
+            .into_iter()
+            .map(|(group, entries)| {
+                let bind_group = device.create_bind_group(&BindGroupDescriptor {
+                    label: Some(&format!("{label} Bind Group {group}")),
+                    layout: &entry.pipeline.get_bind_group_layout(group),
+                    entries: &entries,
+                });
+                (group, bind_group)
+            })
+            .collect();
+
+        let mut encoder = device.create_command_encoder(&CommandEncoderDescriptor {
+            label: Some(&format!("{label} Encoder")),
+        });
+        {
+            let mut compute_pass = encoder.begin_compute_pass(&ComputePassDescriptor {
+                label: Some(&format!("{label} Pass")),
+                timestamp_writes: None,
+            });
+            compute_pass.set_pipeline(&entry.pipeline);
+            for (group, bind_group) in &bind_groups {
+                compute_pass.set_bind_group(*group, bind_group, &[]);
+            }
+            let [x, y, z] = dispatch.workgroups;
+            compute_pass.dispatch_workgroups(x, y, z);
+        }
+        let command_buffer = encoder.finish();
+        if let Some(err) = pollster::block_on(device.pop_error_scope()) {
+            anyhow::bail!("Unable to dispatch {label}: {err}");
+        }
+        queue.submit([command_buffer]);
+
+        Ok(())
+    }
+
+    pub fn read_buffer(
+        &self,
+        device: &Device,
+        queue: &Queue,
+        id: StorageBufferId,
+    ) -> anyhow::Result<Readback> {
+        let buffer = self.buffer(id)?;
+        let staging = staging_buffer(
+            device,
+            &format!("{} Readback", buffer_label(id)),
+            buffer.size(),
+        );
+        let mut encoder = device.create_command_encoder(&CommandEncoderDescriptor {
+            label: Some("Readback Encoder"),
+        });
+        encoder.copy_buffer_to_buffer(buffer, 0, &staging, 0, buffer.size());
+        queue.submit([encoder.finish()]);
+
+        Ok(Readback::new(staging, None))
+    }
+
+    /// Recreates every pipeline on a new device, buffers come back zeroed.
+    pub fn recreate(&mut self, device: &Device) {
+        self.pipelines.retain(|id, entry| {
+            match create_pipeline(
+                device,
+                &pipeline_label(*id),
+                &entry.source,
+                &entry.entry_point,
+            ) {
+                Ok(pipeline) => {
+                    entry.pipeline = pipeline;
+                    true
+                }
+                Err(err) => {
+                    log::error!("Unable to recreate {id:?}: {err}");
+                    false
+                }
+            }
+        });
+        for (id, buffer) in &mut self.buffers {
+            let recreated = create_buffer(device, &buffer_label(*id), buffer.size());
+            recreated.unmap();
+            *buffer = recreated;
+        }
+    }
+
+    fn buffer(&self, id: StorageBufferId) -> anyhow::Result<&TrackedBuffer> {
+        self.buffers
+            .get(&id)
+            .ok_or_else(|| anyhow::anyhow!("Unknown storage buffer {id:?}"))
+    }
+
+    fn next_id(&mut self) -> u64 {
+        self.next_id += 1;
+        self.next_id - 1
+    }
+}
+
+/// Copies a RGBA8 texture back, see [`Readback`].
+pub fn read_texture(
+    device: &Device,
+    queue: &Queue,
+    textures: &TextureRegistry,
+    id: TextureId,
+) -> anyhow::Result<Readback> {
+    let Some(texture) = textures.texture(id) else {
+        anyhow::bail!("Unknown texture {id:?}");
+    };
+    let (width, height) = (texture.width(), texture.height());
+    let bytes_per_row = width * 4;
+    let padded_bytes_per_row = bytes_per_row.next_multiple_of(COPY_BYTES_PER_ROW_ALIGNMENT);
+    let staging = staging_buffer(
+        device,
+        "Texture Readback",
+        u64::from(padded_bytes_per_row) * u64::from(height),
+    );
+
+    let mut encoder = device.create_command_encoder(&CommandEncoderDescriptor {
+        label: Some("Readback Encoder"),
+    });
+    encoder.copy_texture_to_buffer(
+        texture.as_image_copy(),
+        TexelCopyBufferInfo {
+            buffer: &staging,
+            layout: TexelCopyBufferLayout {
+                offset: 0,
+                bytes_per_row: Some(padded_bytes_per_row),
+                rows_per_image: Some(height),
+            },
+        },
+        texture.size(),
+    );
+    queue.submit([encoder.finish()]);
+
+    Ok(Readback::new(
+        staging,
+        Some((bytes_per_row, padded_bytes_per_row)),
+    ))
+}
+
+fn create_pipeline(
+    device: &Device,
+    label: &str,
+    source: &str,
+    entry_point: &str,
+) -> anyhow::Result<ComputePipeline> {
+    device.push_error_scope(ErrorFilter::Validation);
+    let module = device.create_shader_module(ShaderModuleDescriptor {
+        label: Some(label),
+        source: ShaderSource::Wgsl(source.into()),
+    });
+    let pipeline = device.create_compute_pipeline(&ComputePipelineDescriptor {
+        label: Some(label),
+        layout: None,
+        module: &module,
+        entry_point: Some(entry_point),
+        compilation_options: PipelineCompilationOptions::default(),
+        cache: None,
+    });
+    if let Some(err) = pollster::block_on(device.pop_error_scope()) {
+        anyhow::bail!("Unable to create {label}: {err}");
+    }
+
+    Ok(pipeline)
+}
+
+/// Mapped at creation, the caller fills and unmaps it.
+fn create_buffer(device: &Device, label: &str, size: u64) -> TrackedBuffer {
+    TrackedBuffer::new(
+        device,
+        &BufferDescriptor {
+            label: Some(label),
+            size: size.next_multiple_of(COPY_BUFFER_ALIGNMENT),
+            usage: BufferUsages::STORAGE
+                | BufferUsages::UNIFORM
+                | BufferUsages::COPY_DST
+                | BufferUsages::COPY_SRC,
+            mapped_at_creation: true,
+        },
+    )
+}
+
+fn staging_buffer(device: &Device, label: &str, size: u64) -> TrackedBuffer {
+    TrackedBuffer::new(
+        device,
+        &BufferDescriptor {
+            label: Some(label),
+            size,
+            usage: BufferUsages::MAP_READ | BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        },
+    )
+}
+
+fn padded(contents: &[u8]) -> Vec<u8> {
+    let mut padded = contents.to_vec();
+    padded.resize(
+        contents
+            .len()
+            .next_multiple_of(COPY_BUFFER_ALIGNMENT as usize),
+        0,
+    );
+    padded
+}
+
+fn pipeline_label(id: ComputePipelineId) -> String {
+    format!("Compute Pipeline #{}", id.0)
+}
+
+fn buffer_label(id: StorageBufferId) -> String {
+    format!("Storage Buffer #{}", id.0)
+}
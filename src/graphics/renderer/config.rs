@@ -0,0 +1,177 @@
+use wgpu::{Backends, Features, Limits, PowerPreference, PresentMode};
+
+use crate::graphics::renderer::resolution::Resolution;
+
+/// Surface format family to ask for, each one falls back to the next: `Hdr` to `TenBit` to
+/// `Srgb`, and `Srgb` to any linear 8-bit format with gamma encoding done in the shader.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SurfaceFormatPreference {
+    #[default]
+    Srgb,
+    /// `Rgb10a2Unorm`, gamma encoded in the shader. Finer steps against banding, the colors
+    /// are still sRGB: no wider gamut is configured.
+    TenBit,
+    /// `Rgba16Float` extended linear sRGB, for HDR displays.
+    Hdr,
+}
+
+/// Edge smoothing of the scene. Unsupported sample counts fall back to the next lower one
+/// the surface format can resolve, and to `Fxaa` if it can't multisample at all.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AntiAliasing {
+    None,
+    /// Sample count, 2, 4 or 8.
+    Msaa(u32),
+    /// Fullscreen post-pass, cheaper and blurrier than MSAA.
+    Fxaa,
+}
+impl Default for AntiAliasing {
+    fn default() -> Self {
+        Self::Msaa(4)
+    }
+}
+
+/// Adapter and device selection. `Default` is what the engine uses without configuration;
+/// environment overrides are applied by [`GraphicsConfig::from_env`].
+#[derive(Clone, Debug)]
+pub struct GraphicsConfig {
+    pub backends: Backends,
+    pub power_preference: PowerPreference,
+    /// Adapters missing any of these are skipped.
+    pub required_features: Features,
+    /// Any the adapter can't satisfy are lowered to what it supports (with a warning) instead
+    /// of failing, the others are kept.
+    pub required_limits: Limits,
+    /// When no adapter matches, retry with a software adapter and then the GL backend.
+    pub allow_fallback: bool,
+    /// Falls back to `AutoVsync` if the surface doesn't support it.
+    pub present_mode: PresentMode,
+    pub desired_maximum_frame_latency: u32,
+    /// `None` renders as fast as the present mode allows.
+    pub target_fps: Option<f64>,
+    pub surface_format: SurfaceFormatPreference,
+    /// Asks for a transparent window and a compositor alpha mode that honours it.
+    pub transparent: bool,
+    pub anti_aliasing: AntiAliasing,
+    /// How the world is fitted into the window, see [`Resolution`].
+    pub resolution: Resolution,
+}
+impl Default for GraphicsConfig {
+    fn default() -> Self {
+        Self {
+            backends: Backends::PRIMARY,
+            power_preference: PowerPreference::default(),
+            required_features: Features::empty(),
+            required_limits: Limits {
+                max_texture_dimension_1d: 4096,
+                max_texture_dimension_2d: 4096,
+                max_texture_dimension_3d: 4096,
+                ..Default::default()
+            },
+            allow_fallback: true,
+            present_mode: PresentMode::AutoVsync,
+            desired_maximum_frame_latency: 2,
+            target_fps: None,
+            surface_format: SurfaceFormatPreference::default(),
+            transparent: false,
+            anti_aliasing: AntiAliasing::default(),
+            resolution: Resolution::default(),
+        }
+    }
+}
+impl GraphicsConfig {
+    /// Defaults overridden by `WGPU_BACKEND` (e.g. `vulkan,gl`), `WGPU_POWER_PREF`
+    /// (`low`/`high`/`none`), `UNNAMED_ENGINE_NO_FALLBACK`, `UNNAMED_ENGINE_VSYNC` (`0`/`1`) and
+    /// `UNNAMED_ENGINE_FPS`, `UNNAMED_ENGINE_SURFACE_FORMAT` (`srgb`/`10bit`/`hdr`) and
+    /// `UNNAMED_ENGINE_AA` (`off`/`fxaa`/`msaa2`/`msaa4`/`msaa8`).
+    pub fn from_env() -> Self {
+        let default = Self::default();
+        Self {
+            backends: Backends::from_env().unwrap_or(default.backends),
+            power_preference: PowerPreference::from_env().unwrap_or(default.power_preference),
+            allow_fallback: std::env::var_os("UNNAMED_ENGINE_NO_FALLBACK").is_none(),
+            present_mode: match std::env::var("UNNAMED_ENGINE_VSYNC").as_deref() {
+                Ok("0") => PresentMode::AutoNoVsync,
+                Ok("1") => PresentMode::AutoVsync,
+                _ => default.present_mode,
+            },
+            target_fps: std::env::var("UNNAMED_ENGINE_FPS")
+                .ok()
+                .and_then(|fps| fps.parse().ok()),
+            surface_format: match std::env::var("UNNAMED_ENGINE_SURFACE_FORMAT").as_deref() {
+                Ok("10bit") => SurfaceFormatPreference::TenBit,
+                Ok("hdr") => SurfaceFormatPreference::Hdr,
+                _ => default.surface_format,
+            },
+            anti_aliasing: match std::env::var("UNNAMED_ENGINE_AA").as_deref() {
+                Ok("off") => AntiAliasing::None,
+                Ok("fxaa") => AntiAliasing::Fxaa,
+                Ok("msaa2") => AntiAliasing::Msaa(2),
+                Ok("msaa4") => AntiAliasing::Msaa(4),
+                Ok("msaa8") => AntiAliasing::Msaa(8),
+                _ => default.anti_aliasing,
+            },
+            ..default
+        }
+    }
+
+    pub fn backends(mut self, backends: Backends) -> Self {
+        self.backends = backends;
+        self
+    }
+
+    pub fn power_preference(mut self, power_preference: PowerPreference) -> Self {
+        self.power_preference = power_preference;
+        self
+    }
+
+    pub fn required_features(mut self, features: Features) -> Self {
+        self.required_features = features;
+        self
+    }
+
+    pub fn required_limits(mut self, limits: Limits) -> Self {
+        self.required_limits = limits;
+        self
+    }
+
+    pub fn allow_fallback(mut self, allow_fallback: bool) -> Self {
+        self.allow_fallback = allow_fallback;
+        self
+    }
+
+    pub fn present_mode(mut self, present_mode: PresentMode) -> Self {
+        self.present_mode = present_mode;
+        self
+    }
+
+    pub fn desired_maximum_frame_latency(mut self, frames: u32) -> Self {
+        self.desired_maximum_frame_latency = frames;
+        self
+    }
+
+    pub fn target_fps(mut self, target_fps: Option<f64>) -> Self {
+        self.target_fps = target_fps;
+        self
+    }
+
+    pub fn surface_format(mut self, surface_format: SurfaceFormatPreference) -> Self {
+        self.surface_format = surface_format;
+        self
+    }
+
+    pub fn transparent(mut self, transparent: bool) -> Self {
+        self.transparent = transparent;
+        self
+    }
+
+    pub fn anti_aliasing(mut self, anti_aliasing: AntiAliasing) -> Self {
+        self.anti_aliasing = anti_aliasing;
+        self
+    }
+
+    pub fn resolution(mut self, resolution: Resolution) -> Self {
+        self.resolution = resolution;
+        self
+    }
+}
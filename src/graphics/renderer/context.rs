@@ -1,59 +1,196 @@
-use std::sync::Arc;
+use std::{
+    collections::HashMap,
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+    },
+};
 
 use wgpu::*;
-use winit::dpi::PhysicalSize;
+use winit::dpi::{PhysicalPosition, PhysicalSize};
 
 use crate::{
     app::State,
-    graphics::renderer::{
-        pipeline,
-        uniforms::{GlobalUniforms, UniformKind},
+    graphics::{
+        Color, FontId, RenderObject,
+        camera::{Camera, CameraBinding, CameraUniform},
+        live_buffer_bytes, live_buffer_count,
+        renderer::{
+            adapter::{self, AdapterReport},
+            antialiasing::AntiAliasingPass,
+            batching,
+            compute::{
+                self, ComputePipelineId, ComputeRegistry, Dispatch, Readback, StorageBufferId,
+            },
+            config::{AntiAliasing, GraphicsConfig},
+            culling::{self, CullStats},
+            frame_limiter::FrameLimiter,
+            graveyard::Graveyard,
+            letterbox::LetterboxPass,
+            lighting::{LightingInputs, LightingPass},
+            pipeline::{self, ObjectPipelines},
+            pixel_art::PixelArtPass,
+            post_effect::PostEffect,
+            post_processing::{POST_FORMAT, PostProcessor},
+            render_graph::{RenderGraph, ResourceId, TransientDesc, TransientPool},
+            render_target::RenderTarget,
+            resolution::{Resolution, Viewport},
+            shared_geometry::SharedGeometry,
+            text_renderer::TextRenderer,
+            texture::{TEXTURE_FORMAT, TextureId, TextureRegistry},
+            uniforms::{GlobalUniforms, SurfaceSizeUniform, UniformKind},
+        },
     },
 };
 
+/// Failed reconfigurations in a row after which the device itself is assumed broken.
+const MAX_RECONFIGURE_ATTEMPTS: u32 = 3;
+
+/// Lifecycle of the surface, advanced by [`GraphicsContext::render`] and
+/// [`GraphicsContext::resize_surface`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SurfaceStatus {
+    Ready,
+    /// Zero-sized (e.g. minimized) window, nothing is rendered until a nonzero resize.
+    Minimized,
+    /// Lost, outdated or suboptimal swapchain, reconfigured before the next frame.
+    NeedsReconfigure,
+    /// The device is gone, it and everything created from it is rebuilt before the next frame.
+    DeviceLost,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FrameStatus {
+    Presented,
+    Skipped,
+    /// The device was lost and rebuilt instead of rendering. What was created on it comes back
+    /// as documented by the functions creating it, e.g. storage buffers zeroed, and is up to
+    /// the game to fill again before the next frame.
+    DeviceRecreated,
+}
+
 pub struct GraphicsContext {
+    window: Arc<winit::window::Window>,
+    config: GraphicsConfig,
+    adapter_report: AdapterReport,
+    instance: Instance,
+    adapter: Adapter,
     surface_config: SurfaceConfiguration,
     surface: Surface<'static>,
+    surface_capabilities: SurfaceCapabilities,
+    surface_status: SurfaceStatus,
+    reconfigure_attempts: u32,
     device: Device,
+    device_lost: Arc<AtomicBool>,
     queue: Queue,
-    pipeline: RenderPipeline,
+    pipelines: ObjectPipelines,
+    /// Same shaders as `pipelines`, for [`RenderTarget`] textures.
+    target_pipelines: ObjectPipelines,
+    anti_aliasing: AntiAliasingPass,
+    post_effects: Vec<PostEffect>,
+    /// Only exists while there are effects, the scene goes straight to the surface otherwise.
+    post_processor: Option<PostProcessor>,
+    /// Only exists while the state has lighting.
+    lighting: Option<LightingPass>,
+    letterbox: LetterboxPass,
+    /// Only exists while the resolution is [`crate::graphics::ScaleMode::PixelArt`].
+    pixel_art: Option<PixelArtPass>,
     uniforms: GlobalUniforms,
+    textures: TextureRegistry,
+    /// Buffers static meshes are sub-allocated from.
+    shared_geometry: SharedGeometry,
+    compute: ComputeRegistry,
+    camera_binding: CameraBinding,
+    render_targets: Vec<RenderTarget>,
+    text_renderer: TextRenderer,
+    /// Textures behind the transients of the frame's [`RenderGraph`].
+    transient_pool: TransientPool,
+    graveyard: Graveyard,
+    frame_index: u64,
+    frame_limiter: FrameLimiter,
+    cull_stats: CullStats,
 }
 
 impl GraphicsContext {
-    pub fn setup(window: &Arc<winit::window::Window>, state: &mut State) -> anyhow::Result<Self> {
+    pub fn setup(
+        window: &Arc<winit::window::Window>,
+        state: &mut State,
+        config: GraphicsConfig,
+    ) -> anyhow::Result<Self> {
         log::debug!("Setting up wgpu");
 
-        let instance = Instance::new(&InstanceDescriptor {
-            backends: Backends::PRIMARY,
-            ..Default::default()
-        });
-        let surface = instance.create_surface(window.clone())?;
-        let adapter = pipeline::request_adapter(instance, &surface)?;
-        let (device, queue) = pipeline::request_device(&adapter)?;
-        let surface_config = pipeline::create_surface_config(window, &surface, adapter);
+        let (instance, surface, adapter) = adapter::select_adapter(window, &config)?;
+        let (device, queue) = pipeline::request_device(&adapter, &config)?;
+        let adapter_report = AdapterReport::new(&adapter, &device, &config);
+        log::info!("Using adapter: {adapter_report}");
+        let device_lost = watch_device_lost(&device);
+        let surface_capabilities = surface.get_capabilities(&adapter);
+        let surface_config =
+            pipeline::create_surface_config(window, &surface_capabilities, &config)?;
         let uniforms = GlobalUniforms::new(&device);
-
-        let pipeline = pipeline::create_render_pipeline(
+        let mut textures = TextureRegistry::new(&device, &queue);
+        textures.set_filter(&device, texture_filter(config.resolution));
+        let anti_aliasing = AntiAliasingPass::new(
+            &device,
+            &adapter,
+            surface_config.format,
+            scene_anti_aliasing(&config),
+        );
+        let pipelines = create_pipelines(
             &device,
-            &surface_config,
-            &[
-                uniforms.layout(),
-                &crate::graphics::Transform::bind_group_layout(&device),
-            ],
+            surface_config.format,
+            anti_aliasing.sample_count(),
+            &uniforms,
+            &textures,
         );
+        let target_pipelines = create_pipelines(&device, TEXTURE_FORMAT, 1, &uniforms, &textures);
+        let camera_binding = CameraBinding::new(&device, "Surface");
+        let letterbox = LetterboxPass::new(&device, surface_config.format);
 
-        surface.configure(&device, &surface_config);
-        state.ensure_render_data(&device);
+        let frame_limiter = FrameLimiter::new(config.target_fps);
 
-        Ok(Self {
+        let mut shared_geometry = SharedGeometry::new(&device, &adapter);
+        state.ensure_render_data(&device, &queue, &mut shared_geometry);
+
+        let mut context = Self {
+            window: window.clone(),
+            config,
+            adapter_report,
+            instance,
+            adapter,
             surface_config,
             surface,
+            surface_capabilities,
+            surface_status: SurfaceStatus::Ready,
+            reconfigure_attempts: 0,
             device,
+            device_lost,
             queue,
-            pipeline,
+            pipelines,
+            target_pipelines,
+            anti_aliasing,
+            post_effects: Vec::new(),
+            post_processor: None,
+            lighting: None,
+            letterbox,
+            pixel_art: None,
             uniforms,
-        })
+            textures,
+            shared_geometry,
+            compute: ComputeRegistry::default(),
+            camera_binding,
+            render_targets: Vec::new(),
+            text_renderer: TextRenderer::default(),
+            transient_pool: TransientPool::default(),
+            graveyard: Graveyard::default(),
+            frame_index: 0,
+            frame_limiter,
+            cull_stats: CullStats::default(),
+        };
+        let size = window.inner_size();
+        context.resize_surface(size.width, size.height);
+
+        Ok(context)
     }
 
     #[must_use]
@@ -64,75 +201,839 @@ impl GraphicsContext {
         }
     }
 
-    pub fn resize_surface(&mut self, width: u32, height: u32) -> anyhow::Result<()> {
+    #[must_use]
+    pub fn adapter_report(&self) -> &AdapterReport {
+        &self.adapter_report
+    }
+
+    #[must_use]
+    pub fn surface_status(&self) -> SurfaceStatus {
+        self.surface_status
+    }
+
+    #[must_use]
+    pub fn present_mode(&self) -> PresentMode {
+        self.surface_config.present_mode
+    }
+
+    #[must_use]
+    pub fn supported_present_modes(&self) -> &[PresentMode] {
+        &self.surface_capabilities.present_modes
+    }
+
+    /// Reconfigures the surface only, the device and every GPU resource stay as they are.
+    /// Returns the mode actually applied, unsupported modes fall back to `AutoVsync`.
+    pub fn set_present_mode(&mut self, present_mode: PresentMode) -> PresentMode {
+        let present_mode =
+            pipeline::supported_present_mode(present_mode, &self.surface_capabilities);
+        self.config.present_mode = present_mode;
+        self.surface_config.present_mode = present_mode;
+        if self.surface_status == SurfaceStatus::Ready {
+            self.configure_surface();
+        }
+        log::info!("Present mode set to {present_mode:?}");
+
+        present_mode
+    }
+
+    #[must_use]
+    pub fn vsync(&self) -> bool {
+        !matches!(
+            self.surface_config.present_mode,
+            PresentMode::AutoNoVsync | PresentMode::Immediate | PresentMode::Mailbox
+        )
+    }
+
+    pub fn set_vsync(&mut self, vsync: bool) {
+        self.set_present_mode(if vsync {
+            PresentMode::AutoVsync
+        } else {
+            PresentMode::AutoNoVsync
+        });
+    }
+
+    #[must_use]
+    pub fn target_fps(&self) -> Option<f64> {
+        self.config.target_fps
+    }
+
+    pub fn set_target_fps(&mut self, target_fps: Option<f64>) {
+        self.config.target_fps = target_fps;
+        self.frame_limiter.set_target_fps(target_fps);
+        log::info!("Target FPS set to {target_fps:?}");
+    }
+
+    /// Mode actually in use, which may differ from the configured one, see
+    /// [`GraphicsContext::set_anti_aliasing`].
+    #[must_use]
+    pub fn anti_aliasing(&self) -> AntiAliasing {
+        self.anti_aliasing.mode()
+    }
+
+    /// Rebuilds the scene pipeline and the offscreen targets. Returns the mode actually
+    /// applied, unsupported sample counts fall back as described on [`AntiAliasing`].
+    pub fn set_anti_aliasing(&mut self, anti_aliasing: AntiAliasing) -> AntiAliasing {
+        self.config.anti_aliasing = anti_aliasing;
+        self.rebuild_scene_pipeline();
+
+        self.anti_aliasing.mode()
+    }
+
+    #[must_use]
+    pub fn resolution(&self) -> Resolution {
+        self.config.resolution
+    }
+
+    /// Takes effect with the next frame. Switching to or from pixel art rebuilds the scene
+    /// pipeline and the samplers of every texture.
+    pub fn set_resolution(&mut self, resolution: Resolution) {
+        let pixel_art = resolution.pixel_art_size().is_some();
+        let was_pixel_art = self.config.resolution.pixel_art_size().is_some();
+        self.config.resolution = resolution;
+        if pixel_art != was_pixel_art {
+            self.rebuild_scene_pipeline();
+            self.textures
+                .set_filter(&self.device, texture_filter(resolution));
+        }
+    }
+
+    /// Where the window shows the world with the current [`Resolution`], for converting
+    /// between window pixels and world units.
+    #[must_use]
+    pub fn viewport(&self) -> Viewport {
+        self.config
+            .resolution
+            .viewport((self.surface_config.width, self.surface_config.height))
+    }
+
+    /// World point under the window pixel `position`, as seen through `camera`.
+    #[must_use]
+    pub fn screen_to_world(&self, position: PhysicalPosition<f64>, camera: &Camera) -> [f32; 2] {
+        self.viewport()
+            .screen_to_world([position.x as f32, position.y as f32], camera)
+    }
+
+    /// Window pixel showing the world point `position` through `camera`.
+    #[must_use]
+    pub fn world_to_screen(&self, position: [f32; 2], camera: &Camera) -> PhysicalPosition<f64> {
+        let [x, y] = self.viewport().world_to_screen(position, camera);
+        PhysicalPosition::new(f64::from(x), f64::from(y))
+    }
+
+    #[must_use]
+    pub fn post_effects(&self) -> &[PostEffect] {
+        &self.post_effects
+    }
+
+    /// Effects run in order after the scene. Changes are picked up by the next
+    /// [`GraphicsContext::render`].
+    pub fn post_effects_mut(&mut self) -> &mut Vec<PostEffect> {
+        &mut self.post_effects
+    }
+
+    /// Uploads an sRGB RGBA8 image, `pixels` holds `width * height` tightly packed texels.
+    pub fn create_texture(
+        &mut self,
+        width: u32,
+        height: u32,
+        pixels: Vec<u8>,
+    ) -> anyhow::Result<TextureId> {
+        self.textures
+            .create_from_rgba(&self.device, &self.queue, width, height, pixels)
+    }
+
+    /// Removes a texture or render target. Objects still using it are drawn untextured.
+    pub fn remove_texture(&mut self, texture: TextureId) -> bool {
+        self.render_targets
+            .retain(|target| target.texture() != texture);
+        self.textures.remove(texture)
+    }
+
+    /// Like [`GraphicsContext::create_texture`], but the texels are sampled as they are instead
+    /// of decoded from sRGB, e.g. normal maps.
+    pub fn create_data_texture(
+        &mut self,
+        width: u32,
+        height: u32,
+        pixels: Vec<u8>,
+    ) -> anyhow::Result<TextureId> {
+        self.textures
+            .create_from_data(&self.device, &self.queue, width, height, pixels)
+    }
+
+    #[must_use]
+    pub fn texture_size(&self, texture: TextureId) -> Option<(u32, u32)> {
+        self.textures.size(texture)
+    }
+
+    /// Adds an offscreen target drawing nothing yet, fill [`RenderTarget::objects`] through
+    /// [`GraphicsContext::render_target_mut`]. The returned id doubles as its texture.
+    pub fn create_render_target(&mut self, width: u32, height: u32) -> anyhow::Result<TextureId> {
+        let texture =
+            self.textures
+                .create_render_target(&self.device, &self.queue, width, height)?;
+        self.render_targets
+            .push(RenderTarget::new(&self.device, texture, width, height));
+
+        Ok(texture)
+    }
+
+    #[must_use]
+    pub fn render_target(&self, texture: TextureId) -> Option<&RenderTarget> {
+        self.render_targets
+            .iter()
+            .find(|target| target.texture() == texture)
+    }
+
+    pub fn render_target_mut(&mut self, texture: TextureId) -> Option<&mut RenderTarget> {
+        self.render_targets
+            .iter_mut()
+            .find(|target| target.texture() == texture)
+    }
+
+    /// Parses a TrueType or OpenType font for [`crate::graphics::Text`] objects.
+    pub fn load_font(&mut self, data: Vec<u8>) -> anyhow::Result<FontId> {
+        self.text_renderer.load_font(data)
+    }
+
+    /// Compiles `entry_point` of the WGSL `source`, the bindings are taken from the shader.
+    pub fn create_compute_pipeline(
+        &mut self,
+        source: &str,
+        entry_point: &str,
+    ) -> anyhow::Result<ComputePipelineId> {
+        self.check_compute()?;
+        self.compute
+            .create_pipeline(&self.device, source, entry_point)
+    }
+
+    pub fn remove_compute_pipeline(&mut self, pipeline: ComputePipelineId) -> bool {
+        self.compute.remove_pipeline(pipeline)
+    }
+
+    /// Buffer for [`Dispatch::with_buffer`] starting out with `contents`, rounded up to a
+    /// multiple of 4 bytes. Comes back zeroed after a device loss.
+    pub fn create_storage_buffer(&mut self, contents: &[u8]) -> anyhow::Result<StorageBufferId> {
+        self.check_compute()?;
+        self.compute.create_buffer(&self.device, contents)
+    }
+
+    /// Queued before the next dispatch or frame, `offset` and `data` are multiples of 4 bytes.
+    pub fn write_storage_buffer(
+        &mut self,
+        buffer: StorageBufferId,
+        offset: u64,
+        data: &[u8],
+    ) -> anyhow::Result<()> {
+        self.compute.write_buffer(&self.queue, buffer, offset, data)
+    }
+
+    pub fn remove_storage_buffer(&mut self, buffer: StorageBufferId) -> bool {
+        self.compute.remove_buffer(buffer)
+    }
+
+    /// RGBA8 texture compute shaders write as `texture_storage_2d<rgba8unorm, write>` and
+    /// objects sample like any other, zeroed at first and after a device loss.
+    pub fn create_storage_texture(&mut self, width: u32, height: u32) -> anyhow::Result<TextureId> {
+        self.check_compute()?;
+        self.textures
+            .create_storage(&self.device, &self.queue, width, height)
+    }
+
+    /// Runs `dispatch` right away, ahead of the next frame.
+    pub fn dispatch(&mut self, dispatch: &Dispatch) -> anyhow::Result<()> {
+        self.compute
+            .dispatch(&self.device, &self.queue, &self.textures, dispatch)
+    }
+
+    /// Starts copying `buffer` back, see [`Readback`].
+    pub fn read_storage_buffer(&mut self, buffer: StorageBufferId) -> anyhow::Result<Readback> {
+        self.compute.read_buffer(&self.device, &self.queue, buffer)
+    }
+
+    /// Starts copying the RGBA8 texels of `texture` back, see [`Readback`].
+    pub fn read_texture(&mut self, texture: TextureId) -> anyhow::Result<Readback> {
+        compute::read_texture(&self.device, &self.queue, &self.textures, texture)
+    }
+
+    /// Blocks until `readback` arrived, for tools and tests rather than frames.
+    pub fn wait_for_readback(&self, readback: &mut Readback) -> anyhow::Result<Vec<u8>> {
+        if let Err(err) = self.device.poll(PollType::Wait) {
+            anyhow::bail!("Unable to wait for the GPU: {err}");
+        }
+        readback
+            .try_take()
+            .unwrap_or_else(|| Err(anyhow::anyhow!("Readback was already taken")))
+    }
+
+    /// A zero-sized window (minimized on most platforms) pauses rendering instead of failing;
+    /// the next nonzero size resumes it.
+    pub fn resize_surface(&mut self, width: u32, height: u32) {
         log::debug!("Resizing surface");
 
         if width == 0 || height == 0 {
-            anyhow::bail!(
-                "Invalid size, dimensions have to be nonzero. {{w: {width}, h: {height}}}"
-            );
+            log::debug!("Surface minimized, pausing rendering");
+            if self.surface_status != SurfaceStatus::DeviceLost {
+                self.surface_status = SurfaceStatus::Minimized;
+            }
+            return;
         }
 
         self.surface_config.width = width;
         self.surface_config.height = height;
 
-        self.surface.configure(&self.device, &self.surface_config);
-
-        Ok(())
+        if self.surface_status != SurfaceStatus::DeviceLost {
+            self.configure_surface();
+        }
     }
 
-    pub fn render(&self, state: &mut State) -> anyhow::Result<()> {
+    /// Renders a frame, recovering from lost/outdated surfaces and lost devices along the way.
+    /// Only unrecoverable failures (out of memory, failed device rebuild) are returned as errors,
+    /// objects whose mesh can't be uploaded are logged and skipped.
+    pub fn render(&mut self, state: &mut State) -> anyhow::Result<FrameStatus> {
         log::debug!("Rendering");
 
-        let output = self.surface.get_current_texture()?;
+        if self.device_lost.load(Ordering::Acquire) {
+            self.surface_status = SurfaceStatus::DeviceLost;
+        }
+        match self.surface_status {
+            SurfaceStatus::Ready => {}
+            SurfaceStatus::Minimized => return Ok(FrameStatus::Skipped),
+            SurfaceStatus::NeedsReconfigure => self.configure_surface(),
+            SurfaceStatus::DeviceLost => {
+                self.recreate_device(state)?;
+                return Ok(FrameStatus::DeviceRecreated);
+            }
+        }
+
+        self.graveyard
+            .bury(self.frame_index, state.drain_retired_render_data());
+        if let Err(err) = self.device.poll(PollType::Poll) {
+            log::warn!("Unable to poll device: {err}");
+        }
+        let freed = self.graveyard.collect();
+        self.frame_limiter.wait();
+        if freed > 0 {
+            log::debug!(
+                "Freed GPU resources of {freed} objects, {} still pending, {} live buffers ({} bytes)",
+                self.graveyard.len(),
+                live_buffer_count(),
+                live_buffer_bytes()
+            );
+        }
 
-        let mut encoder = self
-            .device
-            .create_command_encoder(&CommandEncoderDescriptor::default());
-        {
-            let mut render_pass = encoder.begin_render_pass(&RenderPassDescriptor {
-                color_attachments: &[Some(RenderPassColorAttachment {
-                    view: &output
-                        .texture
-                        .create_view(&TextureViewDescriptor::default()),
-                    resolve_target: None,
-                    ops: Operations {
-                        load: LoadOp::Clear(state.clear_color),
-                        store: StoreOp::default(),
-                    },
-                    depth_slice: None,
-                })],
-                ..Default::default()
-            });
-            render_pass.set_pipeline(&self.pipeline);
-            render_pass.set_bind_group(0, self.uniforms.bind_group(), &[]);
-            // TODO: instead of drawing all the objects separately, try keeping object kind/handle and then it's transform in
-            // TODO: keep transforms in separate Vecs, not the entire objects; send transforms as uniforms
-            // TODO: rethink ensure_render_data usage. it's quite strange I think. maybe on state-change not on every render?
-            state.ensure_render_data(&self.device);
-            for obj in &state.render_objects {
-                {
-                    self.queue.write_buffer(
-                        obj.transform_buffer(),
-                        0,
-                        bytemuck::cast_slice(&[obj.transform]),
+        let output = match self.surface.get_current_texture() {
+            Ok(output) => output,
+            Err(SurfaceError::Timeout) => {
+                log::warn!("Timed out acquiring the next frame, skipping it");
+                return Ok(FrameStatus::Skipped);
+            }
+            Err(err @ (SurfaceError::Outdated | SurfaceError::Lost | SurfaceError::Other)) => {
+                log::warn!("Unable to acquire the next frame: {err}");
+                self.reconfigure_attempts += 1;
+                self.surface_status = if self.reconfigure_attempts > MAX_RECONFIGURE_ATTEMPTS {
+                    log::error!(
+                        "Surface still unusable after {MAX_RECONFIGURE_ATTEMPTS} reconfigurations, rebuilding the device"
                     );
-                    render_pass.set_bind_group(1, obj.transform_bind_group(), &[]);
-                }
-                render_pass.set_vertex_buffer(0, obj.vertex_buffer().slice(..));
-                // TODO: consider changing IndexFormat to Uint32
-                render_pass.set_index_buffer(obj.index_buffer().slice(..), IndexFormat::Uint16);
-                render_pass.draw_indexed(0..obj.index_count() as u32, 0, 0..1);
+                    SurfaceStatus::DeviceLost
+                } else {
+                    SurfaceStatus::NeedsReconfigure
+                };
+                return Ok(FrameStatus::Skipped);
+            }
+            Err(SurfaceError::OutOfMemory) => {
+                anyhow::bail!("Out of memory while acquiring the next frame")
             }
+        };
+        self.reconfigure_attempts = 0;
+
+        // TODO: rethink ensure_render_data usage. it's quite strange I think. maybe on state-change not on every render?
+        let viewport = self.viewport();
+        // pixel art renders a view of its own, scaled up into `viewport` afterwards
+        let pixel_art_size = self.config.resolution.pixel_art_size();
+        let scene_viewport = match pixel_art_size {
+            Some(size) => Resolution::window().viewport(size),
+            None => viewport,
+        };
+        self.text_renderer.prepare(
+            &self.device,
+            &self.queue,
+            &mut self.textures,
+            state,
+            scene_viewport.pixels_per_unit(),
+        );
+        state.ensure_render_data(&self.device, &self.queue, &mut self.shared_geometry);
+        self.sync_post_processing();
+        self.sync_lighting(state);
+        self.sync_pixel_art();
+        let camera_uniform = CameraUniform::new(&state.camera, scene_viewport.extent);
+        self.camera_binding.update(
+            &self.queue,
+            match pixel_art_size {
+                Some(size) => camera_uniform.snapped(size),
+                None => camera_uniform,
+            },
+        );
+
+        for target in &mut self.render_targets {
+            let extent = Resolution::window().viewport(target.size()).extent;
+            let uniform = CameraUniform::new(&target.camera, extent);
+            target.camera_binding_mut().update(&self.queue, uniform);
+        }
+
+        let state = &*state;
+        let output_view = output
+            .texture
+            .create_view(&TextureViewDescriptor::default());
+        let size = (self.surface_config.width, self.surface_config.height);
+        let scene_size = pixel_art_size.unwrap_or(size);
+        let visible = visible_objects(
+            state.objects().map(|(_, obj)| obj),
+            &state.camera,
+            scene_viewport.extent,
+        );
+        self.cull_stats = CullStats {
+            drawn: visible.len(),
+            culled: state.objects().count() - visible.len(),
+        };
+        let clear = self.clear_color(state.clear_color);
+        let (textures, globals) = (&self.textures, self.uniforms.bind_group());
+        let shared_geometry = &self.shared_geometry;
+
+        let mut graph = RenderGraph::new();
+        let surface = graph.import("Surface", &output_view);
+        let targets: HashMap<TextureId, ResourceId> = self
+            .render_targets
+            .iter()
+            .filter_map(|target| {
+                let view = textures.view(target.texture())?;
+                Some((target.texture(), graph.import("Render Target", view)))
+            })
+            .collect();
+
+        let target_pipelines = &self.target_pipelines;
+        for target in &self.render_targets {
+            let Some(&resource) = targets.get(&target.texture()) else {
+                continue;
+            };
+            let objects = visible_objects(
+                target.objects.iter().filter_map(|&id| state.object(id)),
+                &target.camera,
+                Resolution::window().viewport(target.size()).extent,
+            );
+            graph
+                .add_pass("Render Target Pass")
+                .reads(sampled_targets(objects.iter().copied(), &targets))
+                .write(resource)
+                .record(move |ctx| {
+                    let mut render_pass = ctx.encoder.begin_render_pass(&RenderPassDescriptor {
+                        label: Some("Render Target Pass"),
+                        color_attachments: &[Some(RenderPassColorAttachment {
+                            view: ctx.view(resource),
+                            resolve_target: None,
+                            ops: Operations {
+                                load: LoadOp::Clear(target.clear_color.into()),
+                                store: StoreOp::Store,
+                            },
+                            depth_slice: None,
+                        })],
+                        ..Default::default()
+                    });
+                    render_pass.set_bind_group(0, globals, &[]);
+                    render_pass.set_bind_group(3, target.camera_binding().bind_group(), &[]);
+                    draw_objects(
+                        &mut render_pass,
+                        target_pipelines,
+                        textures,
+                        shared_geometry,
+                        objects.into_iter(),
+                        Some(target.texture()),
+                    );
+                });
         }
 
-        self.queue.submit(std::iter::once(encoder.finish()));
+        // what the post processor reads, the surface without one
+        let frame = if self.post_processor.is_some() {
+            graph.create_transient("Scene", TransientDesc::color(size.0, size.1, POST_FORMAT))
+        } else {
+            surface
+        };
+        let scene = match &self.pixel_art {
+            Some(pixel_art) => {
+                let (width, height) = scene_size;
+                let desc = TransientDesc::color(width, height, pixel_art.format());
+                graph.create_transient("Pixel Art Scene", desc)
+            }
+            None => frame,
+        };
+        let (pipelines, camera) = (&self.pipelines, self.camera_binding.bind_group());
+        let visible = &visible;
+        let objects = || visible.iter().copied();
+        self.anti_aliasing.add_scene_passes(
+            &mut graph,
+            scene,
+            scene_size,
+            sampled_targets(objects(), &targets),
+            clear,
+            move |render_pass| {
+                scene_viewport.apply(render_pass);
+                render_pass.set_bind_group(0, globals, &[]);
+                render_pass.set_bind_group(3, camera, &[]);
+                // TODO: instead of drawing all the objects separately, try keeping object kind/handle and then it's transform in
+                // TODO: keep transforms in separate Vecs, not the entire objects; send transforms as uniforms
+                draw_objects(
+                    render_pass,
+                    pipelines,
+                    textures,
+                    shared_geometry,
+                    objects(),
+                    None,
+                );
+            },
+        );
+        if let Some(lighting) = &self.lighting {
+            lighting.add_passes(
+                &mut graph,
+                LightingInputs {
+                    textures,
+                    shared_geometry,
+                    camera,
+                    size: scene_size,
+                    viewport: scene_viewport,
+                },
+                visible,
+                scene,
+                sampled_targets(objects(), &targets),
+            );
+        }
+        if let Some(pixel_art) = &self.pixel_art {
+            pixel_art.add_pass(&mut graph, scene, frame, viewport);
+        }
+        if let Some(post_processor) = &self.post_processor {
+            post_processor.add_passes(&mut graph, globals, frame, surface, size);
+        }
+        self.letterbox
+            .add_pass(&mut graph, surface, viewport.bars(size));
+
+        let command_buffers = graph.execute(&self.device, &mut self.transient_pool);
+        self.queue.submit(command_buffers);
+        self.graveyard
+            .track_submission(&self.queue, self.frame_index);
+        self.frame_index += 1;
+
+        if output.suboptimal {
+            log::debug!("Surface is suboptimal, reconfiguring before the next frame");
+            self.surface_status = SurfaceStatus::NeedsReconfigure;
+        }
         output.present();
 
-        Ok(())
+        Ok(FrameStatus::Presented)
+    }
+
+    /// Objects of the window's view the last frame drew and culled.
+    #[must_use]
+    pub fn cull_stats(&self) -> CullStats {
+        self.cull_stats
     }
 
     pub fn update_uniform(&mut self, uniform: UniformKind) {
         self.uniforms.update(&self.queue, uniform);
     }
+
+    fn check_compute(&self) -> anyhow::Result<()> {
+        let flags = self.adapter.get_downlevel_capabilities().flags;
+        if !flags.contains(DownlevelFlags::COMPUTE_SHADERS) {
+            anyhow::bail!(
+                "{} doesn't support compute shaders",
+                self.adapter.get_info().name
+            );
+        }
+
+        Ok(())
+    }
+
+    /// Clears bypass the shader, so they get the same encoding the fragment shader applies.
+    fn clear_color(&self, color: Color) -> wgpu::Color {
+        let color = if self.surface_config.alpha_mode == CompositeAlphaMode::PreMultiplied {
+            Color::linear_rgba(
+                color.r * color.a,
+                color.g * color.a,
+                color.b * color.a,
+                color.a,
+            )
+        } else {
+            color
+        };
+        if pipeline::encodes_srgb_in_shader(self.scene_format()) {
+            let [r, g, b, a] = color.to_srgba();
+            Color::linear_rgba(r, g, b, a).into()
+        } else {
+            color.into()
+        }
+    }
+
+    /// Format the scene pipeline renders in, the post-processing one while effects are active.
+    fn scene_format(&self) -> TextureFormat {
+        if self.post_processor.is_some() {
+            POST_FORMAT
+        } else {
+            self.surface_config.format
+        }
+    }
+
+    /// Recreates the anti-aliasing targets and the scene pipeline for the current
+    /// [`GraphicsContext::scene_format`] and anti-aliasing config.
+    fn rebuild_scene_pipeline(&mut self) {
+        self.anti_aliasing = AntiAliasingPass::new(
+            &self.device,
+            &self.adapter,
+            self.scene_format(),
+            scene_anti_aliasing(&self.config),
+        );
+        self.pipelines = create_pipelines(
+            &self.device,
+            self.scene_format(),
+            self.anti_aliasing.sample_count(),
+            &self.uniforms,
+            &self.textures,
+        );
+    }
+
+    /// Creates the post processor when the first effect is added and drops it with the last
+    /// one, then hands it the current effects.
+    fn sync_post_processing(&mut self) {
+        let active = !self.post_effects.is_empty();
+        if active != self.post_processor.is_some() {
+            self.post_processor = active.then(|| {
+                PostProcessor::new(
+                    &self.device,
+                    self.surface_config.format,
+                    self.uniforms.layout(),
+                )
+            });
+            self.rebuild_scene_pipeline();
+        }
+        if let Some(post_processor) = &mut self.post_processor {
+            post_processor.sync(&self.device, &self.queue, &self.post_effects);
+        }
+    }
+
+    /// Creates the lighting pass for the current [`GraphicsContext::scene_format`] while the
+    /// state has lighting, then hands it this frame's lights and occluders.
+    fn sync_lighting(&mut self, state: &State) {
+        let Some(lighting) = &state.lighting else {
+            self.lighting = None;
+            return;
+        };
+        let format = self.scene_format();
+        let lighting_pass = match &mut self.lighting {
+            Some(lighting_pass) if lighting_pass.format() == format => lighting_pass,
+            lighting_pass => {
+                lighting_pass.insert(LightingPass::new(&self.device, format, &self.textures))
+            }
+        };
+        lighting_pass.prepare(&self.device, &self.queue, state, lighting);
+    }
+
+    /// Creates the pixel art pass for the current [`GraphicsContext::scene_format`] while the
+    /// resolution asks for pixel art.
+    fn sync_pixel_art(&mut self) {
+        if self.config.resolution.pixel_art_size().is_none() {
+            self.pixel_art = None;
+            return;
+        }
+        let format = self.scene_format();
+        if self
+            .pixel_art
+            .as_ref()
+            .is_none_or(|pixel_art| pixel_art.format() != format)
+        {
+            self.pixel_art = Some(PixelArtPass::new(&self.device, format));
+        }
+    }
+
+    fn configure_surface(&mut self) {
+        self.surface.configure(&self.device, &self.surface_config);
+        self.uniforms.update(
+            &self.queue,
+            UniformKind::Surface(SurfaceSizeUniform::new(
+                self.surface_config.width as f32,
+                self.surface_config.height as f32,
+            )),
+        );
+        self.surface_status = SurfaceStatus::Ready;
+    }
+
+    /// Rebuilds the device and everything created from it. Objects re-upload their meshes on
+    /// the next [`State::ensure_render_data`].
+    fn recreate_device(&mut self, state: &mut State) -> anyhow::Result<()> {
+        log::warn!("Recreating the GPU device");
+
+        state.forget_render_data();
+        self.graveyard = Graveyard::default();
+
+        let adapter = pipeline::request_adapter(&self.instance, &self.surface, &self.config)?;
+        let (device, queue) = pipeline::request_device(&adapter, &self.config)?;
+        self.adapter_report = AdapterReport::new(&adapter, &device, &self.config);
+        log::info!("Using adapter: {}", self.adapter_report);
+        // a different adapter may offer different formats, so the surface config starts over
+        self.surface_capabilities = self.surface.get_capabilities(&adapter);
+        self.surface_config = pipeline::create_surface_config(
+            &self.window,
+            &self.surface_capabilities,
+            &self.config,
+        )?;
+        self.device_lost = watch_device_lost(&device);
+        self.uniforms = GlobalUniforms::new(&device);
+        self.textures.recreate(&device, &queue);
+        self.shared_geometry = SharedGeometry::new(&device, &adapter);
+        self.compute.recreate(&device);
+        self.camera_binding = CameraBinding::new(&device, "Surface");
+        self.letterbox = LetterboxPass::new(&device, self.surface_config.format);
+        for target in &mut self.render_targets {
+            target.recreate(&device);
+        }
+        self.target_pipelines =
+            create_pipelines(&device, TEXTURE_FORMAT, 1, &self.uniforms, &self.textures);
+        self.adapter = adapter;
+        self.device = device;
+        self.queue = queue;
+        self.reconfigure_attempts = 0;
+        // recreated for the new surface format by the next frame
+        self.post_processor = None;
+        self.lighting = None;
+        self.pixel_art = None;
+        self.transient_pool = TransientPool::default();
+        self.rebuild_scene_pipeline();
+
+        let size = self.window.inner_size();
+        self.resize_surface(size.width, size.height);
+
+        Ok(())
+    }
+}
+
+/// Pixel art blurs at the edges with anti-aliasing, so it goes without.
+fn scene_anti_aliasing(config: &GraphicsConfig) -> AntiAliasing {
+    match config.resolution.pixel_art_size() {
+        Some(_) => AntiAliasing::None,
+        None => config.anti_aliasing,
+    }
+}
+
+fn texture_filter(resolution: Resolution) -> FilterMode {
+    match resolution.pixel_art_size() {
+        Some(_) => FilterMode::Nearest,
+        None => FilterMode::Linear,
+    }
+}
+
+/// Objects textured with `target` itself are skipped, a texture can't be sampled while it's
+/// being rendered into.
+fn draw_objects<'a>(
+    render_pass: &mut RenderPass<'_>,
+    pipelines: &ObjectPipelines,
+    textures: &TextureRegistry,
+    shared_geometry: &'a SharedGeometry,
+    objects: impl Iterator<Item = &'a RenderObject>,
+    target: Option<TextureId>,
+) {
+    let mut bound_pipeline = None;
+    let mut bound_texture = None;
+    let mut bound_buffer = None;
+    for obj in objects {
+        let instances = match obj.instance_buffer() {
+            Some(_) => obj.instance_count(),
+            None => 1,
+        };
+        if obj.index_count() == 0 || instances == 0 {
+            continue;
+        }
+        if target.is_some() && obj.texture == target {
+            log::debug!(
+                "Skipping {:?}, it samples the target it's drawn into",
+                obj.name
+            );
+            continue;
+        }
+        let pipeline = (
+            obj.particles.is_some(),
+            std::mem::discriminant(&obj.material),
+        );
+        if bound_pipeline != Some(pipeline) {
+            render_pass.set_pipeline(match obj.particles {
+                Some(_) => pipelines.particles(),
+                None => pipelines.get(&obj.material),
+            });
+            bound_pipeline = Some(pipeline);
+        }
+        render_pass.set_bind_group(1, obj.transform_bind_group(), &[]);
+        if bound_texture != Some(obj.texture) {
+            render_pass.set_bind_group(2, textures.bind_group(obj.texture), &[]);
+            bound_texture = Some(obj.texture);
+        }
+        if let Some(instance_buffer) = obj.instance_buffer() {
+            render_pass.set_vertex_buffer(1, instance_buffer.slice(..));
+        }
+        obj.geometry(shared_geometry)
+            .draw(render_pass, &mut bound_buffer, 0..instances);
+    }
+}
+
+/// Objects within `extent` world units around `camera`, in drawing order as regrouped by
+/// [`batching::sort`].
+fn visible_objects<'a>(
+    objects: impl Iterator<Item = &'a RenderObject>,
+    camera: &Camera,
+    extent: [f32; 2],
+) -> Vec<&'a RenderObject> {
+    let visible = objects
+        .filter_map(|obj| {
+            let bounds = culling::view_bounds(obj.bounds()?, &obj.transform, camera, extent);
+            culling::is_visible(bounds).then_some((obj, bounds))
+        })
+        .collect();
+    batching::sort(visible)
+}
+
+/// Render targets the objects are textured with, which their pass has to wait for.
+fn sampled_targets<'a>(
+    objects: impl Iterator<Item = &'a RenderObject>,
+    targets: &HashMap<TextureId, ResourceId>,
+) -> Vec<ResourceId> {
+    objects
+        .filter_map(|obj| targets.get(&obj.texture?).copied())
+        .collect()
+}
+
+fn create_pipelines(
+    device: &Device,
+    format: TextureFormat,
+    sample_count: u32,
+    uniforms: &GlobalUniforms,
+    textures: &TextureRegistry,
+) -> ObjectPipelines {
+    pipeline::create_render_pipelines(
+        device,
+        format,
+        sample_count,
+        &[
+            uniforms.layout(),
+            &RenderObject::bind_group_layout(device),
+            textures.layout(),
+            &Camera::bind_group_layout(device),
+        ],
+    )
+}
+
+/// Flag raised by the device-lost callback, checked at the start of every frame.
+fn watch_device_lost(device: &Device) -> Arc<AtomicBool> {
+    let device_lost = Arc::new(AtomicBool::new(false));
+    let flag = device_lost.clone();
+    device.set_device_lost_callback(move |reason, message| {
+        log::error!("GPU device lost ({reason:?}): {message}");
+        flag.store(true, Ordering::Release);
+    });
+
+    device_lost
 }
@@ -0,0 +1,61 @@
+use crate::graphics::{Camera, Transform};
+
+/// Objects the last frame drew and skipped for being outside the window's view.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct CullStats {
+    pub drawn: usize,
+    pub culled: usize,
+}
+
+/// Box around `bounds`, a `[min, max]` box in mesh units, in a view showing `extent` world
+/// units around `camera`, see [`crate::graphics::Viewport`]. The view spans -1 to 1, vertices
+/// are placed like `vs_main` in `basic.wgsl` does, and rotated boxes are boxed again.
+#[must_use]
+pub fn view_bounds(
+    [min, max]: [[f32; 2]; 2],
+    transform: &Transform,
+    camera: &Camera,
+    extent: [f32; 2],
+) -> [[f32; 2]; 2] {
+    let corners = [
+        [min[0], min[1]],
+        [max[0], min[1]],
+        [max[0], max[1]],
+        [min[0], max[1]],
+    ];
+
+    let [first, rest @ ..] = corners.map(|corner| {
+        let [x, y] = camera.world_to_view(transform.apply(corner));
+        [x / extent[0], y / extent[1]]
+    });
+    rest.into_iter().fold([first, first], |bounds, corner| {
+        union(Some(bounds), [corner, corner])
+    })
+}
+
+/// Whether anything within `view_bounds`, see [`view_bounds`], lands inside the view.
+#[must_use]
+pub fn is_visible(view_bounds: [[f32; 2]; 2]) -> bool {
+    overlaps(view_bounds, [[-1.0, -1.0], [1.0, 1.0]])
+}
+
+/// Whether two `[min, max]` boxes share any point.
+#[must_use]
+pub fn overlaps([min, max]: [[f32; 2]; 2], [other_min, other_max]: [[f32; 2]; 2]) -> bool {
+    min[0] <= other_max[0]
+        && max[0] >= other_min[0]
+        && min[1] <= other_max[1]
+        && max[1] >= other_min[1]
+}
+
+/// Box around `bounds` and `other`.
+#[must_use]
+pub fn union(bounds: Option<[[f32; 2]; 2]>, other: [[f32; 2]; 2]) -> [[f32; 2]; 2] {
+    let Some([min, max]) = bounds else {
+        return other;
+    };
+    [
+        [min[0].min(other[0][0]), min[1].min(other[0][1])],
+        [max[0].max(other[1][0]), max[1].max(other[1][1])],
+    ]
+}
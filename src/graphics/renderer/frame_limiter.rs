@@ -0,0 +1,58 @@
+use std::time::{Duration, Instant};
+
+/// Below this much remaining time the limiter spins instead of sleeping, OS sleep granularity
+/// would otherwise overshoot the deadline.
+const SPIN_THRESHOLD: Duration = Duration::from_micros(1500);
+
+/// Caps the frame rate by sleeping until the next frame deadline.
+pub struct FrameLimiter {
+    frame_time: Option<Duration>,
+    next_frame: Instant,
+}
+impl FrameLimiter {
+    #[must_use]
+    pub fn new(target_fps: Option<f64>) -> Self {
+        let mut limiter = Self {
+            frame_time: None,
+            next_frame: Instant::now(),
+        };
+        limiter.set_target_fps(target_fps);
+        limiter
+    }
+
+    #[must_use]
+    pub fn target_fps(&self) -> Option<f64> {
+        self.frame_time
+            .map(|frame_time| 1.0 / frame_time.as_secs_f64())
+    }
+
+    /// `None`, zero, negative or non-finite values disable the limiter.
+    pub fn set_target_fps(&mut self, target_fps: Option<f64>) {
+        self.frame_time = target_fps
+            .filter(|fps| fps.is_finite() && *fps > 0.0)
+            .map(|fps| Duration::from_secs_f64(1.0 / fps));
+        self.next_frame = Instant::now();
+    }
+
+    /// Blocks until the current frame's deadline and schedules the next one.
+    pub fn wait(&mut self) {
+        let Some(frame_time) = self.frame_time else {
+            return;
+        };
+
+        let remaining = self.next_frame.saturating_duration_since(Instant::now());
+        if remaining > SPIN_THRESHOLD {
+            std::thread::sleep(remaining - SPIN_THRESHOLD);
+        }
+        while Instant::now() < self.next_frame {
+            std::hint::spin_loop();
+        }
+
+        let now = Instant::now();
+        self.next_frame += frame_time;
+        // after a hitch, resynchronise instead of rushing out frames to catch up
+        if self.next_frame < now {
+            self.next_frame = now + frame_time;
+        }
+    }
+}
@@ -0,0 +1,224 @@
+use std::collections::HashMap;
+
+use ab_glyph::GlyphId;
+use wgpu::{Device, Queue};
+
+use crate::graphics::{
+    renderer::texture::{TextureId, TextureRegistry},
+    text::FontId,
+};
+
+const INITIAL_SIZE: u32 = 256;
+/// Atlases aren't grown past this even where the device would allow it.
+const MAX_SIZE: u32 = 4096;
+/// Empty texels between glyphs, so linear filtering doesn't bleed neighbours in.
+const PADDING: u32 = 1;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct GlyphKey {
+    pub font: FontId,
+    pub glyph: GlyphId,
+    /// Font size in whole pixels.
+    pub px: u32,
+}
+
+/// Glyph as it's put into the atlas.
+pub struct GlyphBitmap {
+    pub width: u32,
+    pub height: u32,
+    /// RGBA8 rows without padding.
+    pub pixels: Vec<u8>,
+    /// Top left corner relative to the pen position on the baseline, in pixels.
+    pub offset: [f32; 2],
+}
+
+/// Where a rasterized glyph sits in the atlas.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct AtlasGlyph {
+    pub origin: [u32; 2],
+    pub size: [u32; 2],
+    /// Top left corner relative to the pen position on the baseline, in pixels.
+    pub offset: [f32; 2],
+}
+
+struct Shelf {
+    y: u32,
+    height: u32,
+    /// Start of the free space.
+    x: u32,
+}
+
+/// Glyph bitmaps packed into shelves of one texture. Glyphs are rasterized on first use; a
+/// full atlas doubles in size and, at its largest, starts over. Either bumps
+/// [`GlyphAtlas::generation`], as the texture coordinates handed out so far no longer hold.
+pub struct GlyphAtlas {
+    /// Texels are data (distance fields) rather than sRGB colors.
+    data: bool,
+    texture: Option<TextureId>,
+    width: u32,
+    height: u32,
+    shelves: Vec<Shelf>,
+    /// `None` for glyphs without an outline, like spaces.
+    glyphs: HashMap<GlyphKey, Option<AtlasGlyph>>,
+    generation: u64,
+}
+impl GlyphAtlas {
+    #[must_use]
+    pub fn new(data: bool) -> Self {
+        Self {
+            data,
+            texture: None,
+            width: INITIAL_SIZE,
+            height: INITIAL_SIZE,
+            shelves: Vec::new(),
+            glyphs: HashMap::new(),
+            generation: 0,
+        }
+    }
+
+    /// `None` until the first glyph is rasterized.
+    #[must_use]
+    pub fn texture(&self) -> Option<TextureId> {
+        self.texture
+    }
+
+    #[must_use]
+    pub fn size(&self) -> (u32, u32) {
+        (self.width, self.height)
+    }
+
+    #[must_use]
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    /// Looks the glyph up, putting what `rasterize` returns into the atlas on first use.
+    pub fn glyph(
+        &mut self,
+        device: &Device,
+        queue: &Queue,
+        textures: &mut TextureRegistry,
+        key: GlyphKey,
+        rasterize: impl FnOnce() -> Option<GlyphBitmap>,
+    ) -> anyhow::Result<Option<AtlasGlyph>> {
+        if let Some(glyph) = self.glyphs.get(&key) {
+            return Ok(*glyph);
+        }
+
+        let Some(GlyphBitmap {
+            width,
+            height,
+            pixels,
+            offset,
+        }) = rasterize()
+        else {
+            self.glyphs.insert(key, None);
+            return Ok(None);
+        };
+
+        let texture = match self.texture {
+            Some(texture) if textures.contains(texture) => texture,
+            _ => self.create_texture(device, queue, textures)?,
+        };
+        let origin = self.allocate(device, queue, textures, texture, width, height)?;
+        textures.write_rgba_region(queue, texture, origin, (width, height), &pixels)?;
+
+        let glyph = AtlasGlyph {
+            origin: [origin.0, origin.1],
+            size: [width, height],
+            offset,
+        };
+        self.glyphs.insert(key, Some(glyph));
+
+        Ok(Some(glyph))
+    }
+
+    fn create_texture(
+        &mut self,
+        device: &Device,
+        queue: &Queue,
+        textures: &mut TextureRegistry,
+    ) -> anyhow::Result<TextureId> {
+        *self = Self {
+            generation: self.generation + 1,
+            ..Self::new(self.data)
+        };
+        let pixels = vec![0; (INITIAL_SIZE * INITIAL_SIZE * 4) as usize];
+        let texture = if self.data {
+            textures.create_from_data(device, queue, INITIAL_SIZE, INITIAL_SIZE, pixels)?
+        } else {
+            textures.create_from_rgba(device, queue, INITIAL_SIZE, INITIAL_SIZE, pixels)?
+        };
+        self.texture = Some(texture);
+
+        Ok(texture)
+    }
+
+    /// Finds room for a `width` x `height` glyph, growing or clearing the atlas if there's
+    /// none left.
+    fn allocate(
+        &mut self,
+        device: &Device,
+        queue: &Queue,
+        textures: &mut TextureRegistry,
+        texture: TextureId,
+        width: u32,
+        height: u32,
+    ) -> anyhow::Result<(u32, u32)> {
+        let max_size = MAX_SIZE.min(device.limits().max_texture_dimension_2d);
+        loop {
+            if let Some(origin) = self.find_space(width + PADDING, height + PADDING) {
+                return Ok(origin);
+            }
+
+            if self.width < max_size || self.height < max_size {
+                if self.height > self.width {
+                    self.width = (self.width * 2).min(max_size);
+                } else {
+                    self.height = (self.height * 2).min(max_size);
+                }
+                log::debug!("Growing the glyph atlas to {}x{}", self.width, self.height);
+                textures.resize_image(device, queue, texture, self.width, self.height)?;
+                // texture coordinates are normalized, so every glyph handed out moved
+                self.generation += 1;
+            } else if !self.shelves.is_empty() {
+                log::debug!("Glyph atlas full, starting over");
+                self.shelves.clear();
+                self.glyphs.clear();
+                self.generation += 1;
+            } else {
+                anyhow::bail!("Glyph of {width}x{height} doesn't fit a {max_size}px atlas");
+            }
+        }
+    }
+
+    /// The shortest shelf tall enough with room left, or a new one below the others.
+    fn find_space(&mut self, width: u32, height: u32) -> Option<(u32, u32)> {
+        let atlas_width = self.width;
+        if let Some(shelf) = self
+            .shelves
+            .iter_mut()
+            .filter(|shelf| shelf.height >= height && shelf.x + width <= atlas_width)
+            .min_by_key(|shelf| shelf.height)
+        {
+            let origin = (shelf.x, shelf.y);
+            shelf.x += width;
+            return Some(origin);
+        }
+
+        let y = self
+            .shelves
+            .last()
+            .map_or(0, |shelf| shelf.y + shelf.height);
+        if width > self.width || y + height > self.height {
+            return None;
+        }
+        self.shelves.push(Shelf {
+            y,
+            height,
+            x: width,
+        });
+
+        Some((0, y))
+    }
+}
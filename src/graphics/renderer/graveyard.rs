@@ -0,0 +1,63 @@
+use std::{
+    collections::VecDeque,
+    sync::{
+        Arc,
+        atomic::{AtomicU64, Ordering},
+    },
+};
+
+use wgpu::Queue;
+
+use crate::graphics::RenderData;
+
+/// Holds GPU resources of removed objects until every frame that could still reference them has
+/// finished on the GPU, then destroys them.
+#[derive(Default)]
+pub struct Graveyard {
+    /// Number of frames the GPU has completed, bumped from `on_submitted_work_done`.
+    completed_frames: Arc<AtomicU64>,
+    buried: VecDeque<(u64, RenderData)>,
+}
+impl Graveyard {
+    /// Resources retired before recording `frame` are used at most by `frame - 1`.
+    pub fn bury(&mut self, frame: u64, render_data: impl IntoIterator<Item = RenderData>) {
+        self.buried.extend(
+            render_data
+                .into_iter()
+                .map(|render_data| (frame, render_data)),
+        );
+    }
+
+    pub fn track_submission(&self, queue: &Queue, frame: u64) {
+        let completed_frames = self.completed_frames.clone();
+        queue.on_submitted_work_done(move || {
+            completed_frames.fetch_max(frame + 1, Ordering::AcqRel);
+        });
+    }
+
+    /// Destroys whatever is safe to destroy and returns how many objects' resources were freed.
+    pub fn collect(&mut self) -> usize {
+        let completed_frames = self.completed_frames.load(Ordering::Acquire);
+        let mut freed = 0;
+        while let Some((frame, _)) = self.buried.front() {
+            if *frame > completed_frames {
+                break;
+            }
+            let (_, render_data) = self.buried.pop_front().unwrap();
+            render_data.destroy();
+            freed += 1;
+        }
+
+        freed
+    }
+
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.buried.len()
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.buried.is_empty()
+    }
+}
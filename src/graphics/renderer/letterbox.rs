@@ -0,0 +1,91 @@
+use wgpu::*;
+
+use crate::graphics::renderer::render_graph::{RenderGraph, ResourceId};
+
+/// Paints the parts of the surface outside the view black, after everything else drew there.
+pub struct LetterboxPass {
+    pipeline: RenderPipeline,
+    format: TextureFormat,
+}
+impl LetterboxPass {
+    #[must_use]
+    pub fn new(device: &Device, format: TextureFormat) -> Self {
+        let module = device.create_shader_module(ShaderModuleDescriptor {
+            label: Some("Letterbox Shader"),
+            source: ShaderSource::Wgsl(
+                concat!(
+                    include_str!("../shaders/fullscreen.wgsl"),
+                    include_str!("../shaders/letterbox.wgsl")
+                )
+                .into(),
+            ),
+        });
+        let pipeline = device.create_render_pipeline(&RenderPipelineDescriptor {
+            label: Some("Letterbox Pipeline"),
+            layout: None,
+            vertex: VertexState {
+                module: &module,
+                entry_point: Some("vs_fullscreen"),
+                compilation_options: PipelineCompilationOptions::default(),
+                buffers: &[],
+            },
+            primitive: PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: MultisampleState::default(),
+            fragment: Some(FragmentState {
+                module: &module,
+                entry_point: Some("fs_bar"),
+                compilation_options: PipelineCompilationOptions::default(),
+                targets: &[Some(ColorTargetState {
+                    format,
+                    blend: None,
+                    write_mask: ColorWrites::ALL,
+                })],
+            }),
+            multiview: None,
+            cache: None,
+        });
+
+        Self { pipeline, format }
+    }
+
+    #[must_use]
+    pub fn format(&self) -> TextureFormat {
+        self.format
+    }
+
+    /// Fills `bars`, `[x, y, width, height]` rectangles of `surface`.
+    pub fn add_pass<'a>(
+        &'a self,
+        graph: &mut RenderGraph<'a>,
+        surface: ResourceId,
+        bars: Vec<[u32; 4]>,
+    ) {
+        if bars.is_empty() {
+            return;
+        }
+        graph
+            .add_pass("Letterbox Pass")
+            .write(surface)
+            .record(move |ctx| {
+                let mut render_pass = ctx.encoder.begin_render_pass(&RenderPassDescriptor {
+                    label: Some("Letterbox Pass"),
+                    color_attachments: &[Some(RenderPassColorAttachment {
+                        view: ctx.view(surface),
+                        resolve_target: None,
+                        ops: Operations {
+                            load: LoadOp::Load,
+                            store: StoreOp::Store,
+                        },
+                        depth_slice: None,
+                    })],
+                    ..Default::default()
+                });
+                render_pass.set_pipeline(&self.pipeline);
+                for [x, y, width, height] in bars {
+                    render_pass.set_scissor_rect(x, y, width, height);
+                    render_pass.draw(0..3, 0..1);
+                }
+            });
+    }
+}
@@ -0,0 +1,675 @@
+use std::{
+    collections::{HashMap, HashSet},
+    ops::Range,
+};
+
+use bytemuck::{Pod, Zeroable};
+use wgpu::*;
+
+use crate::{
+    app::{ObjectId, State},
+    graphics::{
+        Camera, Light, LightKind, Lighting, Material, RenderObject, Vertex,
+        renderer::{
+            buffer::GrowableBuffer,
+            pipeline::encodes_srgb_in_shader,
+            render_graph::{RenderGraph, ResourceId, TransientDesc},
+            resolution::Viewport,
+            shared_geometry::SharedGeometry,
+            texture::TextureRegistry,
+        },
+    },
+};
+
+/// Lights drawn per frame, each marks its shadows in the stencil buffer with a value of its
+/// own. Lights past this are skipped.
+pub const MAX_LIGHTS: usize = u8::MAX as usize;
+
+const NORMAL_FORMAT: TextureFormat = TextureFormat::Rgba8Unorm;
+/// Linear and above 1.0, so bright lights can overexpose.
+const LIGHT_FORMAT: TextureFormat = TextureFormat::Rgba16Float;
+const STENCIL_FORMAT: TextureFormat = TextureFormat::Stencil8;
+/// How far shadows reach past their edge, in light radii. Only edges almost in line with the
+/// light get a shadow shorter than the light's reach.
+const SHADOW_LENGTH: f32 = 1000.0;
+
+/// Laid out like `LightInput` in `lighting.wgsl`.
+#[repr(C)]
+#[derive(Clone, Copy, Pod, Zeroable)]
+struct LightInstance {
+    position: [f32; 2],
+    color: [f32; 4],
+    params: [f32; 4],
+    cone: [f32; 4],
+}
+impl LightInstance {
+    const ATTRIBUTES: &[VertexAttribute] = &vertex_attr_array![
+        0 => Float32x2,
+        1 => Float32x4,
+        2 => Float32x4,
+        3 => Float32x4,
+    ];
+
+    fn new(light: &Light) -> Self {
+        let cone = match light.kind {
+            LightKind::Point => [1.0, 0.0, -2.0, -2.0],
+            LightKind::Spot {
+                direction,
+                inner_angle,
+                outer_angle,
+            } => {
+                let outer_angle = outer_angle.abs();
+                let inner_angle = inner_angle.abs().min(outer_angle);
+                [
+                    direction.cos(),
+                    direction.sin(),
+                    inner_angle.cos(),
+                    outer_angle.cos(),
+                ]
+            }
+        };
+        let color = light.color;
+        let intensity = light.intensity.max(0.0);
+        Self {
+            position: light.position,
+            color: [
+                color.r * intensity,
+                color.g * intensity,
+                color.b * intensity,
+                1.0,
+            ],
+            params: [
+                light.radius,
+                light.falloff.max(0.0),
+                light.elevation.clamp(0.05, 1.5).tan(),
+                0.0,
+            ],
+            cone,
+        }
+    }
+}
+
+/// Outline of an occluder's mesh, kept until the mesh changes.
+struct CachedOutline {
+    vertices_revision: u64,
+    indices_revision: u64,
+    edges: Vec<[[f32; 2]; 2]>,
+}
+
+/// The scene [`LightingPass::add_passes`] lights and what its objects are drawn with.
+#[derive(Clone, Copy)]
+pub struct LightingInputs<'a> {
+    pub textures: &'a TextureRegistry,
+    pub shared_geometry: &'a SharedGeometry,
+    /// Camera of the window's view.
+    pub camera: &'a BindGroup,
+    /// Of the scene texture, in pixels.
+    pub size: (u32, u32),
+    /// Where the view lands in the scene texture.
+    pub viewport: Viewport,
+}
+
+/// Draws the [`Lighting`] of the window's scene: a normal buffer of the objects, the lights
+/// added up over the ambient color with the shadows of occluders left out, and the sum
+/// multiplied into the scene.
+pub struct LightingPass {
+    /// Format of the scene the composite pipeline writes.
+    format: TextureFormat,
+    normal_flat: RenderPipeline,
+    normal_mapped: RenderPipeline,
+    normal_unlit: RenderPipeline,
+    shadow: RenderPipeline,
+    light: RenderPipeline,
+    composite: RenderPipeline,
+    texture_layout: BindGroupLayout,
+    light_buffer: GrowableBuffer,
+    shadow_buffer: GrowableBuffer,
+    outlines: HashMap<ObjectId, CachedOutline>,
+    ambient: wgpu::Color,
+    /// Vertices of the shadows of each light in `shadow_buffer`.
+    shadow_ranges: Vec<Range<u32>>,
+    warned_light_limit: bool,
+}
+impl LightingPass {
+    /// `format` is the format of the view the scene ends up in.
+    pub fn new(device: &Device, format: TextureFormat, textures: &TextureRegistry) -> Self {
+        let texture_layout = device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+            label: Some("Lighting Texture Bind Group Layout"),
+            entries: &[BindGroupLayoutEntry {
+                binding: 0,
+                visibility: ShaderStages::FRAGMENT,
+                ty: BindingType::Texture {
+                    sample_type: TextureSampleType::Float { filterable: false },
+                    view_dimension: TextureViewDimension::D2,
+                    multisampled: false,
+                },
+                count: None,
+            }],
+        });
+        let camera_layout = Camera::bind_group_layout(device);
+
+        let normals_module = device.create_shader_module(ShaderModuleDescriptor {
+            label: Some("Normals Shader"),
+            source: ShaderSource::Wgsl(include_str!("../shaders/normals.wgsl").into()),
+        });
+        let normals_layout = device.create_pipeline_layout(&PipelineLayoutDescriptor {
+            label: Some("Normals Pipeline Layout"),
+            bind_group_layouts: &[
+                textures.layout(),
+                &RenderObject::bind_group_layout(device),
+                textures.layout(),
+                &camera_layout,
+            ],
+            push_constant_ranges: &[],
+        });
+        let normal_pipeline = |label: &str, entry_point: &str| {
+            device.create_render_pipeline(&RenderPipelineDescriptor {
+                label: Some(label),
+                layout: Some(&normals_layout),
+                vertex: VertexState {
+                    module: &normals_module,
+                    entry_point: Some("vs_normals"),
+                    compilation_options: PipelineCompilationOptions::default(),
+                    buffers: &[Vertex::vertex_buffer_layout()],
+                },
+                primitive: PrimitiveState::default(),
+                depth_stencil: None,
+                multisample: MultisampleState::default(),
+                fragment: Some(FragmentState {
+                    module: &normals_module,
+                    entry_point: Some(entry_point),
+                    compilation_options: PipelineCompilationOptions::default(),
+                    targets: &[Some(ColorTargetState {
+                        format: NORMAL_FORMAT,
+                        blend: Some(BlendState::ALPHA_BLENDING),
+                        write_mask: ColorWrites::ALL,
+                    })],
+                }),
+                multiview: None,
+                cache: None,
+            })
+        };
+
+        let lighting_module = device.create_shader_module(ShaderModuleDescriptor {
+            label: Some("Lighting Shader"),
+            source: ShaderSource::Wgsl(
+                concat!(
+                    include_str!("../shaders/fullscreen.wgsl"),
+                    include_str!("../shaders/lighting.wgsl")
+                )
+                .into(),
+            ),
+        });
+        let lighting_layout = device.create_pipeline_layout(&PipelineLayoutDescriptor {
+            label: Some("Lighting Pipeline Layout"),
+            bind_group_layouts: &[&camera_layout, &texture_layout, &texture_layout],
+            push_constant_ranges: &[],
+        });
+        let constants = [(
+            "ENCODE_SRGB",
+            f64::from(u8::from(encodes_srgb_in_shader(format))),
+        )];
+        let stencil = |compare, pass_op, write_mask| {
+            let face = StencilFaceState {
+                compare,
+                fail_op: StencilOperation::Keep,
+                depth_fail_op: StencilOperation::Keep,
+                pass_op,
+            };
+            Some(DepthStencilState {
+                format: STENCIL_FORMAT,
+                depth_write_enabled: false,
+                depth_compare: CompareFunction::Always,
+                stencil: StencilState {
+                    front: face,
+                    back: face,
+                    read_mask: u32::MAX,
+                    write_mask,
+                },
+                bias: DepthBiasState::default(),
+            })
+        };
+        let lighting_pipeline = |label: &str,
+                                 (vertex_entry_point, buffers): (&str, &[VertexBufferLayout]),
+                                 (fragment_entry_point, target): (&str, ColorTargetState),
+                                 depth_stencil| {
+            device.create_render_pipeline(&RenderPipelineDescriptor {
+                label: Some(label),
+                layout: Some(&lighting_layout),
+                vertex: VertexState {
+                    module: &lighting_module,
+                    entry_point: Some(vertex_entry_point),
+                    compilation_options: PipelineCompilationOptions::default(),
+                    buffers,
+                },
+                primitive: PrimitiveState::default(),
+                depth_stencil,
+                multisample: MultisampleState::default(),
+                fragment: Some(FragmentState {
+                    module: &lighting_module,
+                    entry_point: Some(fragment_entry_point),
+                    compilation_options: PipelineCompilationOptions {
+                        constants: &constants,
+                        ..Default::default()
+                    },
+                    targets: &[Some(target)],
+                }),
+                multiview: None,
+                cache: None,
+            })
+        };
+
+        let shadow = lighting_pipeline(
+            "Shadow Pipeline",
+            (
+                "vs_shadow",
+                &[VertexBufferLayout {
+                    array_stride: size_of::<[f32; 2]>() as BufferAddress,
+                    step_mode: VertexStepMode::Vertex,
+                    attributes: &vertex_attr_array![0 => Float32x2],
+                }],
+            ),
+            (
+                "fs_shadow",
+                ColorTargetState {
+                    format: LIGHT_FORMAT,
+                    blend: None,
+                    write_mask: ColorWrites::empty(),
+                },
+            ),
+            stencil(CompareFunction::Always, StencilOperation::Replace, u32::MAX),
+        );
+        let additive = BlendComponent {
+            src_factor: BlendFactor::One,
+            dst_factor: BlendFactor::One,
+            operation: BlendOperation::Add,
+        };
+        let light = lighting_pipeline(
+            "Light Pipeline",
+            (
+                "vs_light",
+                &[VertexBufferLayout {
+                    array_stride: size_of::<LightInstance>() as BufferAddress,
+                    step_mode: VertexStepMode::Instance,
+                    attributes: LightInstance::ATTRIBUTES,
+                }],
+            ),
+            (
+                "fs_light",
+                ColorTargetState {
+                    format: LIGHT_FORMAT,
+                    blend: Some(BlendState {
+                        color: additive,
+                        alpha: additive,
+                    }),
+                    write_mask: ColorWrites::ALL,
+                },
+            ),
+            stencil(CompareFunction::NotEqual, StencilOperation::Keep, 0),
+        );
+        let composite = lighting_pipeline(
+            "Lighting Composite Pipeline",
+            ("vs_fullscreen", &[]),
+            (
+                "fs_composite",
+                ColorTargetState {
+                    format,
+                    blend: Some(BlendState {
+                        color: BlendComponent {
+                            src_factor: BlendFactor::Dst,
+                            dst_factor: BlendFactor::Zero,
+                            operation: BlendOperation::Add,
+                        },
+                        alpha: BlendComponent::REPLACE,
+                    }),
+                    write_mask: ColorWrites::COLOR,
+                },
+            ),
+            None,
+        );
+
+        let buffer = |label: &str| {
+            GrowableBuffer::new(
+                device,
+                label.to_owned(),
+                BufferUsages::VERTEX | BufferUsages::COPY_DST,
+                &[],
+                0,
+            )
+        };
+
+        Self {
+            format,
+            normal_flat: normal_pipeline("Flat Normals Pipeline", "fs_flat"),
+            normal_mapped: normal_pipeline("Normal Mapped Pipeline", "fs_normal_mapped"),
+            normal_unlit: normal_pipeline("Unlit Normals Pipeline", "fs_unlit"),
+            shadow,
+            light,
+            composite,
+            texture_layout,
+            light_buffer: buffer("Light Instance Buffer"),
+            shadow_buffer: buffer("Shadow Vertex Buffer"),
+            outlines: HashMap::new(),
+            ambient: wgpu::Color::WHITE,
+            shadow_ranges: Vec::new(),
+            warned_light_limit: false,
+        }
+    }
+
+    #[must_use]
+    pub fn format(&self) -> TextureFormat {
+        self.format
+    }
+
+    /// Uploads the lights and the shadows the occluders of `state` cast.
+    pub fn prepare(&mut self, device: &Device, queue: &Queue, state: &State, lighting: &Lighting) {
+        let edges = self.occluder_edges(state);
+
+        if lighting.lights.len() > MAX_LIGHTS && !self.warned_light_limit {
+            log::warn!(
+                "{} lights, only the first {MAX_LIGHTS} are drawn",
+                lighting.lights.len()
+            );
+            self.warned_light_limit = true;
+        }
+        let mut instances = Vec::new();
+        let mut shadows: Vec<[f32; 2]> = Vec::new();
+        self.shadow_ranges.clear();
+        for light in lighting.lights.iter().take(MAX_LIGHTS) {
+            if light.radius <= 0.0 {
+                continue;
+            }
+            instances.push(LightInstance::new(light));
+
+            let start = shadows.len() as u32;
+            if light.casts_shadows {
+                add_shadows(&mut shadows, &edges, light.position, light.radius);
+            }
+            self.shadow_ranges.push(start..shadows.len() as u32);
+        }
+
+        let light_bytes: &[u8] = bytemuck::cast_slice(&instances);
+        let shadow_bytes: &[u8] = bytemuck::cast_slice(&shadows);
+        self.light_buffer.write(
+            device,
+            queue,
+            light_bytes,
+            (light_bytes.len() as u64).next_power_of_two(),
+        );
+        self.shadow_buffer.write(
+            device,
+            queue,
+            shadow_bytes,
+            (shadow_bytes.len() as u64).next_power_of_two(),
+        );
+        let ambient = lighting.ambient;
+        self.ambient = wgpu::Color {
+            r: f64::from(ambient.r),
+            g: f64::from(ambient.g),
+            b: f64::from(ambient.b),
+            a: 1.0,
+        };
+    }
+
+    /// Adds the passes lighting `scene`, a texture of the format given to [`LightingPass::new`]
+    /// that already holds `objects`, the visible ones. `reads` are the textures the objects
+    /// sample.
+    pub fn add_passes<'a>(
+        &'a self,
+        graph: &mut RenderGraph<'a>,
+        LightingInputs {
+            textures,
+            shared_geometry,
+            camera,
+            size: (width, height),
+            viewport,
+        }: LightingInputs<'a>,
+        objects: &'a [&'a RenderObject],
+        scene: ResourceId,
+        reads: Vec<ResourceId>,
+    ) {
+        let normals = graph.create_transient(
+            "Normal Buffer",
+            TransientDesc::color(width, height, NORMAL_FORMAT),
+        );
+        let light = graph.create_transient(
+            "Light Buffer",
+            TransientDesc::color(width, height, LIGHT_FORMAT),
+        );
+        let stencil = graph.create_transient(
+            "Shadow Stencil",
+            TransientDesc {
+                width,
+                height,
+                format: STENCIL_FORMAT,
+                sample_count: 1,
+                usage: TextureUsages::RENDER_ATTACHMENT,
+            },
+        );
+
+        graph
+            .add_pass("Normals Pass")
+            .reads(reads)
+            .write(normals)
+            .record(move |ctx| {
+                let mut render_pass = ctx.encoder.begin_render_pass(&RenderPassDescriptor {
+                    label: Some("Normals Pass"),
+                    color_attachments: &[Some(RenderPassColorAttachment {
+                        view: ctx.view(normals),
+                        resolve_target: None,
+                        ops: Operations {
+                            // flat and lit, like the background
+                            load: LoadOp::Clear(wgpu::Color {
+                                r: 0.5,
+                                g: 0.5,
+                                b: 1.0,
+                                a: 0.0,
+                            }),
+                            store: StoreOp::Store,
+                        },
+                        depth_slice: None,
+                    })],
+                    ..Default::default()
+                });
+                viewport.apply(&mut render_pass);
+                render_pass.set_bind_group(3, camera, &[]);
+                self.draw_normals(&mut render_pass, textures, shared_geometry, objects);
+            });
+
+        graph
+            .add_pass("Light Accumulation Pass")
+            .read(normals)
+            .write(light)
+            .write(stencil)
+            .record(move |ctx| {
+                let normals = self.texture_bind_group(ctx.device, ctx.view(normals));
+                let mut render_pass = ctx.encoder.begin_render_pass(&RenderPassDescriptor {
+                    label: Some("Light Accumulation Pass"),
+                    color_attachments: &[Some(RenderPassColorAttachment {
+                        view: ctx.view(light),
+                        resolve_target: None,
+                        ops: Operations {
+                            load: LoadOp::Clear(self.ambient),
+                            store: StoreOp::Store,
+                        },
+                        depth_slice: None,
+                    })],
+                    depth_stencil_attachment: Some(RenderPassDepthStencilAttachment {
+                        view: ctx.view(stencil),
+                        depth_ops: None,
+                        stencil_ops: Some(Operations {
+                            load: LoadOp::Clear(0),
+                            store: StoreOp::Discard,
+                        }),
+                    }),
+                    ..Default::default()
+                });
+                viewport.apply(&mut render_pass);
+                render_pass.set_bind_group(0, camera, &[]);
+                render_pass.set_bind_group(1, &normals, &[]);
+                render_pass.set_bind_group(2, &normals, &[]);
+                self.draw_lights(&mut render_pass);
+            });
+
+        graph
+            .add_pass("Lighting Composite Pass")
+            .read(normals)
+            .read(light)
+            .write(scene)
+            .record(move |ctx| {
+                let normals = self.texture_bind_group(ctx.device, ctx.view(normals));
+                let light = self.texture_bind_group(ctx.device, ctx.view(light));
+                let mut render_pass = ctx.encoder.begin_render_pass(&RenderPassDescriptor {
+                    label: Some("Lighting Composite Pass"),
+                    color_attachments: &[Some(RenderPassColorAttachment {
+                        view: ctx.view(scene),
+                        resolve_target: None,
+                        ops: Operations {
+                            load: LoadOp::Load,
+                            store: StoreOp::Store,
+                        },
+                        depth_slice: None,
+                    })],
+                    ..Default::default()
+                });
+                render_pass.set_pipeline(&self.composite);
+                render_pass.set_bind_group(0, camera, &[]);
+                render_pass.set_bind_group(1, &normals, &[]);
+                render_pass.set_bind_group(2, &light, &[]);
+                render_pass.draw(0..3, 0..1);
+            });
+    }
+
+    /// Shapes, MSDF text and particles have no coverage the normal pipelines could compute,
+    /// they take the lighting of what's below them.
+    fn draw_normals<'a>(
+        &self,
+        render_pass: &mut RenderPass<'_>,
+        textures: &TextureRegistry,
+        shared_geometry: &'a SharedGeometry,
+        objects: &[&'a RenderObject],
+    ) {
+        let mut bound_buffer = None;
+        for obj in objects {
+            if obj.index_count() == 0
+                || obj.material != Material::Standard
+                || obj.particles.is_some()
+            {
+                continue;
+            }
+            let pipeline = match (obj.lit, obj.normal_map) {
+                (false, _) => &self.normal_unlit,
+                (true, Some(_)) => &self.normal_mapped,
+                (true, None) => &self.normal_flat,
+            };
+            render_pass.set_pipeline(pipeline);
+            render_pass.set_bind_group(0, textures.bind_group(obj.normal_map), &[]);
+            render_pass.set_bind_group(1, obj.transform_bind_group(), &[]);
+            render_pass.set_bind_group(2, textures.bind_group(obj.texture), &[]);
+            obj.geometry(shared_geometry)
+                .draw(render_pass, &mut bound_buffer, 0..1);
+        }
+    }
+
+    /// Each light marks its shadows with its own stencil value, then adds up where the value
+    /// differs. Values of earlier lights never match, so shadows don't need clearing.
+    fn draw_lights(&self, render_pass: &mut RenderPass<'_>) {
+        for (index, shadows) in self.shadow_ranges.iter().enumerate() {
+            render_pass.set_stencil_reference(index as u32 + 1);
+            if !shadows.is_empty() {
+                render_pass.set_pipeline(&self.shadow);
+                render_pass.set_vertex_buffer(0, self.shadow_buffer.buffer().slice(..));
+                render_pass.draw(shadows.clone(), 0..1);
+            }
+            let index = index as u32;
+            render_pass.set_pipeline(&self.light);
+            render_pass.set_vertex_buffer(0, self.light_buffer.buffer().slice(..));
+            render_pass.draw(0..6, index..index + 1);
+        }
+    }
+
+    /// Outline edges of every occluder in world units, refreshing the cached outlines of
+    /// meshes that changed.
+    fn occluder_edges(&mut self, state: &State) -> Vec<[[f32; 2]; 2]> {
+        let mut edges = Vec::new();
+        let mut occluders = HashSet::new();
+        for (id, obj) in state.objects().filter(|(_, obj)| obj.occluder) {
+            occluders.insert(id);
+            let revisions = (obj.mesh.vertices_revision(), obj.mesh.indices_revision());
+            let outline = self.outlines.entry(id).or_insert_with(|| CachedOutline {
+                vertices_revision: revisions.0,
+                indices_revision: revisions.1,
+                edges: obj.mesh.outline(),
+            });
+            if (outline.vertices_revision, outline.indices_revision) != revisions {
+                *outline = CachedOutline {
+                    vertices_revision: revisions.0,
+                    indices_revision: revisions.1,
+                    edges: obj.mesh.outline(),
+                };
+            }
+
+            edges.extend(outline.edges.iter().map(|&[from, to]| {
+                let [from, to] = [obj.transform.apply(from), obj.transform.apply(to)];
+                // mirroring turns the outline inside out
+                if obj.transform.scale[0] * obj.transform.scale[1] < 0.0 {
+                    [to, from]
+                } else {
+                    [from, to]
+                }
+            }));
+        }
+        self.outlines.retain(|id, _| occluders.contains(id));
+
+        edges
+    }
+
+    fn texture_bind_group(&self, device: &Device, view: &TextureView) -> BindGroup {
+        device.create_bind_group(&BindGroupDescriptor {
+            label: Some("Lighting Texture Bind Group"),
+            layout: &self.texture_layout,
+            entries: &[BindGroupEntry {
+                binding: 0,
+                resource: BindingResource::TextureView(view),
+            }],
+        })
+    }
+}
+
+/// Triangles covering what the edges facing away from `light` hide from it. The edges facing
+/// it would shadow the occluder itself, which stays lit this way.
+fn add_shadows(shadows: &mut Vec<[f32; 2]>, edges: &[[[f32; 2]; 2]], light: [f32; 2], radius: f32) {
+    let away = |point: [f32; 2]| {
+        let offset = [point[0] - light[0], point[1] - light[1]];
+        let length = offset[0].hypot(offset[1]);
+        (length > f32::EPSILON).then(|| {
+            let scale = radius * SHADOW_LENGTH / length;
+            [point[0] + offset[0] * scale, point[1] + offset[1] * scale]
+        })
+    };
+    for &[from, to] in edges {
+        let edge = [to[0] - from[0], to[1] - from[1]];
+        // the outside is on the right
+        let outward = [edge[1], -edge[0]];
+        let facing = outward[0] * (from[0] - light[0]) + outward[1] * (from[1] - light[1]);
+        if facing <= 0.0 || segment_distance(light, [from, to]) > radius {
+            continue;
+        }
+        let (Some(far_from), Some(far_to)) = (away(from), away(to)) else {
+            continue;
+        };
+        shadows.extend([from, to, far_to, from, far_to, far_from]);
+    }
+}
+
+fn segment_distance(point: [f32; 2], [from, to]: [[f32; 2]; 2]) -> f32 {
+    let edge = [to[0] - from[0], to[1] - from[1]];
+    let offset = [point[0] - from[0], point[1] - from[1]];
+    let length_squared = edge[0] * edge[0] + edge[1] * edge[1];
+    let t = if length_squared > 0.0 {
+        ((offset[0] * edge[0] + offset[1] * edge[1]) / length_squared).clamp(0.0, 1.0)
+    } else {
+        0.0
+    };
+    (offset[0] - edge[0] * t).hypot(offset[1] - edge[1] * t)
+}
@@ -1,4 +1,26 @@
+pub mod adapter;
+pub mod antialiasing;
+pub mod batching;
+pub mod buffer;
+pub mod compute;
+pub mod config;
 pub mod context;
+pub mod culling;
+pub mod frame_limiter;
+pub mod glyph_atlas;
+pub mod graveyard;
+pub mod letterbox;
+pub mod lighting;
+pub mod msdf;
+pub mod pixel_art;
+pub mod post_effect;
+pub mod post_processing;
+pub mod render_graph;
+pub mod render_target;
+pub mod resolution;
+pub mod shared_geometry;
+pub mod text_renderer;
+pub mod texture;
 pub mod uniforms;
 
 mod pipeline;
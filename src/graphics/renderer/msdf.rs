@@ -0,0 +1,370 @@
+use ab_glyph::{Font, FontArc, GlyphId, OutlineCurve, Point};
+
+use crate::graphics::renderer::glyph_atlas::GlyphBitmap;
+
+/// Line segments a curve is flattened into.
+const CURVE_STEPS: usize = 8;
+/// Edges meeting at an angle whose sine is above this (or turning back) form a corner.
+const CORNER_SINE: f32 = 0.14;
+
+const RED: u8 = 0b001;
+const GREEN: u8 = 0b010;
+const BLUE: u8 = 0b100;
+const CYAN: u8 = GREEN | BLUE;
+const MAGENTA: u8 = RED | BLUE;
+const YELLOW: u8 = RED | GREEN;
+const WHITE: u8 = RED | GREEN | BLUE;
+
+type Vec2 = [f32; 2];
+
+/// Curve of the outline, flattened, with the channels it's drawn into.
+struct Edge {
+    points: Vec<Vec2>,
+    color: u8,
+}
+impl Edge {
+    fn start_direction(&self) -> Vec2 {
+        normalize(sub(self.points[1], self.points[0]))
+    }
+
+    fn end_direction(&self) -> Vec2 {
+        let n = self.points.len();
+        normalize(sub(self.points[n - 1], self.points[n - 2]))
+    }
+
+    /// Signed distance from the nearest segment, with the segment and the parameter along it.
+    fn distance(&self, p: Vec2, orientation: f32) -> EdgeDistance {
+        let mut nearest = EdgeDistance {
+            distance: f32::INFINITY,
+            orthogonality: f32::INFINITY,
+            segment: 0,
+            t: 0.0,
+        };
+        for (segment, pair) in self.points.windows(2).enumerate() {
+            let (a, b) = (pair[0], pair[1]);
+            let ab = sub(b, a);
+            let t = dot(sub(p, a), ab) / dot(ab, ab);
+            let clamped = t.clamp(0.0, 1.0);
+            let to_p = sub(p, add(a, scale(ab, clamped)));
+            let distance = length(to_p);
+            // how far off perpendicular the nearest point is, breaks ties at shared endpoints
+            let orthogonality = if (0.0..=1.0).contains(&t) {
+                0.0
+            } else {
+                dot(normalize(ab), normalize(to_p)).abs()
+            };
+            if distance < nearest.distance.abs()
+                || (distance == nearest.distance.abs() && orthogonality < nearest.orthogonality)
+            {
+                let side = cross(ab, sub(p, a)) * orientation;
+                nearest = EdgeDistance {
+                    distance: if side < 0.0 { -distance } else { distance },
+                    orthogonality,
+                    segment,
+                    t,
+                };
+            }
+        }
+        nearest
+    }
+
+    /// Distance from the edge extended along its end tangents, where that's nearer than the
+    /// edge itself. Keeps channels meeting at a corner agreeing on its sides.
+    fn pseudo_distance(&self, p: Vec2, nearest: &EdgeDistance, orientation: f32) -> f32 {
+        let last_segment = self.points.len() - 2;
+        let extension = if nearest.segment == 0 && nearest.t < 0.0 {
+            Some((self.points[0], self.start_direction()))
+        } else if nearest.segment == last_segment && nearest.t > 1.0 {
+            Some((self.points[last_segment + 1], self.end_direction()))
+        } else {
+            None
+        };
+
+        match extension {
+            Some((origin, direction)) => {
+                let pseudo = cross(direction, sub(p, origin)) * orientation;
+                if pseudo.abs() <= nearest.distance.abs() {
+                    pseudo
+                } else {
+                    nearest.distance
+                }
+            }
+            None => nearest.distance,
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+struct EdgeDistance {
+    /// Positive inside.
+    distance: f32,
+    orthogonality: f32,
+    segment: usize,
+    t: f32,
+}
+impl EdgeDistance {
+    fn closer_than(&self, other: &Self) -> bool {
+        let (distance, other_distance) = (self.distance.abs(), other.distance.abs());
+        distance < other_distance
+            || (distance == other_distance && self.orthogonality < other.orthogonality)
+    }
+}
+
+/// Multi-channel signed distance field of a glyph at `px`, after Chlumský's msdfgen: every
+/// edge is stored in two of the three channels, so the median of the channels keeps corners
+/// sharp under magnification. Distances span `range` pixels, 0.5 being the edge; the bitmap
+/// has `range` pixels of room around the outline.
+pub fn generate(font: &FontArc, glyph: GlyphId, px: f32, range: f32) -> Option<GlyphBitmap> {
+    let outline = font.outline(glyph)?;
+    let font_scale = px / font.height_unscaled();
+    // pixels, y down
+    let to_px = |point: Point| [point.x * font_scale, -point.y * font_scale];
+
+    let mut contours: Vec<Vec<Edge>> = Vec::new();
+    let mut last_end: Option<Vec2> = None;
+    for curve in &outline.curves {
+        let points: Vec<Vec2> = match *curve {
+            OutlineCurve::Line(p0, p1) => vec![to_px(p0), to_px(p1)],
+            OutlineCurve::Quad(p0, p1, p2) => {
+                let [p0, p1, p2] = [p0, p1, p2].map(to_px);
+                (0..=CURVE_STEPS)
+                    .map(|step| {
+                        let t = step as f32 / CURVE_STEPS as f32;
+                        let u = 1.0 - t;
+                        add(
+                            add(scale(p0, u * u), scale(p1, 2.0 * u * t)),
+                            scale(p2, t * t),
+                        )
+                    })
+                    .collect()
+            }
+            OutlineCurve::Cubic(p0, p1, p2, p3) => {
+                let [p0, p1, p2, p3] = [p0, p1, p2, p3].map(to_px);
+                (0..=CURVE_STEPS)
+                    .map(|step| {
+                        let t = step as f32 / CURVE_STEPS as f32;
+                        let u = 1.0 - t;
+                        add(
+                            add(scale(p0, u * u * u), scale(p1, 3.0 * u * u * t)),
+                            add(scale(p2, 3.0 * u * t * t), scale(p3, t * t * t)),
+                        )
+                    })
+                    .collect()
+            }
+        };
+        let (start, end) = (points[0], points[points.len() - 1]);
+        if last_end != Some(start) || contours.is_empty() {
+            contours.push(Vec::new());
+        }
+        last_end = Some(end);
+        let points = dedup(points);
+        if points.len() >= 2 {
+            contours.last_mut()?.push(Edge {
+                points,
+                color: WHITE,
+            });
+        }
+    }
+    contours.retain(|contour| !contour.is_empty());
+    if contours.is_empty() {
+        return None;
+    }
+    for contour in &mut contours {
+        color_edges(contour);
+    }
+
+    let edges: Vec<&Edge> = contours.iter().flatten().collect();
+    let signed_area: f32 = edges
+        .iter()
+        .flat_map(|edge| edge.points.windows(2))
+        .map(|pair| cross(pair[0], pair[1]))
+        .sum();
+    // inside is left of the edges for one winding direction and right for the other
+    let orientation = if signed_area < 0.0 { -1.0 } else { 1.0 };
+
+    let (mut min, mut max) = ([f32::INFINITY; 2], [f32::NEG_INFINITY; 2]);
+    for point in edges.iter().flat_map(|edge| &edge.points) {
+        min = [min[0].min(point[0]), min[1].min(point[1])];
+        max = [max[0].max(point[0]), max[1].max(point[1])];
+    }
+    let left = (min[0] - range).floor();
+    let top = (min[1] - range).floor();
+    let width = ((max[0] + range).ceil() - left) as u32;
+    let height = ((max[1] + range).ceil() - top) as u32;
+
+    let mut pixels = Vec::with_capacity(width as usize * height as usize * 4);
+    for y in 0..height {
+        for x in 0..width {
+            let p = [left + x as f32 + 0.5, top + y as f32 + 0.5];
+            let distances = pixel_distances(&edges, p, orientation);
+            for distance in distances {
+                pixels.push(((distance / range + 0.5).clamp(0.0, 1.0) * 255.0).round() as u8);
+            }
+            pixels.push(u8::MAX);
+        }
+    }
+
+    Some(GlyphBitmap {
+        width,
+        height,
+        pixels,
+        offset: [left, top],
+    })
+}
+
+/// Per channel pseudo-distance of the nearest edge drawn into it. Where the channels' median
+/// would put `p` on the wrong side, all of them take the true distance instead.
+fn pixel_distances(edges: &[&Edge], p: Vec2, orientation: f32) -> [f32; 3] {
+    let mut nearest: [Option<(&Edge, EdgeDistance)>; 3] = [None, None, None];
+    let mut true_nearest: Option<EdgeDistance> = None;
+    for edge in edges {
+        let distance = edge.distance(p, orientation);
+        for (channel, nearest) in nearest.iter_mut().enumerate() {
+            if edge.color & (1 << channel) != 0
+                && nearest
+                    .as_ref()
+                    .is_none_or(|(_, other)| distance.closer_than(other))
+            {
+                *nearest = Some((edge, distance));
+            }
+        }
+        if true_nearest
+            .as_ref()
+            .is_none_or(|other| distance.closer_than(other))
+        {
+            true_nearest = Some(distance);
+        }
+    }
+
+    let distances = nearest.map(|nearest| {
+        nearest.map_or(f32::NEG_INFINITY, |(edge, distance)| {
+            edge.pseudo_distance(p, &distance, orientation)
+        })
+    });
+
+    let median = distances[0]
+        .min(distances[1])
+        .max(distances[0].max(distances[1]).min(distances[2]));
+    let inside = winding(edges, p) != 0;
+    if (median > 0.0) != inside {
+        let distance = true_nearest.map_or(0.0, |nearest| nearest.distance.abs());
+        return [if inside { distance } else { -distance }; 3];
+    }
+    distances
+}
+
+/// Nonzero winding number of the outline around `p`.
+fn winding(edges: &[&Edge], p: Vec2) -> i32 {
+    let mut winding = 0;
+    for pair in edges.iter().flat_map(|edge| edge.points.windows(2)) {
+        let (a, b) = (pair[0], pair[1]);
+        let side = cross(sub(b, a), sub(p, a));
+        if a[1] <= p[1] && b[1] > p[1] && side > 0.0 {
+            winding += 1;
+        } else if b[1] <= p[1] && a[1] > p[1] && side < 0.0 {
+            winding -= 1;
+        }
+    }
+    winding
+}
+
+/// msdfgen's simple edge coloring: edges between two corners share a color, and neighbouring
+/// colors differ in exactly one channel.
+fn color_edges(contour: &mut Vec<Edge>) {
+    let count = contour.len();
+    let corners: Vec<usize> = (0..count)
+        .filter(|&index| {
+            let before = contour[(index + count - 1) % count].end_direction();
+            let after = contour[index].start_direction();
+            dot(before, after) <= 0.0 || cross(before, after).abs() > CORNER_SINE
+        })
+        .collect();
+
+    match corners.as_slice() {
+        [] => {}
+        // teardrop: three colors spread over the contour so the corner still gets two
+        &[corner] => {
+            contour.rotate_left(corner);
+            if contour.len() < 3 {
+                let edges = std::mem::take(contour);
+                contour.extend(edges.into_iter().flat_map(split_in_three));
+            }
+            let count = contour.len();
+            for (index, edge) in contour.iter_mut().enumerate() {
+                edge.color = [MAGENTA, WHITE, YELLOW][index * 3 / count];
+            }
+        }
+        corners => {
+            let splines = corners.len();
+            for (spline, &start) in corners.iter().enumerate() {
+                let end = corners[(spline + 1) % splines];
+                let color = if spline == splines - 1 && splines % 3 == 1 {
+                    MAGENTA
+                } else {
+                    [CYAN, MAGENTA, YELLOW][spline % 3]
+                };
+                let length = (end + count - start - 1) % count + 1;
+                for index in 0..length {
+                    contour[(start + index) % count].color = color;
+                }
+            }
+        }
+    }
+}
+
+/// Three edges following `edge`, so that a short contour has enough to color.
+fn split_in_three(edge: Edge) -> [Edge; 3] {
+    let mut points = edge.points;
+    while points.len() < 4 {
+        points = points
+            .windows(2)
+            .flat_map(|pair| [pair[0], scale(add(pair[0], pair[1]), 0.5)])
+            .chain(points.last().copied())
+            .collect();
+    }
+    let segments = points.len() - 1;
+    let (first, second) = (segments / 3, 2 * segments / 3);
+    let part = |from: usize, to: usize| Edge {
+        points: points[from..=to].to_vec(),
+        color: edge.color,
+    };
+    [part(0, first), part(first, second), part(second, segments)]
+}
+
+fn dedup(mut points: Vec<Vec2>) -> Vec<Vec2> {
+    points.dedup();
+    points
+}
+
+fn add(a: Vec2, b: Vec2) -> Vec2 {
+    [a[0] + b[0], a[1] + b[1]]
+}
+
+fn sub(a: Vec2, b: Vec2) -> Vec2 {
+    [a[0] - b[0], a[1] - b[1]]
+}
+
+fn scale(a: Vec2, factor: f32) -> Vec2 {
+    [a[0] * factor, a[1] * factor]
+}
+
+fn dot(a: Vec2, b: Vec2) -> f32 {
+    a[0] * b[0] + a[1] * b[1]
+}
+
+fn cross(a: Vec2, b: Vec2) -> f32 {
+    a[0] * b[1] - a[1] * b[0]
+}
+
+fn length(a: Vec2) -> f32 {
+    dot(a, a).sqrt()
+}
+
+fn normalize(a: Vec2) -> Vec2 {
+    let length = length(a);
+    if length == 0.0 {
+        [0.0, 0.0]
+    } else {
+        scale(a, 1.0 / length)
+    }
+}
@@ -2,87 +2,309 @@ use std::sync::Arc;
 
 use wgpu::*;
 
-use crate::graphics::Vertex;
+use crate::graphics::{
+    Material, Vertex,
+    particles::ParticleInstance,
+    renderer::config::{GraphicsConfig, SurfaceFormatPreference},
+};
 
-pub fn request_device(adapter: &Adapter) -> Result<(Device, Queue), RequestDeviceError> {
+/// Also enables [`Features::TEXTURE_ADAPTER_SPECIFIC_FORMAT_FEATURES`] where the adapter has
+/// it, for the MSAA sample counts beyond the guaranteed 4x.
+pub fn request_device(
+    adapter: &Adapter,
+    config: &GraphicsConfig,
+) -> Result<(Device, Queue), RequestDeviceError> {
+    let optional_features = adapter.features() & Features::TEXTURE_ADAPTER_SPECIFIC_FORMAT_FEATURES;
     let desc = DeviceDescriptor {
-        required_limits: Limits {
-            max_texture_dimension_1d: 4096,
-            max_texture_dimension_2d: 4096,
-            max_texture_dimension_3d: 4096,
-            ..Default::default()
-        },
+        required_features: config.required_features | optional_features,
+        required_limits: device_limits(adapter, config),
         ..Default::default()
     };
 
     pollster::block_on(adapter.request_device(&desc))
 }
 
+/// The configured limits, each one the adapter can't satisfy lowered (alignments raised) to
+/// what it offers, typical for GL and software adapters. The rest are kept as configured.
+#[must_use]
+pub fn device_limits(adapter: &Adapter, config: &GraphicsConfig) -> Limits {
+    let supported = adapter.limits();
+    let mut failed = Vec::new();
+    config.required_limits.check_limits_with_fail_fn(
+        &supported,
+        false,
+        |name, requested, allowed| {
+            failed.push(format!("{name}: requested {requested}, allowed {allowed}"));
+        },
+    );
+    if failed.is_empty() {
+        return config.required_limits.clone();
+    }
+
+    log::warn!(
+        "Adapter doesn't support some of the requested limits, lowering them ({})",
+        failed.join(", ")
+    );
+    clamp_limits(config.required_limits.clone(), &supported)
+}
+
+/// `limits` within `supported`, the fields [`Limits::check_limits`] compares.
+fn clamp_limits(mut limits: Limits, supported: &Limits) -> Limits {
+    macro_rules! clamp {
+        (max: $($max:ident),*; min: $($min:ident),*) => {
+            $(limits.$max = limits.$max.min(supported.$max);)*
+            $(limits.$min = limits.$min.max(supported.$min);)*
+        };
+    }
+    clamp!(
+        max: max_texture_dimension_1d,
+        max_texture_dimension_2d,
+        max_texture_dimension_3d,
+        max_texture_array_layers,
+        max_bind_groups,
+        max_bindings_per_bind_group,
+        max_dynamic_uniform_buffers_per_pipeline_layout,
+        max_dynamic_storage_buffers_per_pipeline_layout,
+        max_sampled_textures_per_shader_stage,
+        max_samplers_per_shader_stage,
+        max_storage_buffers_per_shader_stage,
+        max_storage_textures_per_shader_stage,
+        max_uniform_buffers_per_shader_stage,
+        max_binding_array_elements_per_shader_stage,
+        max_uniform_buffer_binding_size,
+        max_storage_buffer_binding_size,
+        max_vertex_buffers,
+        max_buffer_size,
+        max_vertex_attributes,
+        max_vertex_buffer_array_stride,
+        max_inter_stage_shader_components,
+        max_color_attachments,
+        max_color_attachment_bytes_per_sample,
+        max_compute_workgroup_storage_size,
+        max_compute_invocations_per_workgroup,
+        max_compute_workgroup_size_x,
+        max_compute_workgroup_size_y,
+        max_compute_workgroup_size_z,
+        max_compute_workgroups_per_dimension,
+        max_push_constant_size,
+        max_non_sampler_bindings,
+        max_blas_primitive_count,
+        max_blas_geometry_count,
+        max_tlas_instance_count;
+        min: min_uniform_buffer_offset_alignment,
+        min_storage_buffer_offset_alignment
+    );
+    // only compared when both are requested
+    if limits.min_subgroup_size > 0 && limits.max_subgroup_size > 0 {
+        clamp!(max: max_subgroup_size; min: min_subgroup_size);
+    }
+    limits
+}
+
+/// Tries a hardware adapter first and, if fallback is allowed, a software one.
 pub fn request_adapter(
-    instance: Instance,
+    instance: &Instance,
     surface: &Surface<'_>,
-) -> Result<Adapter, RequestAdapterError> {
-    pollster::block_on(instance.request_adapter(&RequestAdapterOptions {
-        power_preference: PowerPreference::default(),
-        force_fallback_adapter: false,
-        compatible_surface: Some(surface),
-    }))
+    config: &GraphicsConfig,
+) -> anyhow::Result<Adapter> {
+    let attempts: &[bool] = if config.allow_fallback {
+        &[false, true]
+    } else {
+        &[false]
+    };
+
+    let mut failures = Vec::new();
+    for &force_fallback_adapter in attempts {
+        let adapter = pollster::block_on(instance.request_adapter(&RequestAdapterOptions {
+            power_preference: config.power_preference,
+            force_fallback_adapter,
+            compatible_surface: Some(surface),
+        }));
+        let kind = if force_fallback_adapter {
+            "software adapter"
+        } else {
+            "adapter"
+        };
+        match adapter {
+            Ok(adapter) if adapter.features().contains(config.required_features) => {
+                return Ok(adapter);
+            }
+            Ok(adapter) => failures.push(format!(
+                "{kind} {} lacks features {:?}",
+                adapter.get_info().name,
+                config.required_features - adapter.features()
+            )),
+            Err(err) => failures.push(format!("{kind}: {err}")),
+        }
+    }
+
+    anyhow::bail!("{}", failures.join(", "))
 }
 
-#[must_use]
 pub fn create_surface_config(
     window: &Arc<winit::window::Window>,
-    surface: &Surface<'_>,
-    adapter: Adapter,
-) -> SurfaceConfiguration {
+    surface_capabilities: &SurfaceCapabilities,
+    config: &GraphicsConfig,
+) -> anyhow::Result<SurfaceConfiguration> {
     let surface_size = window.inner_size();
-    let surface_capabilities = surface.get_capabilities(&adapter);
-    SurfaceConfiguration {
+    let format = select_surface_format(surface_capabilities, config.surface_format)?;
+    let alpha_mode = select_alpha_mode(surface_capabilities, config.transparent);
+    log::info!(
+        "Surface format: {format:?}{}, alpha mode: {alpha_mode:?}",
+        if encodes_srgb_in_shader(format) {
+            " (gamma encoded in shader)"
+        } else {
+            ""
+        }
+    );
+
+    Ok(SurfaceConfiguration {
         usage: TextureUsages::RENDER_ATTACHMENT,
-        format: *surface_capabilities
-            .formats
-            .iter()
-            .find(|format| format.is_srgb())
-            .unwrap(),
+        format,
         width: surface_size.width,
         height: surface_size.height,
-        present_mode: PresentMode::AutoVsync,
-        desired_maximum_frame_latency: 2,
-        alpha_mode: CompositeAlphaMode::Auto,
+        present_mode: supported_present_mode(config.present_mode, surface_capabilities),
+        desired_maximum_frame_latency: config.desired_maximum_frame_latency,
+        alpha_mode,
         view_formats: vec![],
+    })
+}
+
+pub fn select_surface_format(
+    surface_capabilities: &SurfaceCapabilities,
+    preference: SurfaceFormatPreference,
+) -> anyhow::Result<TextureFormat> {
+    let formats = &surface_capabilities.formats;
+    let exact = |wanted: TextureFormat| formats.contains(&wanted).then_some(wanted);
+    let hdr = || exact(TextureFormat::Rgba16Float);
+    let ten_bit = || exact(TextureFormat::Rgb10a2Unorm);
+    let srgb = || formats.iter().copied().find(TextureFormat::is_srgb);
+    let linear = || {
+        exact(TextureFormat::Bgra8Unorm)
+            .or_else(|| exact(TextureFormat::Rgba8Unorm))
+            .or_else(|| formats.first().copied())
+    };
+
+    let format = match preference {
+        SurfaceFormatPreference::Hdr => hdr().or_else(ten_bit).or_else(srgb),
+        SurfaceFormatPreference::TenBit => ten_bit().or_else(srgb),
+        SurfaceFormatPreference::Srgb => srgb(),
+    }
+    .or_else(linear)
+    .ok_or_else(|| anyhow::anyhow!("Surface doesn't support any texture format"))?;
+
+    Ok(format)
+}
+
+/// Surface formats that store gamma encoded values but don't encode on write.
+#[must_use]
+pub fn encodes_srgb_in_shader(format: TextureFormat) -> bool {
+    !format.is_srgb()
+        && !matches!(
+            format,
+            TextureFormat::Rgba16Float | TextureFormat::Rgba32Float
+        )
+}
+
+#[must_use]
+pub fn select_alpha_mode(
+    surface_capabilities: &SurfaceCapabilities,
+    transparent: bool,
+) -> CompositeAlphaMode {
+    if !transparent {
+        return CompositeAlphaMode::Auto;
+    }
+
+    [
+        CompositeAlphaMode::PreMultiplied,
+        CompositeAlphaMode::PostMultiplied,
+        CompositeAlphaMode::Inherit,
+    ]
+    .into_iter()
+    .find(|mode| surface_capabilities.alpha_modes.contains(mode))
+    .unwrap_or_else(|| {
+        log::warn!("Surface doesn't support transparency, the window will be opaque");
+        CompositeAlphaMode::Auto
+    })
+}
+
+/// `Auto*` modes are always supported, explicit ones only if the surface lists them.
+#[must_use]
+pub fn supported_present_mode(
+    requested: PresentMode,
+    surface_capabilities: &SurfaceCapabilities,
+) -> PresentMode {
+    match requested {
+        PresentMode::AutoVsync | PresentMode::AutoNoVsync => requested,
+        _ if surface_capabilities.present_modes.contains(&requested) => requested,
+        _ => {
+            log::warn!("Present mode {requested:?} not supported, using AutoVsync");
+            PresentMode::AutoVsync
+        }
+    }
+}
+
+/// Object pipelines, one per [`Material`] kind, sharing the vertex shader and the layout, and
+/// one for particle systems.
+pub struct ObjectPipelines {
+    standard: RenderPipeline,
+    shape: RenderPipeline,
+    msdf_text: RenderPipeline,
+    particles: RenderPipeline,
+}
+impl ObjectPipelines {
+    /// Standard material, one instance of the mesh per particle.
+    #[must_use]
+    pub fn particles(&self) -> &RenderPipeline {
+        &self.particles
+    }
+
+    #[must_use]
+    pub fn get(&self, material: &Material) -> &RenderPipeline {
+        match material {
+            Material::Standard => &self.standard,
+            Material::Shape { .. } => &self.shape,
+            Material::MsdfText { .. } => &self.msdf_text,
+        }
     }
 }
 
 #[must_use]
-pub fn create_render_pipeline(
+pub fn create_render_pipelines(
     device: &Device,
-    surface_config: &SurfaceConfiguration,
+    format: TextureFormat,
+    sample_count: u32,
     bind_group_layouts: &[&BindGroupLayout],
-) -> RenderPipeline {
+) -> ObjectPipelines {
     let shader_module = device.create_shader_module(ShaderModuleDescriptor {
         label: Some("Shader #0"),
         source: ShaderSource::Wgsl(include_str!("../shaders/basic.wgsl").into()),
     });
+    let sdf_shader_module = device.create_shader_module(ShaderModuleDescriptor {
+        label: Some("SDF Shader"),
+        source: ShaderSource::Wgsl(
+            concat!(
+                include_str!("../shaders/basic.wgsl"),
+                include_str!("../shaders/sdf.wgsl")
+            )
+            .into(),
+        ),
+    });
+    let particle_shader_module = device.create_shader_module(ShaderModuleDescriptor {
+        label: Some("Particle Shader"),
+        source: ShaderSource::Wgsl(
+            concat!(
+                include_str!("../shaders/basic.wgsl"),
+                include_str!("../shaders/particles.wgsl")
+            )
+            .into(),
+        ),
+    });
 
-    let vertex_state = VertexState {
-        module: &shader_module,
-        entry_point: Some("vs_main"),
-        compilation_options: PipelineCompilationOptions::default(),
-        buffers: &[Vertex::vertex_buffer_layout()],
-    };
-
-    let color_target_state = ColorTargetState {
-        format: surface_config.format,
-        blend: None,
-        write_mask: ColorWrites::ALL,
-    };
-
-    let fragment_state = FragmentState {
-        module: &shader_module,
-        entry_point: Some("fs_main"),
-        compilation_options: PipelineCompilationOptions::default(),
-        targets: &[Some(color_target_state)],
-    };
+    let constants = [(
+        "ENCODE_SRGB",
+        f64::from(u8::from(encodes_srgb_in_shader(format))),
+    )];
 
     let pipeline_layout = device.create_pipeline_layout(&PipelineLayoutDescriptor {
         label: Some("Pipeline Layout"),
@@ -90,15 +312,141 @@ pub fn create_render_pipeline(
         push_constant_ranges: &[],
     });
 
-    device.create_render_pipeline(&RenderPipelineDescriptor {
-        label: Some("Render Pipeline"),
-        layout: Some(&pipeline_layout),
-        vertex: vertex_state,
-        primitive: PrimitiveState::default(),
-        depth_stencil: None,
-        multisample: MultisampleState::default(),
-        fragment: Some(fragment_state),
-        multiview: None,
-        cache: None,
-    })
+    let create = |label: &str,
+                  module: &ShaderModule,
+                  (vertex_entry_point, buffers): (&str, &[VertexBufferLayout]),
+                  fragment_entry_point: &str| {
+        let vertex_state = VertexState {
+            module,
+            entry_point: Some(vertex_entry_point),
+            compilation_options: PipelineCompilationOptions::default(),
+            buffers,
+        };
+
+        let color_target_state = ColorTargetState {
+            format,
+            blend: Some(BlendState::ALPHA_BLENDING),
+            write_mask: ColorWrites::ALL,
+        };
+
+        let fragment_state = FragmentState {
+            module,
+            entry_point: Some(fragment_entry_point),
+            compilation_options: PipelineCompilationOptions {
+                constants: &constants,
+                ..Default::default()
+            },
+            targets: &[Some(color_target_state)],
+        };
+
+        device.create_render_pipeline(&RenderPipelineDescriptor {
+            label: Some(label),
+            layout: Some(&pipeline_layout),
+            vertex: vertex_state,
+            primitive: PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: MultisampleState {
+                count: sample_count,
+                ..Default::default()
+            },
+            fragment: Some(fragment_state),
+            multiview: None,
+            cache: None,
+        })
+    };
+
+    let vertex = ("vs_main", &[Vertex::vertex_buffer_layout()][..]);
+    let particle_vertex = (
+        "vs_particle",
+        &[
+            Vertex::vertex_buffer_layout(),
+            ParticleInstance::vertex_buffer_layout(),
+        ][..],
+    );
+    ObjectPipelines {
+        standard: create("Render Pipeline", &shader_module, vertex, "fs_main"),
+        shape: create("Shape Pipeline", &sdf_shader_module, vertex, "fs_shape"),
+        msdf_text: create("MSDF Text Pipeline", &sdf_shader_module, vertex, "fs_msdf"),
+        particles: create(
+            "Particle Pipeline",
+            &particle_shader_module,
+            particle_vertex,
+            "fs_main",
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn maxed_out() -> Limits {
+        Limits {
+            max_texture_dimension_1d: u32::MAX,
+            max_texture_dimension_2d: u32::MAX,
+            max_texture_dimension_3d: u32::MAX,
+            max_texture_array_layers: u32::MAX,
+            max_bind_groups: u32::MAX,
+            max_bindings_per_bind_group: u32::MAX,
+            max_dynamic_uniform_buffers_per_pipeline_layout: u32::MAX,
+            max_dynamic_storage_buffers_per_pipeline_layout: u32::MAX,
+            max_sampled_textures_per_shader_stage: u32::MAX,
+            max_samplers_per_shader_stage: u32::MAX,
+            max_storage_buffers_per_shader_stage: u32::MAX,
+            max_storage_textures_per_shader_stage: u32::MAX,
+            max_uniform_buffers_per_shader_stage: u32::MAX,
+            max_binding_array_elements_per_shader_stage: u32::MAX,
+            max_binding_array_sampler_elements_per_shader_stage: u32::MAX,
+            max_uniform_buffer_binding_size: u32::MAX,
+            max_storage_buffer_binding_size: u32::MAX,
+            max_vertex_buffers: u32::MAX,
+            max_buffer_size: u64::MAX,
+            max_vertex_attributes: u32::MAX,
+            max_vertex_buffer_array_stride: u32::MAX,
+            min_uniform_buffer_offset_alignment: 1,
+            min_storage_buffer_offset_alignment: 1,
+            max_inter_stage_shader_components: u32::MAX,
+            max_color_attachments: u32::MAX,
+            max_color_attachment_bytes_per_sample: u32::MAX,
+            max_compute_workgroup_storage_size: u32::MAX,
+            max_compute_invocations_per_workgroup: u32::MAX,
+            max_compute_workgroup_size_x: u32::MAX,
+            max_compute_workgroup_size_y: u32::MAX,
+            max_compute_workgroup_size_z: u32::MAX,
+            max_compute_workgroups_per_dimension: u32::MAX,
+            min_subgroup_size: 1,
+            max_subgroup_size: u32::MAX,
+            max_push_constant_size: u32::MAX,
+            max_non_sampler_bindings: u32::MAX,
+            max_blas_primitive_count: u32::MAX,
+            max_blas_geometry_count: u32::MAX,
+            max_tlas_instance_count: u32::MAX,
+            max_acceleration_structures_per_shader_stage: u32::MAX,
+        }
+    }
+
+    #[test]
+    fn maxed_out_limits_clamp_within_webgl2() {
+        let webgl2 = Limits::downlevel_webgl2_defaults();
+        assert!(!maxed_out().check_limits(&webgl2));
+
+        let clamped = clamp_limits(maxed_out(), &webgl2);
+
+        assert!(clamped.check_limits(&webgl2));
+        assert_eq!(
+            clamped.max_texture_dimension_2d,
+            webgl2.max_texture_dimension_2d
+        );
+        assert_eq!(
+            clamped.min_uniform_buffer_offset_alignment,
+            webgl2.min_uniform_buffer_offset_alignment
+        );
+    }
+
+    #[test]
+    fn supported_limits_are_kept() {
+        let webgl2 = Limits::downlevel_webgl2_defaults();
+
+        assert_eq!(clamp_limits(webgl2.clone(), &Limits::default()), webgl2);
+    }
 }
@@ -0,0 +1,119 @@
+use wgpu::*;
+
+use crate::graphics::renderer::{
+    render_graph::{RenderGraph, ResourceId},
+    resolution::Viewport,
+};
+
+/// Scales the scene rendered at a [`crate::graphics::ScaleMode::PixelArt`] resolution up to
+/// the window, every scene pixel a square block of window pixels.
+pub struct PixelArtPass {
+    pipeline: RenderPipeline,
+    sampler: Sampler,
+    format: TextureFormat,
+}
+impl PixelArtPass {
+    /// `format` is the one of both the low resolution scene and the texture it's scaled into.
+    #[must_use]
+    pub fn new(device: &Device, format: TextureFormat) -> Self {
+        let module = device.create_shader_module(ShaderModuleDescriptor {
+            label: Some("Pixel Art Shader"),
+            source: ShaderSource::Wgsl(
+                concat!(
+                    include_str!("../shaders/fullscreen.wgsl"),
+                    include_str!("../shaders/pixel_art.wgsl")
+                )
+                .into(),
+            ),
+        });
+        let pipeline = device.create_render_pipeline(&RenderPipelineDescriptor {
+            label: Some("Pixel Art Pipeline"),
+            layout: None,
+            vertex: VertexState {
+                module: &module,
+                entry_point: Some("vs_fullscreen"),
+                compilation_options: PipelineCompilationOptions::default(),
+                buffers: &[],
+            },
+            primitive: PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: MultisampleState::default(),
+            fragment: Some(FragmentState {
+                module: &module,
+                entry_point: Some("fs_upscale"),
+                compilation_options: PipelineCompilationOptions::default(),
+                targets: &[Some(ColorTargetState {
+                    format,
+                    blend: None,
+                    write_mask: ColorWrites::ALL,
+                })],
+            }),
+            multiview: None,
+            cache: None,
+        });
+        let sampler = device.create_sampler(&SamplerDescriptor {
+            label: Some("Pixel Art Sampler"),
+            mag_filter: FilterMode::Nearest,
+            min_filter: FilterMode::Nearest,
+            ..Default::default()
+        });
+
+        Self {
+            pipeline,
+            sampler,
+            format,
+        }
+    }
+
+    #[must_use]
+    pub fn format(&self) -> TextureFormat {
+        self.format
+    }
+
+    /// Draws `scene` into `viewport` of `target`, black around it.
+    pub fn add_pass<'a>(
+        &'a self,
+        graph: &mut RenderGraph<'a>,
+        scene: ResourceId,
+        target: ResourceId,
+        viewport: Viewport,
+    ) {
+        graph
+            .add_pass("Pixel Art Pass")
+            .read(scene)
+            .write(target)
+            .record(move |ctx| {
+                let bind_group = ctx.device.create_bind_group(&BindGroupDescriptor {
+                    label: Some("Pixel Art Bind Group"),
+                    layout: &self.pipeline.get_bind_group_layout(0),
+                    entries: &[
+                        BindGroupEntry {
+                            binding: 0,
+                            resource: BindingResource::TextureView(ctx.view(scene)),
+                        },
+                        BindGroupEntry {
+                            binding: 1,
+                            resource: BindingResource::Sampler(&self.sampler),
+                        },
+                    ],
+                });
+                let mut render_pass = ctx.encoder.begin_render_pass(&RenderPassDescriptor {
+                    label: Some("Pixel Art Pass"),
+                    color_attachments: &[Some(RenderPassColorAttachment {
+                        view: ctx.view(target),
+                        resolve_target: None,
+                        ops: Operations {
+                            load: LoadOp::Clear(Color::BLACK),
+                            store: StoreOp::Store,
+                        },
+                        depth_slice: None,
+                    })],
+                    ..Default::default()
+                });
+                viewport.apply(&mut render_pass);
+                render_pass.set_pipeline(&self.pipeline);
+                render_pass.set_bind_group(0, &bind_group, &[]);
+                render_pass.draw(0..3, 0..1);
+            });
+    }
+}
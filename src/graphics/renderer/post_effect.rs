@@ -0,0 +1,343 @@
+use std::sync::{
+    Arc,
+    atomic::{AtomicU64, Ordering},
+};
+
+/// Parameters an effect shader can read with `param(index)`.
+pub const MAX_EFFECT_PARAMS: usize = 8;
+
+static NEXT_LUT_ID: AtomicU64 = AtomicU64::new(0);
+
+/// Effect parameter, animated on the GPU from the global [`super::uniforms::TimeUniform`]:
+/// `value + amplitude * sin(TAU * (frequency * time + phase))`.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct EffectParam {
+    pub value: f32,
+    pub amplitude: f32,
+    /// Oscillations per second.
+    pub frequency: f32,
+    /// Offset in oscillations, `0.5` starts half a period later.
+    pub phase: f32,
+}
+impl EffectParam {
+    #[must_use]
+    pub const fn constant(value: f32) -> Self {
+        Self {
+            value,
+            amplitude: 0.0,
+            frequency: 0.0,
+            phase: 0.0,
+        }
+    }
+
+    #[must_use]
+    pub const fn oscillating(mut self, amplitude: f32, frequency: f32) -> Self {
+        self.amplitude = amplitude;
+        self.frequency = frequency;
+        self
+    }
+
+    #[must_use]
+    pub const fn with_phase(mut self, phase: f32) -> Self {
+        self.phase = phase;
+        self
+    }
+
+    fn to_slot(self) -> [f32; 4] {
+        [self.value, self.amplitude, self.frequency, self.phase]
+    }
+}
+impl From<f32> for EffectParam {
+    fn from(value: f32) -> Self {
+        Self::constant(value)
+    }
+}
+
+/// Color lookup table mapping sRGB encoded colors to sRGB encoded colors, `size` entries per
+/// channel. Clones share the data.
+#[derive(Clone, Debug)]
+pub struct Lut3d {
+    size: u32,
+    /// RGBA8 texels, red varying fastest, then green, then blue.
+    texels: Arc<[u8]>,
+    id: u64,
+}
+impl Lut3d {
+    /// Largest size [`Lut3d::from_fn`] builds, 64 MiB of texels.
+    pub const MAX_GENERATED_SIZE: u32 = 256;
+
+    /// Leaves colors as they are.
+    #[must_use]
+    pub fn identity(size: u32) -> Self {
+        Self::from_fn(size, |rgb| rgb)
+    }
+
+    /// `grade` gets and returns sRGB encoded `[r, g, b]` in `0.0..=1.0`. `size` is clamped to
+    /// `2..=`[`Lut3d::MAX_GENERATED_SIZE`].
+    #[must_use]
+    pub fn from_fn(size: u32, grade: impl Fn([f32; 3]) -> [f32; 3]) -> Self {
+        let size = size.clamp(2, Self::MAX_GENERATED_SIZE);
+        let max = (size - 1) as f32;
+        let mut texels = Vec::with_capacity(size.pow(3) as usize * 4);
+        for b in 0..size {
+            for g in 0..size {
+                for r in 0..size {
+                    let graded = grade([r as f32 / max, g as f32 / max, b as f32 / max]);
+                    texels.extend(graded.map(to_unorm8));
+                    texels.push(u8::MAX);
+                }
+            }
+        }
+
+        Self::from_texels(size, texels)
+    }
+
+    /// Parses an Adobe/Resolve `.cube` file with a `LUT_3D_SIZE`. Its entries are taken to be
+    /// sRGB encoded, which is what most grading tools export.
+    pub fn from_cube(source: &str) -> anyhow::Result<Self> {
+        let mut size = None;
+        let mut texels = Vec::new();
+        for (line_nr, line) in source.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let mut words = line.split_whitespace();
+            let first = words.next().unwrap_or_default();
+            match first {
+                "LUT_3D_SIZE" => {
+                    let value = words.next().unwrap_or_default();
+                    size = Some(value.parse::<u32>().map_err(|err| {
+                        anyhow::anyhow!(
+                            "Invalid LUT_3D_SIZE {value:?} on line {}: {err}",
+                            line_nr + 1
+                        )
+                    })?);
+                }
+                "LUT_1D_SIZE" => anyhow::bail!("1D LUTs are not supported"),
+                "TITLE" | "DOMAIN_MIN" | "DOMAIN_MAX" | "LUT_3D_INPUT_RANGE" => {}
+                _ => {
+                    let mut rgb = [0.0; 3];
+                    for (index, word) in std::iter::once(first).chain(words).enumerate() {
+                        if index >= 3 {
+                            anyhow::bail!("Too many values on line {}", line_nr + 1);
+                        }
+                        rgb[index] = word.parse::<f32>().map_err(|err| {
+                            anyhow::anyhow!("Invalid value {word:?} on line {}: {err}", line_nr + 1)
+                        })?;
+                    }
+                    texels.extend(rgb.map(to_unorm8));
+                    texels.push(u8::MAX);
+                }
+            }
+        }
+
+        let Some(size) = size else {
+            anyhow::bail!("Missing LUT_3D_SIZE");
+        };
+        let Some(expected) = size.checked_pow(3).map(|entries| entries as usize) else {
+            anyhow::bail!("LUT_3D_SIZE {size} is too large");
+        };
+        if size < 2 || texels.len() / 4 != expected {
+            anyhow::bail!(
+                "LUT of size {size} needs {expected} entries, got {}",
+                texels.len() / 4
+            );
+        }
+
+        Ok(Self::from_texels(size, texels))
+    }
+
+    #[must_use]
+    pub fn size(&self) -> u32 {
+        self.size
+    }
+
+    #[must_use]
+    pub fn texels(&self) -> &[u8] {
+        &self.texels
+    }
+
+    /// Shared by clones, tells the renderer whether the texture has to be uploaded again.
+    #[must_use]
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+
+    fn from_texels(size: u32, texels: Vec<u8>) -> Self {
+        Self {
+            size,
+            texels: texels.into(),
+            id: NEXT_LUT_ID.fetch_add(1, Ordering::Relaxed),
+        }
+    }
+}
+
+/// Fullscreen effect run after the scene, in the order of
+/// [`crate::graphics::GraphicsContext::post_effects_mut`]. Parameters are re-read every
+/// frame; swapping an effect for another kind (or a different LUT or shader) rebuilds it.
+#[derive(Clone, Debug)]
+pub enum PostEffect {
+    /// Blurs what's brighter than `threshold` and adds it back on top.
+    Bloom {
+        threshold: EffectParam,
+        intensity: EffectParam,
+        /// Spacing of the blur taps in half resolution texels.
+        radius: EffectParam,
+    },
+    /// Darkens the screen towards the edges.
+    Vignette {
+        intensity: EffectParam,
+        /// Distance from the center where the darkening ends, `0.5` is the top edge.
+        radius: EffectParam,
+        smoothness: EffectParam,
+    },
+    ColorGrading {
+        lut: Lut3d,
+        /// `0.0` leaves colors as they are, `1.0` applies the LUT fully.
+        intensity: EffectParam,
+    },
+    /// Splits red and blue apart towards the edges.
+    ChromaticAberration { strength: EffectParam },
+    /// Curved screen with scanlines.
+    Crt {
+        curvature: EffectParam,
+        scanline_intensity: EffectParam,
+        /// `0.0` draws one scanline per pixel row.
+        scanline_count: EffectParam,
+        /// Lines per second.
+        scanline_speed: EffectParam,
+    },
+    /// `wgsl` defines `fn fs_main(in: FullscreenOutput) -> @location(0) vec4f` and can use
+    /// `sample_input(uv)`, `input_size()`, `param(index)`, `time_uniform` and the sRGB helpers
+    /// from `post_common.wgsl`. An invalid shader is logged and skipped.
+    Custom {
+        name: String,
+        wgsl: String,
+        params: Vec<EffectParam>,
+    },
+}
+impl PostEffect {
+    #[must_use]
+    pub fn bloom() -> Self {
+        Self::Bloom {
+            threshold: 0.8.into(),
+            intensity: 0.8.into(),
+            radius: 1.5.into(),
+        }
+    }
+
+    #[must_use]
+    pub fn vignette() -> Self {
+        Self::Vignette {
+            intensity: 0.6.into(),
+            radius: 0.75.into(),
+            smoothness: 0.45.into(),
+        }
+    }
+
+    #[must_use]
+    pub fn color_grading(lut: Lut3d) -> Self {
+        Self::ColorGrading {
+            lut,
+            intensity: 1.0.into(),
+        }
+    }
+
+    #[must_use]
+    pub fn chromatic_aberration() -> Self {
+        Self::ChromaticAberration {
+            strength: 0.01.into(),
+        }
+    }
+
+    #[must_use]
+    pub fn crt() -> Self {
+        Self::Crt {
+            curvature: 0.08.into(),
+            scanline_intensity: 0.35.into(),
+            scanline_count: 0.0.into(),
+            scanline_speed: 0.0.into(),
+        }
+    }
+
+    #[must_use]
+    pub fn custom(name: &str, wgsl: &str, params: Vec<EffectParam>) -> Self {
+        Self::Custom {
+            name: name.to_owned(),
+            wgsl: wgsl.to_owned(),
+            params,
+        }
+    }
+
+    #[must_use]
+    pub fn name(&self) -> &str {
+        match self {
+            Self::Bloom { .. } => "Bloom",
+            Self::Vignette { .. } => "Vignette",
+            Self::ColorGrading { .. } => "Color Grading",
+            Self::ChromaticAberration { .. } => "Chromatic Aberration",
+            Self::Crt { .. } => "CRT",
+            Self::Custom { name, .. } => name,
+        }
+    }
+
+    /// Uniform slots in the order the shader's `param(index)` reads them, extra custom params
+    /// are dropped.
+    #[must_use]
+    pub fn param_slots(&self) -> [[f32; 4]; MAX_EFFECT_PARAMS] {
+        let params: &[EffectParam] = match self {
+            Self::Bloom {
+                threshold,
+                intensity,
+                radius,
+            } => &[*threshold, *intensity, *radius],
+            Self::Vignette {
+                intensity,
+                radius,
+                smoothness,
+            } => &[*intensity, *radius, *smoothness],
+            Self::ColorGrading { intensity, .. } => &[*intensity],
+            Self::ChromaticAberration { strength } => &[*strength],
+            Self::Crt {
+                curvature,
+                scanline_intensity,
+                scanline_count,
+                scanline_speed,
+            } => &[
+                *curvature,
+                *scanline_intensity,
+                *scanline_count,
+                *scanline_speed,
+            ],
+            Self::Custom { params, .. } => params,
+        };
+
+        let mut slots = [[0.0; 4]; MAX_EFFECT_PARAMS];
+        for (slot, param) in slots.iter_mut().zip(params) {
+            *slot = param.to_slot();
+        }
+        slots
+    }
+
+    /// Whether GPU resources built for `other` can be reused for `self`.
+    #[must_use]
+    pub fn same_resources(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Self::Bloom { .. }, Self::Bloom { .. })
+            | (Self::Vignette { .. }, Self::Vignette { .. })
+            | (Self::ChromaticAberration { .. }, Self::ChromaticAberration { .. })
+            | (Self::Crt { .. }, Self::Crt { .. }) => true,
+            (Self::ColorGrading { lut, .. }, Self::ColorGrading { lut: other, .. }) => {
+                lut.id() == other.id()
+            }
+            (Self::Custom { wgsl, .. }, Self::Custom { wgsl: other, .. }) => wgsl == other,
+            _ => false,
+        }
+    }
+}
+
+fn to_unorm8(value: f32) -> u8 {
+    (value.clamp(0.0, 1.0) * 255.0).round() as u8
+}
@@ -0,0 +1,648 @@
+use wgpu::*;
+
+use crate::graphics::renderer::{
+    buffer::TrackedBuffer,
+    pipeline::encodes_srgb_in_shader,
+    post_effect::{MAX_EFFECT_PARAMS, PostEffect},
+    render_graph::{RenderGraph, ResourceId, TransientDesc},
+};
+
+/// Format of the scene and of every intermediate texture while effects are active: linear and
+/// above 1.0, so bloom has highlights to work with.
+pub const POST_FORMAT: TextureFormat = TextureFormat::Rgba16Float;
+
+const FULLSCREEN_WGSL: &str = include_str!("../shaders/fullscreen.wgsl");
+const POST_COMMON_WGSL: &str = include_str!("../shaders/post_common.wgsl");
+
+type ParamSlots = [[f32; 4]; MAX_EFFECT_PARAMS];
+
+/// Runs [`PostEffect`]s over the scene and copies the result to the surface. Every effect
+/// writes a transient of the [`RenderGraph`], which lets effects that aren't adjacent share one.
+pub struct PostProcessor {
+    shared: Shared,
+    effects: Vec<EffectInstance>,
+    blit: Blit,
+}
+
+/// What every effect builds on: layouts and the sampler.
+struct Shared {
+    globals_layout: BindGroupLayout,
+    input_layout: BindGroupLayout,
+    texture_layout: BindGroupLayout,
+    lut_layout: BindGroupLayout,
+    sampler: Sampler,
+}
+
+impl PostProcessor {
+    pub fn new(
+        device: &Device,
+        surface_format: TextureFormat,
+        globals_layout: &BindGroupLayout,
+    ) -> Self {
+        let shared = Shared {
+            globals_layout: globals_layout.clone(),
+            input_layout: create_input_layout(device),
+            texture_layout: create_texture_layout(device, TextureViewDimension::D2),
+            lut_layout: create_texture_layout(device, TextureViewDimension::D3),
+            sampler: device.create_sampler(&SamplerDescriptor {
+                label: Some("Post Processing Sampler"),
+                mag_filter: FilterMode::Linear,
+                min_filter: FilterMode::Linear,
+                ..Default::default()
+            }),
+        };
+        let blit = Blit::new(device, &shared, surface_format);
+
+        Self {
+            shared,
+            effects: Vec::new(),
+            blit,
+        }
+    }
+
+    /// Rebuilds the effects that changed kind, LUT or shader and uploads changed parameters.
+    pub fn sync(&mut self, device: &Device, queue: &Queue, effects: &[PostEffect]) {
+        self.effects.truncate(effects.len());
+        for (index, effect) in effects.iter().enumerate() {
+            let reusable = self
+                .effects
+                .get(index)
+                .is_some_and(|instance| instance.effect.same_resources(effect));
+            if !reusable {
+                let instance = EffectInstance::new(device, queue, &self.shared, effect);
+                if index < self.effects.len() {
+                    self.effects[index] = instance;
+                } else {
+                    self.effects.push(instance);
+                }
+            }
+            self.effects[index].update_params(queue, effect.param_slots());
+        }
+    }
+
+    /// Adds the passes taking `input`, a `width` x `height` [`POST_FORMAT`] texture, through
+    /// every effect into `output`.
+    pub fn add_passes<'a>(
+        &'a self,
+        graph: &mut RenderGraph<'a>,
+        globals: &'a BindGroup,
+        input: ResourceId,
+        output: ResourceId,
+        (width, height): (u32, u32),
+    ) {
+        let mut current = input;
+        for effect in &self.effects {
+            if matches!(effect.passes, EffectPasses::Failed) {
+                continue;
+            }
+            let effect_output = graph.create_transient(
+                effect.effect.name(),
+                TransientDesc::color(width, height, POST_FORMAT),
+            );
+            effect.add_passes(
+                graph,
+                &self.shared,
+                globals,
+                current,
+                effect_output,
+                (width, height),
+            );
+            current = effect_output;
+        }
+
+        let (shared, blit) = (&self.shared, &self.blit);
+        graph
+            .add_pass("Post Processing Blit")
+            .read(current)
+            .write(output)
+            .record(move |ctx| {
+                let input = shared.input_bind_group(ctx.device, ctx.view(current), &blit.params);
+                fullscreen_pass(
+                    ctx.encoder,
+                    "Post Processing Blit",
+                    &blit.pipeline,
+                    &[globals, &input],
+                    ctx.view(output),
+                );
+            });
+    }
+}
+
+struct EffectInstance {
+    /// Snapshot the resources were built from.
+    effect: PostEffect,
+    params: TrackedBuffer,
+    uploaded: Option<ParamSlots>,
+    passes: EffectPasses,
+}
+
+enum EffectPasses {
+    Single {
+        pipeline: RenderPipeline,
+        /// The LUT of color grading.
+        extra: Option<(Texture, BindGroup)>,
+    },
+    Bloom(Box<Bloom>),
+    /// Invalid custom shader, skipped.
+    Failed,
+}
+
+struct Bloom {
+    threshold: RenderPipeline,
+    blur_horizontal: RenderPipeline,
+    blur_vertical: RenderPipeline,
+    composite: RenderPipeline,
+}
+
+impl EffectInstance {
+    fn new(device: &Device, queue: &Queue, shared: &Shared, effect: &PostEffect) -> Self {
+        let name = effect.name();
+        let params = create_params_buffer(device, name);
+
+        let max_lut_size = device.limits().max_texture_dimension_3d;
+        if let PostEffect::ColorGrading { lut, .. } = effect
+            && lut.size() > max_lut_size
+        {
+            log::error!(
+                "Unable to build the {name} effect, skipping it: LUT size {} is over {max_lut_size}",
+                lut.size()
+            );
+            return Self {
+                effect: effect.clone(),
+                params,
+                uploaded: None,
+                passes: EffectPasses::Failed,
+            };
+        }
+
+        device.push_error_scope(ErrorFilter::Validation);
+        let passes = match effect {
+            PostEffect::Bloom { .. } => EffectPasses::Bloom(Box::new(Bloom::new(device, shared))),
+            PostEffect::Vignette { .. } => EffectPasses::Single {
+                pipeline: shared.pipeline(
+                    device,
+                    name,
+                    include_str!("../shaders/vignette.wgsl"),
+                    "fs_main",
+                    None,
+                    POST_FORMAT,
+                ),
+                extra: None,
+            },
+            PostEffect::ChromaticAberration { .. } => EffectPasses::Single {
+                pipeline: shared.pipeline(
+                    device,
+                    name,
+                    include_str!("../shaders/chromatic_aberration.wgsl"),
+                    "fs_main",
+                    None,
+                    POST_FORMAT,
+                ),
+                extra: None,
+            },
+            PostEffect::Crt { .. } => EffectPasses::Single {
+                pipeline: shared.pipeline(
+                    device,
+                    name,
+                    include_str!("../shaders/crt.wgsl"),
+                    "fs_main",
+                    None,
+                    POST_FORMAT,
+                ),
+                extra: None,
+            },
+            PostEffect::ColorGrading { lut, .. } => EffectPasses::Single {
+                pipeline: shared.pipeline(
+                    device,
+                    name,
+                    include_str!("../shaders/color_grading.wgsl"),
+                    "fs_main",
+                    Some(&shared.lut_layout),
+                    POST_FORMAT,
+                ),
+                extra: Some(shared.upload_lut(device, queue, lut.size(), lut.texels())),
+            },
+            PostEffect::Custom { wgsl, .. } => EffectPasses::Single {
+                pipeline: shared.pipeline(device, name, wgsl, "fs_main", None, POST_FORMAT),
+                extra: None,
+            },
+        };
+        let passes = match pollster::block_on(device.pop_error_scope()) {
+            Some(err) => {
+                log::error!("Unable to build the {name} effect, skipping it: {err}");
+                EffectPasses::Failed
+            }
+            None => passes,
+        };
+
+        Self {
+            effect: effect.clone(),
+            params,
+            uploaded: None,
+            passes,
+        }
+    }
+
+    fn update_params(&mut self, queue: &Queue, slots: ParamSlots) {
+        if self.uploaded != Some(slots) {
+            queue.write_buffer(&self.params, 0, bytemuck::cast_slice(&slots));
+            self.uploaded = Some(slots);
+        }
+    }
+
+    fn add_passes<'a>(
+        &'a self,
+        graph: &mut RenderGraph<'a>,
+        shared: &'a Shared,
+        globals: &'a BindGroup,
+        input: ResourceId,
+        output: ResourceId,
+        size: (u32, u32),
+    ) {
+        match &self.passes {
+            EffectPasses::Single { pipeline, extra } => {
+                let name = self.effect.name();
+                graph
+                    .add_pass(name)
+                    .read(input)
+                    .write(output)
+                    .record(move |ctx| {
+                        let input =
+                            shared.input_bind_group(ctx.device, ctx.view(input), &self.params);
+                        let mut bind_groups = vec![globals, &input];
+                        bind_groups.extend(extra.as_ref().map(|(_, bind_group)| bind_group));
+                        fullscreen_pass(
+                            ctx.encoder,
+                            name,
+                            pipeline,
+                            &bind_groups,
+                            ctx.view(output),
+                        );
+                    });
+            }
+            EffectPasses::Bloom(bloom) => {
+                bloom.add_passes(graph, shared, globals, &self.params, (input, output), size);
+            }
+            EffectPasses::Failed => {}
+        }
+    }
+}
+
+impl Bloom {
+    fn new(device: &Device, shared: &Shared) -> Self {
+        let source = include_str!("../shaders/bloom.wgsl");
+        Self {
+            threshold: shared.pipeline(
+                device,
+                "Bloom Threshold",
+                source,
+                "fs_threshold",
+                None,
+                POST_FORMAT,
+            ),
+            blur_horizontal: shared.pipeline(
+                device,
+                "Bloom Horizontal Blur",
+                source,
+                "fs_blur_horizontal",
+                None,
+                POST_FORMAT,
+            ),
+            blur_vertical: shared.pipeline(
+                device,
+                "Bloom Vertical Blur",
+                source,
+                "fs_blur_vertical",
+                None,
+                POST_FORMAT,
+            ),
+            composite: shared.pipeline(
+                device,
+                "Bloom Composite",
+                source,
+                "fs_composite",
+                Some(&shared.texture_layout),
+                POST_FORMAT,
+            ),
+        }
+    }
+
+    /// Threshold and blur at half resolution, then composite the blur over `input`.
+    fn add_passes<'a>(
+        &'a self,
+        graph: &mut RenderGraph<'a>,
+        shared: &'a Shared,
+        globals: &'a BindGroup,
+        params: &'a Buffer,
+        (input, output): (ResourceId, ResourceId),
+        (width, height): (u32, u32),
+    ) {
+        let half = TransientDesc::color(width.div_ceil(2), height.div_ceil(2), POST_FORMAT);
+        let bright = graph.create_transient("Bloom Threshold", half);
+        let horizontal = graph.create_transient("Bloom Horizontal Blur", half);
+        let blurred = graph.create_transient("Bloom Vertical Blur", half);
+
+        let steps = [
+            ("Bloom Threshold", &self.threshold, input, bright),
+            (
+                "Bloom Horizontal Blur",
+                &self.blur_horizontal,
+                bright,
+                horizontal,
+            ),
+            (
+                "Bloom Vertical Blur",
+                &self.blur_vertical,
+                horizontal,
+                blurred,
+            ),
+        ];
+        for (label, pipeline, step_input, step_output) in steps {
+            graph
+                .add_pass(label)
+                .read(step_input)
+                .write(step_output)
+                .record(move |ctx| {
+                    let bind_group =
+                        shared.input_bind_group(ctx.device, ctx.view(step_input), params);
+                    fullscreen_pass(
+                        ctx.encoder,
+                        label,
+                        pipeline,
+                        &[globals, &bind_group],
+                        ctx.view(step_output),
+                    );
+                });
+        }
+
+        graph
+            .add_pass("Bloom Composite")
+            .read(input)
+            .read(blurred)
+            .write(output)
+            .record(move |ctx| {
+                let input = shared.input_bind_group(ctx.device, ctx.view(input), params);
+                let blurred = shared.texture_bind_group(ctx.device, ctx.view(blurred));
+                fullscreen_pass(
+                    ctx.encoder,
+                    "Bloom Composite",
+                    &self.composite,
+                    &[globals, &input, &blurred],
+                    ctx.view(output),
+                );
+            });
+    }
+}
+
+/// Final copy into the surface format, gamma encoding in the shader where the format doesn't.
+struct Blit {
+    pipeline: RenderPipeline,
+    params: TrackedBuffer,
+}
+impl Blit {
+    fn new(device: &Device, shared: &Shared, surface_format: TextureFormat) -> Self {
+        let entry_point = if encodes_srgb_in_shader(surface_format) {
+            "fs_encode_srgb"
+        } else {
+            "fs_copy"
+        };
+        let pipeline = shared.pipeline(
+            device,
+            "Post Processing Blit",
+            include_str!("../shaders/blit.wgsl"),
+            entry_point,
+            None,
+            surface_format,
+        );
+        Self {
+            pipeline,
+            params: create_params_buffer(device, "Blit"),
+        }
+    }
+}
+
+impl Shared {
+    /// `source` is appended to `fullscreen.wgsl` and `post_common.wgsl`.
+    fn pipeline(
+        &self,
+        device: &Device,
+        label: &str,
+        source: &str,
+        entry_point: &str,
+        extra_layout: Option<&BindGroupLayout>,
+        format: TextureFormat,
+    ) -> RenderPipeline {
+        let shader_module = device.create_shader_module(ShaderModuleDescriptor {
+            label: Some(&format!("{label} Shader")),
+            source: ShaderSource::Wgsl(
+                format!("{FULLSCREEN_WGSL}{POST_COMMON_WGSL}{source}").into(),
+            ),
+        });
+
+        let mut bind_group_layouts = vec![&self.globals_layout, &self.input_layout];
+        bind_group_layouts.extend(extra_layout);
+        let pipeline_layout = device.create_pipeline_layout(&PipelineLayoutDescriptor {
+            label: Some(&format!("{label} Pipeline Layout")),
+            bind_group_layouts: &bind_group_layouts,
+            push_constant_ranges: &[],
+        });
+
+        device.create_render_pipeline(&RenderPipelineDescriptor {
+            label: Some(&format!("{label} Pipeline")),
+            layout: Some(&pipeline_layout),
+            vertex: VertexState {
+                module: &shader_module,
+                entry_point: Some("vs_fullscreen"),
+                compilation_options: PipelineCompilationOptions::default(),
+                buffers: &[],
+            },
+            primitive: PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: MultisampleState::default(),
+            fragment: Some(FragmentState {
+                module: &shader_module,
+                entry_point: Some(entry_point),
+                compilation_options: PipelineCompilationOptions::default(),
+                targets: &[Some(ColorTargetState {
+                    format,
+                    blend: None,
+                    write_mask: ColorWrites::ALL,
+                })],
+            }),
+            multiview: None,
+            cache: None,
+        })
+    }
+
+    fn input_bind_group(&self, device: &Device, view: &TextureView, params: &Buffer) -> BindGroup {
+        device.create_bind_group(&BindGroupDescriptor {
+            label: Some("Post Processing Input Bind Group"),
+            layout: &self.input_layout,
+            entries: &[
+                BindGroupEntry {
+                    binding: 0,
+                    resource: BindingResource::TextureView(view),
+                },
+                BindGroupEntry {
+                    binding: 1,
+                    resource: BindingResource::Sampler(&self.sampler),
+                },
+                BindGroupEntry {
+                    binding: 2,
+                    resource: params.as_entire_binding(),
+                },
+            ],
+        })
+    }
+
+    fn texture_bind_group(&self, device: &Device, view: &TextureView) -> BindGroup {
+        device.create_bind_group(&BindGroupDescriptor {
+            label: Some("Post Processing Texture Bind Group"),
+            layout: &self.texture_layout,
+            entries: &[BindGroupEntry {
+                binding: 0,
+                resource: BindingResource::TextureView(view),
+            }],
+        })
+    }
+
+    fn upload_lut(
+        &self,
+        device: &Device,
+        queue: &Queue,
+        size: u32,
+        texels: &[u8],
+    ) -> (Texture, BindGroup) {
+        let extent = Extent3d {
+            width: size,
+            height: size,
+            depth_or_array_layers: size,
+        };
+        let texture = device.create_texture(&TextureDescriptor {
+            label: Some("Color Grading LUT"),
+            size: extent,
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: TextureDimension::D3,
+            format: TextureFormat::Rgba8Unorm,
+            usage: TextureUsages::TEXTURE_BINDING | TextureUsages::COPY_DST,
+            view_formats: &[],
+        });
+        queue.write_texture(
+            TexelCopyTextureInfo {
+                texture: &texture,
+                mip_level: 0,
+                origin: Origin3d::ZERO,
+                aspect: TextureAspect::All,
+            },
+            texels,
+            TexelCopyBufferLayout {
+                offset: 0,
+                bytes_per_row: Some(size * 4),
+                rows_per_image: Some(size),
+            },
+            extent,
+        );
+
+        let view = texture.create_view(&TextureViewDescriptor::default());
+        let bind_group = device.create_bind_group(&BindGroupDescriptor {
+            label: Some("Color Grading LUT Bind Group"),
+            layout: &self.lut_layout,
+            entries: &[BindGroupEntry {
+                binding: 0,
+                resource: BindingResource::TextureView(&view),
+            }],
+        });
+
+        (texture, bind_group)
+    }
+}
+
+fn fullscreen_pass(
+    encoder: &mut CommandEncoder,
+    label: &str,
+    pipeline: &RenderPipeline,
+    bind_groups: &[&BindGroup],
+    output: &TextureView,
+) {
+    let mut render_pass = encoder.begin_render_pass(&RenderPassDescriptor {
+        label: Some(label),
+        color_attachments: &[Some(RenderPassColorAttachment {
+            view: output,
+            resolve_target: None,
+            ops: Operations {
+                load: LoadOp::Clear(wgpu::Color::TRANSPARENT),
+                store: StoreOp::Store,
+            },
+            depth_slice: None,
+        })],
+        ..Default::default()
+    });
+    render_pass.set_pipeline(pipeline);
+    for (index, bind_group) in bind_groups.iter().enumerate() {
+        render_pass.set_bind_group(index as u32, *bind_group, &[]);
+    }
+    render_pass.draw(0..3, 0..1);
+}
+
+fn create_params_buffer(device: &Device, name: &str) -> TrackedBuffer {
+    TrackedBuffer::new(
+        device,
+        &BufferDescriptor {
+            label: Some(&format!("{name} Params Buffer")),
+            size: size_of::<ParamSlots>() as u64,
+            usage: BufferUsages::UNIFORM | BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        },
+    )
+}
+
+fn create_input_layout(device: &Device) -> BindGroupLayout {
+    device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+        label: Some("Post Processing Input Bind Group Layout"),
+        entries: &[
+            BindGroupLayoutEntry {
+                binding: 0,
+                visibility: ShaderStages::FRAGMENT,
+                ty: BindingType::Texture {
+                    sample_type: TextureSampleType::Float { filterable: true },
+                    view_dimension: TextureViewDimension::D2,
+                    multisampled: false,
+                },
+                count: None,
+            },
+            BindGroupLayoutEntry {
+                binding: 1,
+                visibility: ShaderStages::FRAGMENT,
+                ty: BindingType::Sampler(SamplerBindingType::Filtering),
+                count: None,
+            },
+            BindGroupLayoutEntry {
+                binding: 2,
+                visibility: ShaderStages::FRAGMENT,
+                ty: BindingType::Buffer {
+                    ty: BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: std::num::NonZeroU64::new(size_of::<ParamSlots>() as u64),
+                },
+                count: None,
+            },
+        ],
+    })
+}
+
+fn create_texture_layout(device: &Device, view_dimension: TextureViewDimension) -> BindGroupLayout {
+    device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+        label: Some("Post Processing Texture Bind Group Layout"),
+        entries: &[BindGroupLayoutEntry {
+            binding: 0,
+            visibility: ShaderStages::FRAGMENT,
+            ty: BindingType::Texture {
+                sample_type: TextureSampleType::Float { filterable: true },
+                view_dimension,
+                multisampled: false,
+            },
+            count: None,
+        }],
+    })
+}
@@ -0,0 +1,480 @@
+use std::collections::{HashMap, VecDeque};
+
+use wgpu::*;
+
+use crate::graphics::renderer::texture::RenderTexture;
+
+/// Handle of a texture declared in a [`RenderGraph`], only valid for that graph.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct ResourceId(usize);
+
+/// Texture that only lives for the frame. Transients with equal descriptors and disjoint
+/// lifetimes share the same GPU texture.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct TransientDesc {
+    pub width: u32,
+    pub height: u32,
+    pub format: TextureFormat,
+    pub sample_count: u32,
+    pub usage: TextureUsages,
+}
+impl TransientDesc {
+    /// Single sampled texture that can be rendered into and sampled afterwards.
+    #[must_use]
+    pub fn color(width: u32, height: u32, format: TextureFormat) -> Self {
+        Self {
+            width,
+            height,
+            format,
+            sample_count: 1,
+            usage: TextureUsages::RENDER_ATTACHMENT | TextureUsages::TEXTURE_BINDING,
+        }
+    }
+}
+
+enum ResourceKind<'a> {
+    /// Owned outside the graph (the surface, render targets); writing one keeps a pass alive.
+    Imported(&'a TextureView),
+    Transient(TransientDesc),
+}
+
+struct Resource<'a> {
+    name: String,
+    kind: ResourceKind<'a>,
+}
+
+type RecordFn<'a> = Box<dyn FnOnce(&mut PassContext<'_>) + 'a>;
+
+struct Pass<'a> {
+    name: String,
+    reads: Vec<ResourceId>,
+    writes: Vec<ResourceId>,
+    record: RecordFn<'a>,
+}
+
+/// Frame described as passes and the textures they read and write. Passes may be added in
+/// any order: [`RenderGraph::execute`] runs every writer of a texture before its readers,
+/// writers of the same texture in the order they were added, and drops passes whose output
+/// never reaches an imported texture.
+#[derive(Default)]
+pub struct RenderGraph<'a> {
+    resources: Vec<Resource<'a>>,
+    passes: Vec<Pass<'a>>,
+}
+
+/// What a pass gets to record its commands with.
+pub struct PassContext<'r> {
+    pub device: &'r Device,
+    pub encoder: &'r mut CommandEncoder,
+    views: &'r [Option<&'r TextureView>],
+}
+impl<'r> PassContext<'r> {
+    /// View of a texture declared by the pass.
+    #[must_use]
+    pub fn view(&self, id: ResourceId) -> &'r TextureView {
+        self.views[id.0].expect("resource not declared by the pass")
+    }
+}
+
+pub struct PassBuilder<'g, 'a> {
+    graph: &'g mut RenderGraph<'a>,
+    name: String,
+    reads: Vec<ResourceId>,
+    writes: Vec<ResourceId>,
+}
+impl<'a> PassBuilder<'_, 'a> {
+    pub fn read(mut self, id: ResourceId) -> Self {
+        self.reads.push(id);
+        self
+    }
+
+    pub fn reads(mut self, ids: impl IntoIterator<Item = ResourceId>) -> Self {
+        self.reads.extend(ids);
+        self
+    }
+
+    pub fn write(mut self, id: ResourceId) -> Self {
+        self.writes.push(id);
+        self
+    }
+
+    pub fn record(self, record: impl FnOnce(&mut PassContext<'_>) + 'a) {
+        self.graph.passes.push(Pass {
+            name: self.name,
+            reads: self.reads,
+            writes: self.writes,
+            record: Box::new(record),
+        });
+    }
+}
+
+impl<'a> RenderGraph<'a> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn import(&mut self, name: &str, view: &'a TextureView) -> ResourceId {
+        self.add_resource(name, ResourceKind::Imported(view))
+    }
+
+    pub fn create_transient(&mut self, name: &str, desc: TransientDesc) -> ResourceId {
+        self.add_resource(name, ResourceKind::Transient(desc))
+    }
+
+    /// Declare what the pass touches with the returned builder, then hand it the recording
+    /// with [`PassBuilder::record`].
+    pub fn add_pass(&mut self, name: &str) -> PassBuilder<'_, 'a> {
+        PassBuilder {
+            graph: self,
+            name: name.to_owned(),
+            reads: Vec::new(),
+            writes: Vec::new(),
+        }
+    }
+
+    /// Orders and culls the passes, assigns GPU textures to the transients and records one
+    /// command buffer per pass, in submission order.
+    pub fn execute(self, device: &Device, pool: &mut TransientPool) -> Vec<CommandBuffer> {
+        let order = self.order();
+        let slots = self.alias_transients(&order);
+
+        let slot_descs: Vec<TransientDesc> = slots.iter().map(|(desc, _)| *desc).collect();
+        pool.prepare(device, &slot_descs);
+
+        let mut views: Vec<Option<&TextureView>> = self
+            .resources
+            .iter()
+            .map(|resource| match resource.kind {
+                ResourceKind::Imported(view) => Some(view),
+                ResourceKind::Transient(_) => None,
+            })
+            .collect();
+        for (slot, (_, resources)) in slots.iter().enumerate() {
+            for &id in resources {
+                views[id.0] = Some(pool.view(slot));
+            }
+        }
+
+        let mut passes: Vec<Option<Pass<'a>>> = self.passes.into_iter().map(Some).collect();
+        order
+            .into_iter()
+            .filter_map(|index| passes[index].take())
+            .map(|pass| {
+                let mut encoder = device.create_command_encoder(&CommandEncoderDescriptor {
+                    label: Some(&pass.name),
+                });
+                (pass.record)(&mut PassContext {
+                    device,
+                    encoder: &mut encoder,
+                    views: &views,
+                });
+                encoder.finish()
+            })
+            .collect()
+    }
+
+    fn add_resource(&mut self, name: &str, kind: ResourceKind<'a>) -> ResourceId {
+        self.resources.push(Resource {
+            name: name.to_owned(),
+            kind,
+        });
+        ResourceId(self.resources.len() - 1)
+    }
+
+    fn order(&self) -> Vec<usize> {
+        let imported: Vec<bool> = self
+            .resources
+            .iter()
+            .map(|resource| matches!(resource.kind, ResourceKind::Imported(_)))
+            .collect();
+        order_passes(&self.passes, &imported)
+    }
+
+    /// Greedy interval assignment: a transient takes over the GPU texture of one whose last
+    /// use came before its first use. Returns every slot's descriptor and its transients.
+    fn alias_transients(&self, order: &[usize]) -> Vec<(TransientDesc, Vec<ResourceId>)> {
+        let mut lifetimes: Vec<Option<(usize, usize)>> = vec![None; self.resources.len()];
+        for (position, &index) in order.iter().enumerate() {
+            let pass = &self.passes[index];
+            for id in pass.reads.iter().chain(&pass.writes) {
+                let lifetime = lifetimes[id.0].get_or_insert((position, position));
+                lifetime.1 = position;
+            }
+        }
+
+        let mut transients: Vec<(ResourceId, TransientDesc, usize, usize)> = self
+            .resources
+            .iter()
+            .enumerate()
+            .filter_map(
+                |(index, resource)| match (&resource.kind, lifetimes[index]) {
+                    (ResourceKind::Transient(desc), Some((first, last))) => {
+                        Some((ResourceId(index), *desc, first, last))
+                    }
+                    _ => None,
+                },
+            )
+            .collect();
+        transients.sort_by_key(|&(_, _, first, _)| first);
+
+        // (descriptor, last use, transients)
+        let mut slots: Vec<(TransientDesc, usize, Vec<ResourceId>)> = Vec::new();
+        for (id, desc, first, last) in transients {
+            match slots
+                .iter_mut()
+                .find(|(slot_desc, slot_last, _)| *slot_desc == desc && *slot_last < first)
+            {
+                Some((_, slot_last, ids)) => {
+                    *slot_last = last;
+                    ids.push(id);
+                }
+                None => slots.push((desc, last, vec![id])),
+            }
+        }
+        for (desc, _, ids) in &slots {
+            if ids.len() > 1 {
+                let names: Vec<&str> = ids
+                    .iter()
+                    .map(|id| self.resources[id.0].name.as_str())
+                    .collect();
+                log::trace!("Aliasing {names:?} ({}x{})", desc.width, desc.height);
+            }
+        }
+
+        slots
+            .into_iter()
+            .map(|(desc, _, ids)| (desc, ids))
+            .collect()
+    }
+}
+
+/// Indices of the passes to run, dependencies first. A cycle (e.g. two render targets
+/// showing each other) is broken by running the earliest added pass of it first, its
+/// reads then see the previous frame. `imported` tells for every resource whether it's
+/// imported.
+fn order_passes(passes: &[Pass<'_>], imported: &[bool]) -> Vec<usize> {
+    let mut writers = vec![Vec::new(); imported.len()];
+    for (index, pass) in passes.iter().enumerate() {
+        for id in &pass.writes {
+            writers[id.0].push(index);
+        }
+    }
+
+    let dependencies: Vec<Vec<usize>> = passes
+        .iter()
+        .enumerate()
+        .map(|(index, pass)| {
+            let mut dependencies = Vec::new();
+            for id in &pass.reads {
+                if !pass.writes.contains(id) {
+                    dependencies.extend(writers[id.0].iter().filter(|&&w| w != index));
+                }
+            }
+            for id in &pass.writes {
+                dependencies.extend(writers[id.0].iter().filter(|&&w| w < index));
+            }
+            dependencies.sort_unstable();
+            dependencies.dedup();
+            dependencies
+        })
+        .collect();
+
+    // keep what contributes to an imported texture
+    let mut needed = vec![false; passes.len()];
+    let mut queue: VecDeque<usize> = passes
+        .iter()
+        .enumerate()
+        .filter(|(_, pass)| pass.writes.iter().any(|id| imported[id.0]))
+        .map(|(index, _)| index)
+        .collect();
+    while let Some(index) = queue.pop_front() {
+        if !std::mem::replace(&mut needed[index], true) {
+            queue.extend(&dependencies[index]);
+        }
+    }
+    for (pass, _) in passes.iter().zip(&needed).filter(|(_, needed)| !**needed) {
+        log::trace!("Culling render pass {}", pass.name);
+    }
+
+    let mut remaining: Vec<usize> = (0..passes.len()).filter(|&i| needed[i]).collect();
+    let mut done = vec![false; passes.len()];
+    let mut order = Vec::with_capacity(remaining.len());
+    while !remaining.is_empty() {
+        let ready = remaining
+            .iter()
+            .position(|&index| dependencies[index].iter().all(|&dep| done[dep]))
+            .unwrap_or_else(|| {
+                let index = pass_on_cycle(&dependencies, &done, remaining[0]);
+                log::debug!(
+                    "Render pass {} is part of a cycle, running it first",
+                    passes[index].name
+                );
+                remaining.iter().position(|&i| i == index).unwrap_or(0)
+            });
+        let index = remaining.remove(ready);
+        done[index] = true;
+        order.push(index);
+    }
+
+    order
+}
+
+/// Earliest added pass of the cycle that the unfinished dependencies of `start` lead into,
+/// `start` itself possibly only depending on it.
+fn pass_on_cycle(dependencies: &[Vec<usize>], done: &[bool], start: usize) -> usize {
+    let mut path = vec![start];
+    loop {
+        let current = path[path.len() - 1];
+        // without an unfinished dependency the pass would have been ready
+        let Some(next) = dependencies[current]
+            .iter()
+            .copied()
+            .find(|&dep| !done[dep])
+        else {
+            return current;
+        };
+        if let Some(position) = path.iter().position(|&index| index == next) {
+            return path[position..].iter().copied().min().unwrap_or(next);
+        }
+        path.push(next);
+    }
+}
+
+/// GPU textures behind the transients, kept between frames so that an unchanged graph
+/// allocates nothing. Textures a frame doesn't use are dropped.
+#[derive(Default)]
+pub struct TransientPool {
+    textures: HashMap<TransientDesc, Vec<RenderTexture>>,
+    /// Per slot of the current frame: descriptor and index into `textures`.
+    slots: Vec<(TransientDesc, usize)>,
+}
+impl TransientPool {
+    fn prepare(&mut self, device: &Device, descs: &[TransientDesc]) {
+        let mut counts: HashMap<TransientDesc, usize> = HashMap::new();
+        self.slots = descs
+            .iter()
+            .map(|desc| {
+                let count = counts.entry(*desc).or_default();
+                *count += 1;
+                (*desc, *count - 1)
+            })
+            .collect();
+
+        self.textures.retain(|desc, _| counts.contains_key(desc));
+        for (desc, count) in counts {
+            let textures = self.textures.entry(desc).or_default();
+            textures.truncate(count);
+            while textures.len() < count {
+                textures.push(RenderTexture::new(
+                    device,
+                    "Transient Texture",
+                    desc.width,
+                    desc.height,
+                    desc.format,
+                    desc.sample_count,
+                    desc.usage,
+                ));
+            }
+        }
+    }
+
+    fn view(&self, slot: usize) -> &TextureView {
+        let (desc, index) = &self.slots[slot];
+        &self.textures[desc][*index].view
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pass(name: &str, reads: &[usize], writes: &[usize]) -> Pass<'static> {
+        Pass {
+            name: name.to_owned(),
+            reads: reads.iter().map(|&id| ResourceId(id)).collect(),
+            writes: writes.iter().map(|&id| ResourceId(id)).collect(),
+            record: Box::new(|_| {}),
+        }
+    }
+
+    fn order<'p>(passes: &'p [Pass<'_>], imported: &[bool]) -> Vec<&'p str> {
+        order_passes(passes, imported)
+            .into_iter()
+            .map(|index| passes[index].name.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn writers_run_before_readers_whatever_the_order_added() {
+        // 0: scene, 1: bloom, 2: surface
+        let passes = [
+            pass("blit", &[1], &[2]),
+            pass("bloom", &[0], &[1]),
+            pass("scene", &[], &[0]),
+        ];
+        assert_eq!(
+            order(&passes, &[false, false, true]),
+            ["scene", "bloom", "blit"]
+        );
+    }
+
+    #[test]
+    fn writers_of_the_same_texture_keep_the_order_added() {
+        // 0: scene, 1: surface
+        let passes = [
+            pass("letterbox", &[], &[1]),
+            pass("scene", &[], &[0]),
+            pass("lights", &[0], &[0]),
+            pass("blit", &[0], &[1]),
+        ];
+        assert_eq!(
+            order(&passes, &[false, true]),
+            ["letterbox", "scene", "lights", "blit"]
+        );
+    }
+
+    #[test]
+    fn passes_not_reaching_an_imported_texture_are_culled() {
+        // 0: unused, 1: read by unused, 2: surface
+        let passes = [
+            pass("unused", &[1], &[0]),
+            pass("feeds unused", &[], &[1]),
+            pass("scene", &[], &[2]),
+        ];
+        assert_eq!(order(&passes, &[false, false, true]), ["scene"]);
+        assert!(order(&passes, &[false, false, false]).is_empty());
+    }
+
+    #[test]
+    fn cycle_starts_with_its_earliest_pass() {
+        // two render targets showing each other
+        let passes = [pass("b", &[0], &[1]), pass("a", &[1], &[0])];
+        assert_eq!(order(&passes, &[true, true]), ["b", "a"]);
+    }
+
+    #[test]
+    fn cycle_is_broken_on_the_cycle_not_before_it() {
+        // 0, 1: render targets showing each other, 2: surface showing both
+        let passes = [
+            pass("surface", &[0, 1], &[2]),
+            pass("a", &[1], &[0]),
+            pass("b", &[0], &[1]),
+        ];
+        assert_eq!(order(&passes, &[true, true, true]), ["a", "b", "surface"]);
+    }
+
+    #[test]
+    fn cycle_reached_through_a_chain() {
+        // 0: transient between "feed" and "surface", 1, 2: targets showing each other
+        let passes = [
+            pass("surface", &[0], &[3]),
+            pass("feed", &[1], &[0]),
+            pass("b", &[1], &[2]),
+            pass("a", &[2], &[1]),
+        ];
+        assert_eq!(
+            order(&passes, &[false, true, true, true]),
+            ["b", "a", "feed", "surface"]
+        );
+    }
+}
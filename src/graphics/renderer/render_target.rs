@@ -0,0 +1,61 @@
+use wgpu::Device;
+
+use crate::{
+    app::ObjectId,
+    graphics::{
+        Color,
+        camera::{Camera, CameraBinding},
+        renderer::texture::TextureId,
+    },
+};
+
+/// Offscreen view of a set of objects. Its texture can be put on any object with
+/// [`crate::graphics::RenderObject::with_texture`]; a target is rendered before everything
+/// showing it, so they see this frame's contents. Of targets showing each other, the one
+/// created first sees the previous frame of the others.
+pub struct RenderTarget {
+    texture: TextureId,
+    width: u32,
+    height: u32,
+    pub camera: Camera,
+    pub clear_color: Color,
+    /// Drawn in this order, ids of removed objects are skipped.
+    pub objects: Vec<ObjectId>,
+    camera_binding: CameraBinding,
+}
+impl RenderTarget {
+    pub fn new(device: &Device, texture: TextureId, width: u32, height: u32) -> Self {
+        Self {
+            texture,
+            width,
+            height,
+            camera: Camera::default(),
+            clear_color: Color::TRANSPARENT,
+            objects: Vec::new(),
+            camera_binding: CameraBinding::new(device, "Render Target"),
+        }
+    }
+
+    #[must_use]
+    pub fn texture(&self) -> TextureId {
+        self.texture
+    }
+
+    #[must_use]
+    pub fn size(&self) -> (u32, u32) {
+        (self.width, self.height)
+    }
+
+    pub fn camera_binding(&self) -> &CameraBinding {
+        &self.camera_binding
+    }
+
+    pub fn camera_binding_mut(&mut self) -> &mut CameraBinding {
+        &mut self.camera_binding
+    }
+
+    /// Replaces the GPU resources after a device loss.
+    pub fn recreate(&mut self, device: &Device) {
+        self.camera_binding = CameraBinding::new(device, "Render Target");
+    }
+}
@@ -0,0 +1,284 @@
+use wgpu::RenderPass;
+
+use crate::graphics::Camera;
+
+/// How the virtual resolution of a [`Resolution`] is fitted into the window.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ScaleMode {
+    /// Fills the window scaled evenly, the virtual area always fits and more of the world
+    /// shows along the side the window has to spare.
+    #[default]
+    Expand,
+    /// Largest evenly scaled fit, centered between bars on the sides the window has to spare.
+    Letterbox,
+    /// Fills the window with exactly the virtual area, scaled unevenly.
+    Stretch,
+    /// Like `Letterbox`, but scaled by whole multiples so every virtual pixel covers the same
+    /// number of window pixels. Windows smaller than the virtual area fall back to `Letterbox`.
+    Integer,
+    /// Fitted like `Integer`, but the scene is rendered at the virtual resolution and scaled up
+    /// with its pixels kept square: objects snap to whole pixels and textures are sampled
+    /// nearest, but for data textures like distance fields. Anti-aliasing is off meanwhile.
+    PixelArt,
+}
+
+/// Virtual size the world is laid out for. A world unit spans half the shorter side of the
+/// virtual area along both axes, whatever the mode, so circles stay round and the cursor maps
+/// to the world the same way everything else does.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Resolution {
+    /// In virtual pixels, `None` follows the window.
+    pub size: Option<(u32, u32)>,
+    pub mode: ScaleMode,
+}
+impl Resolution {
+    /// Follows the window, a world unit being half its shorter side.
+    #[must_use]
+    pub fn window() -> Self {
+        Self::default()
+    }
+
+    #[must_use]
+    pub fn fixed(width: u32, height: u32, mode: ScaleMode) -> Self {
+        Self {
+            size: Some((width, height)),
+            mode,
+        }
+    }
+
+    /// Renders `width` x `height` pixels, see [`ScaleMode::PixelArt`].
+    #[must_use]
+    pub fn pixel_art(width: u32, height: u32) -> Self {
+        Self::fixed(width, height, ScaleMode::PixelArt)
+    }
+
+    /// Size the scene is rendered at before being scaled up, `None` when it's rendered
+    /// straight into the window.
+    #[must_use]
+    pub fn pixel_art_size(&self) -> Option<(u32, u32)> {
+        let (width, height) = self.size?;
+        (self.mode == ScaleMode::PixelArt).then_some((width.max(1), height.max(1)))
+    }
+
+    /// Where the view lands in a `width` x `height` window and how much of the world it shows.
+    #[must_use]
+    pub fn viewport(&self, (width, height): (u32, u32)) -> Viewport {
+        let window = [width.max(1) as f32, height.max(1) as f32];
+        let (virtual_width, virtual_height) = self.size.unwrap_or((width, height));
+        let size = [virtual_width.max(1) as f32, virtual_height.max(1) as f32];
+        let smaller = size[0].min(size[1]);
+        let fit = (window[0] / size[0]).min(window[1] / size[1]);
+
+        let boxed = |scale: f32| {
+            let rect_size = size.map(|side| (side * scale).round().max(1.0));
+            Viewport {
+                position: [0, 1].map(|axis| ((window[axis] - rect_size[axis]) * 0.5).floor()),
+                size: rect_size,
+                extent: size.map(|side| side / smaller),
+            }
+        };
+        match self.mode {
+            ScaleMode::Expand => Viewport {
+                position: [0.0, 0.0],
+                size: window,
+                extent: window.map(|side| side / (fit * smaller)),
+            },
+            ScaleMode::Letterbox => boxed(fit),
+            ScaleMode::Stretch => Viewport {
+                position: [0.0, 0.0],
+                size: window,
+                extent: size.map(|side| side / smaller),
+            },
+            ScaleMode::Integer | ScaleMode::PixelArt if fit >= 1.0 => boxed(fit.floor()),
+            ScaleMode::Integer | ScaleMode::PixelArt => boxed(fit),
+        }
+    }
+}
+
+/// Rectangle of the window the view is drawn into, in pixels from the top left, and the
+/// world it shows.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Viewport {
+    pub position: [f32; 2],
+    pub size: [f32; 2],
+    /// Half the width and height of the view in world units, before the camera's zoom.
+    pub extent: [f32; 2],
+}
+impl Viewport {
+    /// Maps the clip space of `render_pass` onto the view.
+    pub fn apply(&self, render_pass: &mut RenderPass<'_>) {
+        let ([x, y], [width, height]) = (self.position, self.size);
+        render_pass.set_viewport(x, y, width, height, 0.0, 1.0);
+    }
+
+    /// Window pixels a world unit covers, the smaller of the two axes when stretched.
+    #[must_use]
+    pub fn pixels_per_unit(&self) -> f32 {
+        (self.size[0] / (2.0 * self.extent[0])).min(self.size[1] / (2.0 * self.extent[1]))
+    }
+
+    /// Whether the window pixel `[x, y]` is inside the view rather than on a bar.
+    #[must_use]
+    pub fn contains(&self, [x, y]: [f32; 2]) -> bool {
+        let [left, top] = self.position;
+        x >= left && y >= top && x < left + self.size[0] && y < top + self.size[1]
+    }
+
+    /// World point shown at the window pixel `[x, y]`. Pixels on the bars map past the edge of
+    /// the view.
+    #[must_use]
+    pub fn screen_to_world(&self, [x, y]: [f32; 2], camera: &Camera) -> [f32; 2] {
+        let clip = [
+            (x - self.position[0]) / self.size[0] * 2.0 - 1.0,
+            1.0 - (y - self.position[1]) / self.size[1] * 2.0,
+        ];
+        camera.view_to_world([0, 1].map(|axis| clip[axis] * self.extent[axis]))
+    }
+
+    /// Window pixel showing the world point `[x, y]`, see [`Viewport::screen_to_world`].
+    #[must_use]
+    pub fn world_to_screen(&self, point: [f32; 2], camera: &Camera) -> [f32; 2] {
+        let view = camera.world_to_view(point);
+        let clip = [0, 1].map(|axis| view[axis] / self.extent[axis]);
+        [
+            self.position[0] + (clip[0] + 1.0) * 0.5 * self.size[0],
+            self.position[1] + (1.0 - clip[1]) * 0.5 * self.size[1],
+        ]
+    }
+
+    /// Parts of a `width` x `height` window outside the view as `[x, y, width, height]`,
+    /// none when the view fills it.
+    #[must_use]
+    pub fn bars(&self, (width, height): (u32, u32)) -> Vec<[u32; 4]> {
+        let [left, top] = self.position.map(|side| side.max(0.0) as u32);
+        let right = (left + self.size[0] as u32).min(width);
+        let bottom = (top + self.size[1] as u32).min(height);
+        [
+            [0, 0, width, top],
+            [0, bottom, width, height - bottom],
+            [0, top, left, bottom - top],
+            [right, top, width - right, bottom - top],
+        ]
+        .into_iter()
+        .filter(|&[_, _, width, height]| width > 0 && height > 0)
+        .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MODES: [ScaleMode; 5] = [
+        ScaleMode::Expand,
+        ScaleMode::Letterbox,
+        ScaleMode::Stretch,
+        ScaleMode::Integer,
+        ScaleMode::PixelArt,
+    ];
+
+    fn assert_near([x, y]: [f32; 2], [expected_x, expected_y]: [f32; 2]) {
+        assert!(
+            (x - expected_x).abs() < 1e-3 && (y - expected_y).abs() < 1e-3,
+            "{:?} != {:?}",
+            [x, y],
+            [expected_x, expected_y]
+        );
+    }
+
+    fn cameras() -> [Camera; 3] {
+        [
+            Camera::new(),
+            Camera::new().with_position(3.0, -2.0).with_zoom(2.5),
+            Camera::new()
+                .with_position(-1.0, 0.5)
+                .with_rotation(0.7)
+                .with_zoom(0.5),
+        ]
+    }
+
+    #[test]
+    fn screen_to_world_round_trips_in_every_mode() {
+        let windows = [(1280, 720), (1000, 600), (333, 777), (160, 90)];
+        for mode in MODES {
+            for resolution in [Resolution::fixed(320, 180, mode), Resolution::window()] {
+                for window in windows {
+                    let viewport = resolution.viewport(window);
+                    for camera in cameras() {
+                        for pixel in [[0.0, 0.0], [12.5, 40.0], [150.0, 80.0], [159.0, 89.0]] {
+                            let world = viewport.screen_to_world(pixel, &camera);
+                            assert_near(viewport.world_to_screen(world, &camera), pixel);
+                        }
+                        for point in [[0.0, 0.0], [1.0, -1.0], [-2.5, 0.25]] {
+                            let pixel = viewport.world_to_screen(point, &camera);
+                            assert_near(viewport.screen_to_world(pixel, &camera), point);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn view_center_shows_the_camera_position() {
+        for mode in MODES {
+            let viewport = Resolution::fixed(320, 180, mode).viewport((1000, 600));
+            let center = [0, 1].map(|axis| viewport.position[axis] + viewport.size[axis] * 0.5);
+            for camera in cameras() {
+                assert_near(viewport.screen_to_world(center, &camera), camera.position);
+            }
+        }
+    }
+
+    #[test]
+    fn expand_shows_more_world_along_the_spare_side() {
+        let viewport = Resolution::fixed(400, 400, ScaleMode::Expand).viewport((800, 400));
+        assert_eq!(viewport.position, [0.0, 0.0]);
+        assert_eq!(viewport.extent, [2.0, 1.0]);
+        assert_eq!(viewport.pixels_per_unit(), 200.0);
+        assert_near(
+            viewport.screen_to_world([800.0, 0.0], &Camera::new()),
+            [2.0, 1.0],
+        );
+    }
+
+    #[test]
+    fn letterbox_centers_between_bars() {
+        let viewport = Resolution::fixed(320, 180, ScaleMode::Letterbox).viewport((1280, 800));
+        assert_eq!(viewport.position, [0.0, 40.0]);
+        assert_eq!(viewport.size, [1280.0, 720.0]);
+        assert_near(
+            viewport.screen_to_world([640.0, 40.0], &Camera::new()),
+            [0.0, 1.0],
+        );
+        assert!(!viewport.contains([640.0, 39.0]));
+        assert_eq!(
+            viewport.bars((1280, 800)),
+            [[0, 0, 1280, 40], [0, 760, 1280, 40]]
+        );
+    }
+
+    #[test]
+    fn stretch_fills_the_window_with_the_virtual_area() {
+        let viewport = Resolution::fixed(400, 200, ScaleMode::Stretch).viewport((800, 800));
+        assert_eq!(viewport.size, [800.0, 800.0]);
+        assert_eq!(viewport.extent, [2.0, 1.0]);
+        assert_near(
+            viewport.screen_to_world([800.0, 800.0], &Camera::new()),
+            [2.0, -1.0],
+        );
+        assert!(viewport.bars((800, 800)).is_empty());
+    }
+
+    #[test]
+    fn integer_modes_scale_by_whole_multiples() {
+        for mode in [ScaleMode::Integer, ScaleMode::PixelArt] {
+            let viewport = Resolution::fixed(320, 180, mode).viewport((1000, 600));
+            assert_eq!(viewport.size, [960.0, 540.0]);
+            assert_eq!(viewport.position, [20.0, 30.0]);
+
+            let small = Resolution::fixed(320, 180, mode).viewport((160, 90));
+            assert_eq!(small.size, [160.0, 90.0]);
+        }
+    }
+}
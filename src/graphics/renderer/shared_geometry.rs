@@ -0,0 +1,360 @@
+use std::{
+    ops::Range,
+    sync::{Arc, Mutex},
+};
+
+use wgpu::*;
+
+use crate::graphics::{Vertex, renderer::buffer::TrackedBuffer};
+
+/// Vertices and indices the buffers start out with room for, doubled whenever they run out.
+const INITIAL_VERTICES: u32 = 4096;
+const INITIAL_INDICES: u32 = 8192;
+
+const VERTEX_LABEL: &str = "Shared Vertex Buffer";
+const INDEX_LABEL: &str = "Shared Index Buffer";
+
+/// Free ranges of a buffer counted in elements, sorted and never touching.
+struct RangeAllocator {
+    capacity: u32,
+    free: Vec<Range<u32>>,
+}
+impl RangeAllocator {
+    fn new(capacity: u32) -> Self {
+        let mut allocator = Self {
+            capacity: 0,
+            free: Vec::new(),
+        };
+        allocator.grow(capacity);
+        allocator
+    }
+
+    /// First fit.
+    fn allocate(&mut self, len: u32) -> Option<Range<u32>> {
+        let index = self
+            .free
+            .iter()
+            .position(|range| range.end - range.start >= len)?;
+        let start = self.free[index].start;
+        self.free[index].start += len;
+        if self.free[index].is_empty() {
+            self.free.remove(index);
+        }
+        Some(start..start + len)
+    }
+
+    fn free(&mut self, range: Range<u32>) {
+        if range.is_empty() {
+            return;
+        }
+        let index = self.free.partition_point(|free| free.start < range.start);
+        self.free.insert(index, range);
+        // merge with the neighbors
+        if index + 1 < self.free.len() && self.free[index].end == self.free[index + 1].start {
+            self.free[index].end = self.free.remove(index + 1).end;
+        }
+        if index > 0 && self.free[index - 1].end == self.free[index].start {
+            self.free[index - 1].end = self.free.remove(index).end;
+        }
+    }
+
+    fn grow(&mut self, capacity: u32) {
+        let old = std::mem::replace(&mut self.capacity, capacity);
+        self.free(old..capacity);
+    }
+}
+
+struct Allocators {
+    vertices: RangeAllocator,
+    indices: RangeAllocator,
+}
+
+/// Place of a mesh in [`SharedGeometry`], given back when dropped.
+pub struct SharedMesh {
+    vertices: Range<u32>,
+    indices: Range<u32>,
+    allocators: Arc<Mutex<Allocators>>,
+}
+impl SharedMesh {
+    /// Range of the shared index buffer to draw.
+    #[must_use]
+    pub fn indices(&self) -> Range<u32> {
+        self.indices.clone()
+    }
+
+    /// Added to every index, the indices are stored relative to the mesh's first vertex.
+    #[must_use]
+    pub fn base_vertex(&self) -> i32 {
+        self.vertices.start as i32
+    }
+}
+impl Drop for SharedMesh {
+    fn drop(&mut self) {
+        let mut allocators = self.allocators.lock().unwrap();
+        allocators.vertices.free(self.vertices.clone());
+        allocators.indices.free(self.indices.clone());
+    }
+}
+
+/// One vertex and one index buffer static meshes are sub-allocated from, so that drawing a
+/// run of them binds the buffers once. Freed ranges are reused right away: uploads go through
+/// the queue, after the frames already submitted.
+pub struct SharedGeometry {
+    vertex_buffer: TrackedBuffer,
+    index_buffer: TrackedBuffer,
+    allocators: Arc<Mutex<Allocators>>,
+    /// Indices are drawn with a base vertex, which not every adapter supports.
+    supported: bool,
+}
+impl SharedGeometry {
+    #[must_use]
+    pub fn new(device: &Device, adapter: &Adapter) -> Self {
+        let supported = adapter
+            .get_downlevel_capabilities()
+            .flags
+            .contains(DownlevelFlags::BASE_VERTEX);
+        if !supported {
+            log::info!("Adapter can't offset vertices, every mesh gets its own buffers");
+        }
+
+        Self {
+            vertex_buffer: create_buffer(
+                device,
+                VERTEX_LABEL,
+                BufferUsages::VERTEX,
+                vertex_bytes(INITIAL_VERTICES),
+            ),
+            index_buffer: create_buffer(
+                device,
+                INDEX_LABEL,
+                BufferUsages::INDEX,
+                index_bytes(INITIAL_INDICES),
+            ),
+            allocators: Arc::new(Mutex::new(Allocators {
+                vertices: RangeAllocator::new(INITIAL_VERTICES),
+                indices: RangeAllocator::new(INITIAL_INDICES),
+            })),
+            supported,
+        }
+    }
+
+    #[must_use]
+    pub fn vertex_buffer(&self) -> &Buffer {
+        &self.vertex_buffer
+    }
+
+    /// Holds 32 bit indices.
+    #[must_use]
+    pub fn index_buffer(&self) -> &Buffer {
+        &self.index_buffer
+    }
+
+    /// Copies the mesh in, growing the buffers if needed. `None` when the adapter can't draw
+    /// from shared buffers or they can't grow any further, the mesh needs its own then.
+    pub fn upload(
+        &mut self,
+        device: &Device,
+        queue: &Queue,
+        vertices: &[Vertex],
+        indices: &[u32],
+    ) -> Option<SharedMesh> {
+        if !self.supported || vertices.is_empty() || indices.is_empty() {
+            return None;
+        }
+        let vertex_count = u32::try_from(vertices.len()).ok()?;
+        let index_count = u32::try_from(indices.len()).ok()?;
+
+        let mut allocators = self.allocators.lock().unwrap();
+        let vertex_range = match allocators.vertices.allocate(vertex_count) {
+            Some(range) => range,
+            None => {
+                let capacity = grown(allocators.vertices.capacity, vertex_count)?;
+                if vertex_bytes(capacity) > device.limits().max_buffer_size {
+                    return None;
+                }
+                self.vertex_buffer = regrow(
+                    device,
+                    queue,
+                    &self.vertex_buffer,
+                    VERTEX_LABEL,
+                    vertex_bytes(capacity),
+                );
+                allocators.vertices.grow(capacity);
+                allocators.vertices.allocate(vertex_count)?
+            }
+        };
+        let index_range = match allocators.indices.allocate(index_count) {
+            Some(range) => range,
+            None => {
+                let regrown = grown(allocators.indices.capacity, index_count)
+                    .filter(|&capacity| index_bytes(capacity) <= device.limits().max_buffer_size);
+                let Some(capacity) = regrown else {
+                    allocators.vertices.free(vertex_range);
+                    return None;
+                };
+                self.index_buffer = regrow(
+                    device,
+                    queue,
+                    &self.index_buffer,
+                    INDEX_LABEL,
+                    index_bytes(capacity),
+                );
+                allocators.indices.grow(capacity);
+                allocators.indices.allocate(index_count)?
+            }
+        };
+        drop(allocators);
+
+        queue.write_buffer(
+            &self.vertex_buffer,
+            vertex_bytes(vertex_range.start),
+            bytemuck::cast_slice(vertices),
+        );
+        queue.write_buffer(
+            &self.index_buffer,
+            index_bytes(index_range.start),
+            bytemuck::cast_slice(indices),
+        );
+
+        Some(SharedMesh {
+            vertices: vertex_range,
+            indices: index_range,
+            allocators: self.allocators.clone(),
+        })
+    }
+}
+
+/// Doubled capacity with room for `needed` more elements.
+fn grown(capacity: u32, needed: u32) -> Option<u32> {
+    let mut grown = capacity.max(1);
+    while grown - capacity < needed {
+        grown = grown.checked_mul(2)?;
+    }
+    Some(grown)
+}
+
+/// Bigger buffer starting with the contents of `old`.
+fn regrow(
+    device: &Device,
+    queue: &Queue,
+    old: &TrackedBuffer,
+    label: &str,
+    size: u64,
+) -> TrackedBuffer {
+    log::debug!("{label} regrown to {size} bytes");
+    let buffer = create_buffer(device, label, old.usage(), size);
+    let mut encoder = device.create_command_encoder(&CommandEncoderDescriptor {
+        label: Some("Shared Geometry Encoder"),
+    });
+    encoder.copy_buffer_to_buffer(old, 0, &buffer, 0, old.size());
+    queue.submit([encoder.finish()]);
+    buffer
+}
+
+fn create_buffer(device: &Device, label: &str, usage: BufferUsages, size: u64) -> TrackedBuffer {
+    TrackedBuffer::new(
+        device,
+        &BufferDescriptor {
+            label: Some(label),
+            size,
+            usage: usage | BufferUsages::COPY_DST | BufferUsages::COPY_SRC,
+            mapped_at_creation: false,
+        },
+    )
+}
+
+fn vertex_bytes(count: u32) -> u64 {
+    u64::from(count) * size_of::<Vertex>() as u64
+}
+
+fn index_bytes(count: u32) -> u64 {
+    u64::from(count) * size_of::<u32>() as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn allocates_first_fit_in_order() {
+        let mut allocator = RangeAllocator::new(100);
+        assert_eq!(allocator.allocate(10), Some(0..10));
+        assert_eq!(allocator.allocate(20), Some(10..30));
+        assert_eq!(allocator.free, vec![30..100]);
+        assert_eq!(allocator.allocate(71), None);
+        assert_eq!(allocator.allocate(70), Some(30..100));
+        assert!(allocator.free.is_empty());
+        assert_eq!(allocator.allocate(1), None);
+    }
+
+    #[test]
+    fn freed_ranges_merge_with_both_neighbors() {
+        let mut allocator = RangeAllocator::new(30);
+        let ranges: Vec<_> = (0..3).map(|_| allocator.allocate(10).unwrap()).collect();
+        allocator.free(ranges[0].clone());
+        allocator.free(ranges[2].clone());
+        assert_eq!(allocator.free, vec![0..10, 20..30]);
+        allocator.free(ranges[1].clone());
+        assert_eq!(allocator.free, vec![0..30]);
+    }
+
+    #[test]
+    fn freed_range_is_reused_first() {
+        let mut allocator = RangeAllocator::new(100);
+        let first = allocator.allocate(10).unwrap();
+        allocator.allocate(10).unwrap();
+        allocator.free(first);
+        assert_eq!(allocator.allocate(4), Some(0..4));
+        assert_eq!(allocator.allocate(8), Some(20..28));
+        assert_eq!(allocator.free, vec![4..10, 28..100]);
+    }
+
+    #[test]
+    fn growing_extends_the_free_tail() {
+        let mut allocator = RangeAllocator::new(16);
+        assert_eq!(allocator.allocate(12), Some(0..12));
+        assert_eq!(allocator.allocate(8), None);
+        allocator.grow(32);
+        assert_eq!(allocator.free, vec![12..32]);
+        assert_eq!(allocator.allocate(8), Some(12..20));
+    }
+
+    #[test]
+    fn growing_a_full_allocator_adds_a_range() {
+        let mut allocator = RangeAllocator::new(16);
+        allocator.allocate(16).unwrap();
+        allocator.grow(32);
+        assert_eq!(allocator.free, vec![16..32]);
+    }
+
+    #[test]
+    fn freeing_everything_restores_one_range() {
+        let mut allocator = RangeAllocator::new(64);
+        let mut ranges: Vec<_> = [5, 9, 1, 20, 13]
+            .into_iter()
+            .map(|len| allocator.allocate(len).unwrap())
+            .collect();
+        allocator.grow(128);
+        // out of order
+        ranges.swap(0, 3);
+        ranges.swap(1, 4);
+        for range in ranges {
+            allocator.free(range);
+            assert!(
+                allocator
+                    .free
+                    .windows(2)
+                    .all(|pair| pair[0].end < pair[1].start)
+            );
+        }
+        assert_eq!(allocator.free, vec![0..128]);
+        assert_eq!(allocator.allocate(128), Some(0..128));
+    }
+
+    #[test]
+    fn empty_ranges_are_ignored() {
+        let mut allocator = RangeAllocator::new(8);
+        allocator.free(3..3);
+        assert_eq!(allocator.free, vec![0..8]);
+    }
+}
@@ -0,0 +1,240 @@
+use ab_glyph::{Font, FontArc, GlyphId, PxScale};
+use wgpu::{Device, Queue};
+
+use crate::{
+    app::State,
+    graphics::{
+        Material, Outline, Shadow, Vertex,
+        renderer::{
+            glyph_atlas::{GlyphAtlas, GlyphBitmap, GlyphKey},
+            msdf,
+            texture::TextureRegistry,
+        },
+        text::{self, FontId, Text, TextRendering},
+    },
+};
+
+/// Size distance field glyphs are generated at, whatever size they're drawn at.
+const MSDF_PX: u32 = 48;
+/// Pixels of the generated size the distance fields span, twice the widest outline.
+const MSDF_RANGE: f32 = 8.0;
+
+/// What a text object's mesh was last built from, rebuilt when any of it changes.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TextLayoutKey {
+    text: Text,
+    px: u32,
+    atlas_generation: u64,
+}
+impl TextLayoutKey {
+    fn matches(&self, text: &Text, px: u32, atlas_generation: u64) -> bool {
+        self.px == px && self.atlas_generation == atlas_generation && self.text == *text
+    }
+}
+
+/// Loaded fonts and the glyph atlases, turns [`Text`] into meshes.
+pub struct TextRenderer {
+    fonts: Vec<FontArc>,
+    atlas: GlyphAtlas,
+    msdf_atlas: GlyphAtlas,
+}
+impl Default for TextRenderer {
+    fn default() -> Self {
+        Self {
+            fonts: Vec::new(),
+            atlas: GlyphAtlas::new(false),
+            msdf_atlas: GlyphAtlas::new(true),
+        }
+    }
+}
+impl TextRenderer {
+    /// Parses a TrueType or OpenType font.
+    pub fn load_font(&mut self, data: Vec<u8>) -> anyhow::Result<FontId> {
+        let font = FontArc::try_from_vec(data)
+            .map_err(|err| anyhow::anyhow!("Unable to load font: {err}"))?;
+        self.fonts.push(font);
+
+        Ok(FontId(self.fonts.len() as u32 - 1))
+    }
+
+    #[must_use]
+    pub fn font(&self, id: FontId) -> Option<&FontArc> {
+        self.fonts.get(id.0 as usize)
+    }
+
+    /// Rebuilds the meshes of text objects that changed. Bitmap glyphs are rasterized at
+    /// `pixels_per_unit`, so text is sharp where a world unit covers that many pixels.
+    pub fn prepare(
+        &mut self,
+        device: &Device,
+        queue: &Queue,
+        textures: &mut TextureRegistry,
+        state: &mut State,
+        pixels_per_unit: f32,
+    ) {
+        // a grown or restarted atlas invalidates the meshes built before it in the same pass,
+        // which the next pass rebuilds
+        let texts = state
+            .objects()
+            .filter(|(_, obj)| obj.text.is_some())
+            .count();
+        for _ in 0..=texts {
+            let generations = (self.atlas.generation(), self.msdf_atlas.generation());
+            for (_, obj) in state.objects_mut() {
+                let Some(text) = &obj.text else {
+                    continue;
+                };
+                let (px, atlas_generation) = match text.rendering {
+                    TextRendering::Bitmap => (
+                        (text.size * pixels_per_unit).round().max(1.0) as u32,
+                        self.atlas.generation(),
+                    ),
+                    TextRendering::Msdf => (MSDF_PX, self.msdf_atlas.generation()),
+                };
+                if obj
+                    .text_layout_key()
+                    .is_some_and(|key| key.matches(text, px, atlas_generation))
+                {
+                    continue;
+                }
+                let key = TextLayoutKey {
+                    text: text.clone(),
+                    px,
+                    atlas_generation,
+                };
+
+                let (vertices, indices) = self.build_mesh(device, queue, textures, &key);
+                obj.mesh.set_vertices(vertices);
+                obj.mesh.set_indices(indices);
+                (obj.texture, obj.material) = match text.rendering {
+                    TextRendering::Bitmap => (self.atlas.texture(), Material::Standard),
+                    TextRendering::Msdf => (self.msdf_atlas.texture(), msdf_material(text)),
+                };
+                obj.set_text_layout_key(key);
+            }
+            if generations == (self.atlas.generation(), self.msdf_atlas.generation()) {
+                return;
+            }
+        }
+        log::warn!("Glyph atlases kept changing, some text may show wrong glyphs this frame");
+    }
+
+    fn build_mesh(
+        &mut self,
+        device: &Device,
+        queue: &Queue,
+        textures: &mut TextureRegistry,
+        key: &TextLayoutKey,
+    ) -> (Vec<Vertex>, Vec<u32>) {
+        let text = &key.text;
+        let Some(font) = self.fonts.get(text.font.0 as usize) else {
+            log::warn!("Unknown font {:?}, drawing no text", text.font);
+            return (Vec::new(), Vec::new());
+        };
+        let (atlas, bitmap) = match text.rendering {
+            TextRendering::Bitmap => (&mut self.atlas, true),
+            TextRendering::Msdf => (&mut self.msdf_atlas, false),
+        };
+
+        let layout = text::layout(font, text, key.px as f32);
+        let units_per_pixel = text.size / key.px as f32;
+        let mut vertices = Vec::with_capacity(layout.glyphs.len() * 4);
+        let mut indices = Vec::with_capacity(layout.glyphs.len() * 6);
+        for glyph in layout.glyphs {
+            let glyph_key = GlyphKey {
+                font: text.font,
+                glyph: glyph.id,
+                px: key.px,
+            };
+            let rasterize = || {
+                if bitmap {
+                    rasterize_coverage(font, glyph.id, key.px as f32)
+                } else {
+                    msdf::generate(font, glyph.id, key.px as f32, MSDF_RANGE)
+                }
+            };
+            let placed = match atlas.glyph(device, queue, textures, glyph_key, rasterize) {
+                Ok(Some(placed)) => placed,
+                Ok(None) => continue,
+                Err(err) => {
+                    log::warn!("Unable to rasterize {:?}: {err}", glyph.id);
+                    continue;
+                }
+            };
+
+            // whole pixels keep bitmaps as sharp as they were rasterized, distance fields
+            // are resampled anyway
+            let [x, y] = if bitmap {
+                glyph.position.map(f32::round)
+            } else {
+                glyph.position
+            };
+            let left = x + placed.offset[0];
+            let top = y + placed.offset[1];
+            let [width, height] = placed.size.map(|size| size as f32);
+            let (atlas_width, atlas_height) = atlas.size();
+            let uv_min = [
+                placed.origin[0] as f32 / atlas_width as f32,
+                placed.origin[1] as f32 / atlas_height as f32,
+            ];
+            let uv_max = [
+                uv_min[0] + width / atlas_width as f32,
+                uv_min[1] + height / atlas_height as f32,
+            ];
+            let corner = |x: f32, y: f32, uv: [f32; 2]| {
+                Vertex::new([x * units_per_pixel, -y * units_per_pixel], text.color).with_uv(uv)
+            };
+
+            let first = vertices.len() as u32;
+            vertices.extend([
+                corner(left, top, uv_min),
+                corner(left, top + height, [uv_min[0], uv_max[1]]),
+                corner(left + width, top + height, uv_max),
+                corner(left + width, top, [uv_max[0], uv_min[1]]),
+            ]);
+            indices.extend([0, 1, 2, 0, 2, 3].map(|index| first + index));
+        }
+
+        (vertices, indices)
+    }
+}
+
+/// White texels with the glyph's coverage as alpha, so vertex colors tint it.
+fn rasterize_coverage(font: &FontArc, glyph: GlyphId, px: f32) -> Option<GlyphBitmap> {
+    let outlined = font.outline_glyph(glyph.with_scale(PxScale::from(px)))?;
+    let bounds = outlined.px_bounds();
+    let (width, height) = (bounds.width() as u32, bounds.height() as u32);
+    let mut pixels = vec![u8::MAX; width as usize * height as usize * 4];
+    outlined.draw(|x, y, coverage| {
+        let alpha = (y * width + x) as usize * 4 + 3;
+        pixels[alpha] = (coverage.clamp(0.0, 1.0) * 255.0).round() as u8;
+    });
+
+    Some(GlyphBitmap {
+        width,
+        height,
+        pixels,
+        offset: [bounds.min.x, bounds.min.y],
+    })
+}
+
+/// The text's outline and shadow in texels of the distance field atlas.
+fn msdf_material(text: &Text) -> Material {
+    let texels_per_unit = MSDF_PX as f32 / text.size.max(f32::EPSILON);
+    Material::MsdfText {
+        range: MSDF_RANGE,
+        outline: text.outline.map(|outline| {
+            Outline::new(
+                (outline.width * texels_per_unit).min(MSDF_RANGE * 0.5),
+                outline.color,
+            )
+        }),
+        shadow: text.shadow.map(|shadow| {
+            Shadow::new(
+                shadow.offset.map(|offset| offset * texels_per_unit),
+                shadow.color,
+            )
+            .with_softness(shadow.softness * texels_per_unit)
+        }),
+    }
+}
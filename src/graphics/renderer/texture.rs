@@ -0,0 +1,559 @@
+use std::collections::BTreeMap;
+
+use wgpu::*;
+
+/// Texture sized to a render target together with its default view.
+pub struct RenderTexture {
+    pub texture: Texture,
+    pub view: TextureView,
+}
+impl RenderTexture {
+    #[must_use]
+    pub fn new(
+        device: &Device,
+        label: &str,
+        width: u32,
+        height: u32,
+        format: TextureFormat,
+        sample_count: u32,
+        usage: TextureUsages,
+    ) -> Self {
+        let texture = device.create_texture(&TextureDescriptor {
+            label: Some(label),
+            size: Extent3d {
+                width: width.max(1),
+                height: height.max(1),
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count,
+            dimension: TextureDimension::D2,
+            format,
+            usage,
+            view_formats: &[],
+        });
+        let view = texture.create_view(&TextureViewDescriptor::default());
+
+        Self { texture, view }
+    }
+
+    #[must_use]
+    pub fn size(&self) -> (u32, u32) {
+        (self.texture.width(), self.texture.height())
+    }
+}
+
+/// Format of every sampleable texture: images are sRGB and render targets are sampled back as
+/// linear values no matter which format the surface got.
+pub const TEXTURE_FORMAT: TextureFormat = TextureFormat::Rgba8UnormSrgb;
+
+/// Handle of a texture owned by [`TextureRegistry`], never reused within a session.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct TextureId(u64);
+#[cfg(test)]
+impl TextureId {
+    /// Stands in for a registered texture where there's no device.
+    pub(crate) fn for_tests(id: u64) -> Self {
+        Self(id)
+    }
+}
+
+enum TextureSource {
+    /// Tightly packed sRGB RGBA rows, kept to re-upload after a device loss.
+    Pixels(Vec<u8>),
+    /// Like `Pixels`, but sampled as stored instead of decoded from sRGB.
+    Data(Vec<u8>),
+    RenderTarget,
+    /// Written by compute shaders, RGBA8 sampled as stored.
+    Storage,
+}
+impl TextureSource {
+    fn pixels_mut(&mut self) -> Option<&mut Vec<u8>> {
+        match self {
+            Self::Pixels(pixels) | Self::Data(pixels) => Some(pixels),
+            Self::RenderTarget | Self::Storage => None,
+        }
+    }
+
+    fn format(&self) -> TextureFormat {
+        match self {
+            Self::Pixels(_) | Self::RenderTarget => TEXTURE_FORMAT,
+            Self::Data(_) | Self::Storage => TextureFormat::Rgba8Unorm,
+        }
+    }
+}
+
+struct TextureEntry {
+    source: TextureSource,
+    texture: RenderTexture,
+    bind_group: BindGroup,
+}
+
+/// Every texture objects can be drawn with. Objects without one are drawn with a 1x1 white
+/// texture, so their vertex colors come through unchanged.
+pub struct TextureRegistry {
+    layout: BindGroupLayout,
+    sampler: Sampler,
+    filter: FilterMode,
+    /// Linear whatever the filter, distance fields and normals fall apart sampled nearest.
+    data_sampler: Sampler,
+    white: TextureEntry,
+    textures: BTreeMap<TextureId, TextureEntry>,
+    next_id: u64,
+}
+impl TextureRegistry {
+    pub fn new(device: &Device, queue: &Queue) -> Self {
+        let layout = Self::bind_group_layout(device);
+        let filter = FilterMode::Linear;
+        let sampler = create_sampler(device, filter);
+        let data_sampler = create_sampler(device, FilterMode::Linear);
+        let white = TextureEntry::new(
+            device,
+            queue,
+            &layout,
+            &sampler,
+            "White Texture",
+            (1, 1),
+            TextureSource::Pixels(vec![u8::MAX; 4]),
+        );
+
+        Self {
+            layout,
+            sampler,
+            filter,
+            data_sampler,
+            white,
+            textures: BTreeMap::new(),
+            next_id: 0,
+        }
+    }
+
+    pub fn bind_group_layout(device: &Device) -> BindGroupLayout {
+        device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+            label: Some("Texture Bind Group Layout"),
+            entries: &[
+                BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: ShaderStages::FRAGMENT,
+                    ty: BindingType::Texture {
+                        sample_type: TextureSampleType::Float { filterable: true },
+                        view_dimension: TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: ShaderStages::FRAGMENT,
+                    ty: BindingType::Sampler(SamplerBindingType::Filtering),
+                    count: None,
+                },
+            ],
+        })
+    }
+
+    #[must_use]
+    pub fn layout(&self) -> &BindGroupLayout {
+        &self.layout
+    }
+
+    /// `pixels` are sRGB RGBA8 rows without padding.
+    pub fn create_from_rgba(
+        &mut self,
+        device: &Device,
+        queue: &Queue,
+        width: u32,
+        height: u32,
+        pixels: Vec<u8>,
+    ) -> anyhow::Result<TextureId> {
+        check_size(device, width, height)?;
+        check_pixels(width, height, &pixels)?;
+
+        Ok(self.insert(device, queue, width, height, TextureSource::Pixels(pixels)))
+    }
+
+    /// Like [`TextureRegistry::create_from_rgba`], but the texels aren't colors and are
+    /// sampled as they are, e.g. distance fields.
+    pub fn create_from_data(
+        &mut self,
+        device: &Device,
+        queue: &Queue,
+        width: u32,
+        height: u32,
+        pixels: Vec<u8>,
+    ) -> anyhow::Result<TextureId> {
+        check_size(device, width, height)?;
+        check_pixels(width, height, &pixels)?;
+
+        Ok(self.insert(device, queue, width, height, TextureSource::Data(pixels)))
+    }
+
+    /// Texture that can be both rendered into and sampled, its contents are undefined until
+    /// the first pass renders into it.
+    pub fn create_render_target(
+        &mut self,
+        device: &Device,
+        queue: &Queue,
+        width: u32,
+        height: u32,
+    ) -> anyhow::Result<TextureId> {
+        check_size(device, width, height)?;
+
+        Ok(self.insert(device, queue, width, height, TextureSource::RenderTarget))
+    }
+
+    /// Texture compute shaders can write to and objects can sample, zeroed at first.
+    pub fn create_storage(
+        &mut self,
+        device: &Device,
+        queue: &Queue,
+        width: u32,
+        height: u32,
+    ) -> anyhow::Result<TextureId> {
+        check_size(device, width, height)?;
+
+        Ok(self.insert(device, queue, width, height, TextureSource::Storage))
+    }
+
+    /// Overwrites a `width` x `height` block at `(x, y)` of an image texture, `pixels` are
+    /// RGBA8 rows without padding, encoded like the texture was created.
+    pub fn write_rgba_region(
+        &mut self,
+        queue: &Queue,
+        id: TextureId,
+        (x, y): (u32, u32),
+        (width, height): (u32, u32),
+        pixels: &[u8],
+    ) -> anyhow::Result<()> {
+        let Some(entry) = self.textures.get_mut(&id) else {
+            anyhow::bail!("Unknown texture {id:?}");
+        };
+        let (texture_width, texture_height) = entry.texture.size();
+        let Some(stored) = entry.source.pixels_mut() else {
+            anyhow::bail!("{id:?} isn't an image, it can't be written to");
+        };
+        if x + width > texture_width || y + height > texture_height {
+            anyhow::bail!(
+                "Region {width}x{height} at ({x}, {y}) doesn't fit {id:?} of {texture_width}x{texture_height}"
+            );
+        }
+        if pixels.len() != width as usize * height as usize * 4 {
+            anyhow::bail!(
+                "Region of {width}x{height} needs {} bytes of RGBA, got {}",
+                width as usize * height as usize * 4,
+                pixels.len()
+            );
+        }
+        if width == 0 || height == 0 {
+            return Ok(());
+        }
+
+        let row_bytes = width as usize * 4;
+        for (row, source) in pixels.chunks_exact(row_bytes).enumerate() {
+            let start = ((y as usize + row) * texture_width as usize + x as usize) * 4;
+            stored[start..start + row_bytes].copy_from_slice(source);
+        }
+        queue.write_texture(
+            TexelCopyTextureInfo {
+                texture: &entry.texture.texture,
+                mip_level: 0,
+                origin: Origin3d { x, y, z: 0 },
+                aspect: TextureAspect::All,
+            },
+            pixels,
+            TexelCopyBufferLayout {
+                offset: 0,
+                bytes_per_row: Some(width * 4),
+                rows_per_image: Some(height),
+            },
+            Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1,
+            },
+        );
+
+        Ok(())
+    }
+
+    /// Gives an image texture a new size under the same id. The old contents stay in the top
+    /// left corner, new texels are transparent.
+    pub fn resize_image(
+        &mut self,
+        device: &Device,
+        queue: &Queue,
+        id: TextureId,
+        width: u32,
+        height: u32,
+    ) -> anyhow::Result<()> {
+        check_size(device, width, height)?;
+        let Some(entry) = self.textures.remove(&id) else {
+            anyhow::bail!("Unknown texture {id:?}");
+        };
+        let (old_width, old_height) = entry.texture.size();
+        let (old, data) = match entry.source {
+            TextureSource::Pixels(old) => (old, false),
+            TextureSource::Data(old) => (old, true),
+            TextureSource::RenderTarget | TextureSource::Storage => {
+                self.textures.insert(id, entry);
+                anyhow::bail!("{id:?} isn't an image, it can't be resized");
+            }
+        };
+
+        let mut pixels = vec![0; width as usize * height as usize * 4];
+        let row_bytes = old_width.min(width) as usize * 4;
+        for row in 0..old_height.min(height) as usize {
+            let source = row * old_width as usize * 4;
+            let target = row * width as usize * 4;
+            pixels[target..target + row_bytes].copy_from_slice(&old[source..source + row_bytes]);
+        }
+        let source = if data {
+            TextureSource::Data(pixels)
+        } else {
+            TextureSource::Pixels(pixels)
+        };
+        let entry = TextureEntry::new(
+            device,
+            queue,
+            &self.layout,
+            self.sampler(&source),
+            &texture_label(id),
+            (width, height),
+            source,
+        );
+        self.textures.insert(id, entry);
+
+        Ok(())
+    }
+
+    /// The texture is dropped once the frames in flight are done with it.
+    pub fn remove(&mut self, id: TextureId) -> bool {
+        self.textures.remove(&id).is_some()
+    }
+
+    #[must_use]
+    pub fn contains(&self, id: TextureId) -> bool {
+        self.textures.contains_key(&id)
+    }
+
+    #[must_use]
+    pub fn size(&self, id: TextureId) -> Option<(u32, u32)> {
+        self.textures.get(&id).map(|entry| entry.texture.size())
+    }
+
+    #[must_use]
+    pub fn view(&self, id: TextureId) -> Option<&TextureView> {
+        self.textures.get(&id).map(|entry| &entry.texture.view)
+    }
+
+    #[must_use]
+    pub fn texture(&self, id: TextureId) -> Option<&Texture> {
+        self.textures.get(&id).map(|entry| &entry.texture.texture)
+    }
+
+    /// Bind group of `id`, the white texture for `None` and for removed textures.
+    #[must_use]
+    pub fn bind_group(&self, id: Option<TextureId>) -> &BindGroup {
+        id.and_then(|id| self.textures.get(&id))
+            .map_or(&self.white.bind_group, |entry| &entry.bind_group)
+    }
+
+    #[must_use]
+    pub fn filter(&self) -> FilterMode {
+        self.filter
+    }
+
+    /// Samples every texture but the data ones with `filter` from now on, `Nearest` keeps
+    /// texels square.
+    pub fn set_filter(&mut self, device: &Device, filter: FilterMode) {
+        if self.filter == filter {
+            return;
+        }
+        self.filter = filter;
+        self.sampler = create_sampler(device, filter);
+        let (layout, sampler) = (&self.layout, &self.sampler);
+        let white = &mut self.white;
+        white.bind_group = create_bind_group(
+            device,
+            layout,
+            sampler,
+            "White Texture",
+            &white.texture.view,
+        );
+        for (id, entry) in &mut self.textures {
+            if let TextureSource::Data(_) = entry.source {
+                continue;
+            }
+            let label = texture_label(*id);
+            entry.bind_group =
+                create_bind_group(device, layout, sampler, &label, &entry.texture.view);
+        }
+    }
+
+    /// Recreates every texture on a new device. Images are uploaded again, render targets and
+    /// storage textures come back empty.
+    pub fn recreate(&mut self, device: &Device, queue: &Queue) {
+        let mut recreated = Self::new(device, queue);
+        recreated.set_filter(device, self.filter);
+        for (id, entry) in std::mem::take(&mut self.textures) {
+            let entry = TextureEntry::new(
+                device,
+                queue,
+                &recreated.layout,
+                recreated.sampler(&entry.source),
+                &texture_label(id),
+                entry.texture.size(),
+                entry.source,
+            );
+            recreated.textures.insert(id, entry);
+        }
+        recreated.next_id = self.next_id;
+
+        *self = recreated;
+    }
+
+    fn insert(
+        &mut self,
+        device: &Device,
+        queue: &Queue,
+        width: u32,
+        height: u32,
+        source: TextureSource,
+    ) -> TextureId {
+        let id = TextureId(self.next_id);
+        self.next_id += 1;
+        let entry = TextureEntry::new(
+            device,
+            queue,
+            &self.layout,
+            self.sampler(&source),
+            &texture_label(id),
+            (width, height),
+            source,
+        );
+        self.textures.insert(id, entry);
+
+        id
+    }
+
+    fn sampler(&self, source: &TextureSource) -> &Sampler {
+        match source {
+            TextureSource::Data(_) => &self.data_sampler,
+            TextureSource::Pixels(_) | TextureSource::RenderTarget | TextureSource::Storage => {
+                &self.sampler
+            }
+        }
+    }
+}
+
+impl TextureEntry {
+    fn new(
+        device: &Device,
+        queue: &Queue,
+        layout: &BindGroupLayout,
+        sampler: &Sampler,
+        label: &str,
+        (width, height): (u32, u32),
+        source: TextureSource,
+    ) -> Self {
+        // every texture can be read back
+        let usage = TextureUsages::TEXTURE_BINDING
+            | TextureUsages::COPY_SRC
+            | match source {
+                TextureSource::Pixels(_) | TextureSource::Data(_) => TextureUsages::COPY_DST,
+                TextureSource::RenderTarget => TextureUsages::RENDER_ATTACHMENT,
+                TextureSource::Storage => TextureUsages::STORAGE_BINDING,
+            };
+        let texture = RenderTexture::new(device, label, width, height, source.format(), 1, usage);
+
+        if let TextureSource::Pixels(pixels) | TextureSource::Data(pixels) = &source {
+            queue.write_texture(
+                TexelCopyTextureInfo {
+                    texture: &texture.texture,
+                    mip_level: 0,
+                    origin: Origin3d::ZERO,
+                    aspect: TextureAspect::All,
+                },
+                pixels,
+                TexelCopyBufferLayout {
+                    offset: 0,
+                    bytes_per_row: Some(width * 4),
+                    rows_per_image: Some(height),
+                },
+                Extent3d {
+                    width,
+                    height,
+                    depth_or_array_layers: 1,
+                },
+            );
+        }
+
+        let bind_group = create_bind_group(device, layout, sampler, label, &texture.view);
+
+        Self {
+            source,
+            texture,
+            bind_group,
+        }
+    }
+}
+
+fn create_bind_group(
+    device: &Device,
+    layout: &BindGroupLayout,
+    sampler: &Sampler,
+    label: &str,
+    view: &TextureView,
+) -> BindGroup {
+    device.create_bind_group(&BindGroupDescriptor {
+        label: Some(&format!("{label} Bind Group")),
+        layout,
+        entries: &[
+            BindGroupEntry {
+                binding: 0,
+                resource: BindingResource::TextureView(view),
+            },
+            BindGroupEntry {
+                binding: 1,
+                resource: BindingResource::Sampler(sampler),
+            },
+        ],
+    })
+}
+
+fn texture_label(id: TextureId) -> String {
+    format!("Texture #{}", id.0)
+}
+
+fn create_sampler(device: &Device, filter: FilterMode) -> Sampler {
+    device.create_sampler(&SamplerDescriptor {
+        label: Some(match filter {
+            FilterMode::Nearest => "Nearest Texture Sampler",
+            FilterMode::Linear => "Linear Texture Sampler",
+        }),
+        mag_filter: filter,
+        min_filter: filter,
+        ..Default::default()
+    })
+}
+
+fn check_pixels(width: u32, height: u32, pixels: &[u8]) -> anyhow::Result<()> {
+    let expected = width as usize * height as usize * 4;
+    if pixels.len() != expected {
+        anyhow::bail!(
+            "Texture of {width}x{height} needs {expected} bytes of RGBA, got {}",
+            pixels.len()
+        );
+    }
+
+    Ok(())
+}
+
+fn check_size(device: &Device, width: u32, height: u32) -> anyhow::Result<()> {
+    let max = device.limits().max_texture_dimension_2d;
+    if width == 0 || height == 0 || width > max || height > max {
+        anyhow::bail!("Texture size {width}x{height} out of range, device allows 1..={max}");
+    }
+
+    Ok(())
+}
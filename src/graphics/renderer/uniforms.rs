@@ -3,19 +3,21 @@ use std::{any::type_name, mem::size_of, num::NonZeroU64};
 use bytemuck::{Pod, Zeroable};
 use wgpu::*;
 
+use crate::graphics::renderer::buffer::TrackedBuffer;
+
 pub struct GlobalUniforms {
     layout: BindGroupLayout,
     bind_group: BindGroup,
-    time_buffer: Buffer,
-    surface_buffer: Buffer,
+    time_buffer: TrackedBuffer,
+    surface_buffer: TrackedBuffer,
 }
 impl GlobalUniforms {
     pub fn new(device: &Device) -> Self {
         // TODO: we can make it safer by makind a method that will return tuples (binding, entry, buffer)
-        let time_entry = entry::<TimeUniform>(0, ShaderStages::VERTEX);
+        let time_entry = entry::<TimeUniform>(0, ShaderStages::VERTEX_FRAGMENT);
         let time_buffer = create_buffer::<TimeUniform>(device);
 
-        let surface_entry = entry::<SurfaceSizeUniform>(1, ShaderStages::VERTEX);
+        let surface_entry = entry::<SurfaceSizeUniform>(1, ShaderStages::VERTEX_FRAGMENT);
         let surface_buffer = create_buffer::<SurfaceSizeUniform>(device);
 
         let layout = create_layout(device, &[time_entry, surface_entry]);
@@ -110,13 +112,16 @@ fn create_layout(device: &Device, entries: &[BindGroupLayoutEntry]) -> BindGroup
 }
 
 #[must_use]
-fn create_buffer<T: Pod>(device: &Device) -> Buffer {
-    device.create_buffer(&BufferDescriptor {
-        label: Some(&format!("{} Buffer", type_name::<T>())),
-        size: size_of::<T>() as u64,
-        usage: BufferUsages::UNIFORM | BufferUsages::COPY_DST,
-        mapped_at_creation: false,
-    })
+fn create_buffer<T: Pod>(device: &Device) -> TrackedBuffer {
+    TrackedBuffer::new(
+        device,
+        &BufferDescriptor {
+            label: Some(&format!("{} Buffer", type_name::<T>())),
+            size: size_of::<T>() as u64,
+            usage: BufferUsages::UNIFORM | BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        },
+    )
 }
 
 #[must_use]
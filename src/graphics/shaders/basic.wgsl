@@ -6,23 +6,39 @@ struct TimeUniform {
     time: f32,
 }
 
+struct CameraUniform {
+    // half the view in world units, before the zoom
+    extent: vec2f,
+    position: vec2f,
+    rotation: f32,
+    zoom: f32,
+    // half the view in pixels while objects snap to them, zero otherwise
+    pixels: vec2f,
+}
+
 struct VertexInput {
     @location(0) position: vec2f,
-    @location(1) color: vec3f,
+    @location(1) color: vec4f,
+    @location(2) uv: vec2f,
 }
 
 struct VertexOutput {
     @builtin(position) position: vec4f,
-    @location(0) color: vec3f,
+    @location(0) color: vec4f,
+    @location(1) uv: vec2f,
 }
 
 struct TransformUniform {
-    @location(0) position: vec2f,
-    @location(1) rotation: f32,
-    @location(2) scale: vec2f,
+    position: vec2f,
+    rotation: f32,
+    scale: vec2f,
 }
 
 const tau = 6.283185307179586;
+
+// set when the surface format doesn't gamma encode on write
+override ENCODE_SRGB: bool = false;
+
 @group(0) @binding(0)
 var<uniform> time_uniform: TimeUniform;
 
@@ -32,20 +48,40 @@ var<uniform> surface_uniform: SurfaceUniform;
 @group(1) @binding(0)
 var<uniform> transform_uniform: TransformUniform;
 
+@group(2) @binding(0)
+var object_texture: texture_2d<f32>;
+
+@group(2) @binding(1)
+var object_sampler: sampler;
+
+@group(3) @binding(0)
+var<uniform> camera: CameraUniform;
+
 
 @vertex
 fn vs_main(in: VertexInput) -> VertexOutput {
-    let position = scale(in.position, surface_uniform.size) + transform_uniform.position;
+    let local = rotate_2d(in.position * transform_uniform.scale, transform_uniform.rotation);
 
     var out: VertexOutput;
-    out.position = vec4f(position, 0, 1);
+    out.position = vec4f(to_view(local), 0, 1);
     out.color = in.color;
+    out.uv = in.uv;
     return out;
 }
 
 @fragment
 fn fs_main(in: VertexOutput) -> @location(0) vec4f {
-    return vec4f(in.color, 1);
+    let color = in.color * textureSample(object_texture, object_sampler, in.uv);
+    if ENCODE_SRGB {
+        return vec4f(linear_to_srgb(color.rgb), color.a);
+    }
+    return color;
+}
+
+fn linear_to_srgb(color: vec3f) -> vec3f {
+    let low = color * 12.92;
+    let high = 1.055 * pow(color, vec3f(1.0 / 2.4)) - 0.055;
+    return select(high, low, color <= vec3f(0.0031308));
 }
 
 fn rotate_2d(v: vec2f, angle: f32) -> vec2f {
@@ -54,7 +90,17 @@ fn rotate_2d(v: vec2f, angle: f32) -> vec2f {
     return vec2f(v.x * c - v.y * s, v.x * s + v.y * c);
 }
 
-fn scale(v: vec2f, surface_size: vec2f) -> vec2f {
-    let smaller_dimension = min(surface_size.x, surface_size.y);
-    return vec2f(v.x / surface_size.x, v.y / surface_size.y) * smaller_dimension;
+// `local` around the object's position, placed in the view
+fn to_view(local: vec2f) -> vec2f {
+    let origin = rotate_2d(transform_uniform.position - camera.position, -camera.rotation);
+    let scale = camera.zoom / camera.extent;
+    return snap(origin * scale) + rotate_2d(local, -camera.rotation) * scale;
+}
+
+// moves whole objects a pixel at a time instead of letting them shimmer between pixels
+fn snap(view: vec2f) -> vec2f {
+    if camera.pixels.x <= 0.0 {
+        return view;
+    }
+    return round(view * camera.pixels) / camera.pixels;
 }
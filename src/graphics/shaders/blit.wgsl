@@ -0,0 +1,13 @@
+// Copies the end of the post-processing chain to the surface.
+
+@fragment
+fn fs_copy(in: FullscreenOutput) -> @location(0) vec4f {
+    return sample_input(in.uv);
+}
+
+// for surface formats that don't gamma encode on write
+@fragment
+fn fs_encode_srgb(in: FullscreenOutput) -> @location(0) vec4f {
+    let color = sample_input(in.uv);
+    return vec4f(linear_to_srgb(max(color.rgb, vec3f(0.0))), color.a);
+}
@@ -0,0 +1,46 @@
+// params: 0 threshold, 1 intensity, 2 radius (blur tap spacing in texels)
+// passes: fs_threshold (input -> half size), fs_blur_horizontal, fs_blur_vertical,
+// fs_composite (input + blurred -> output)
+
+@group(2) @binding(0)
+var bloom_texture: texture_2d<f32>;
+
+@fragment
+fn fs_threshold(in: FullscreenOutput) -> @location(0) vec4f {
+    let color = sample_input(in.uv).rgb;
+    let brightness = max(color.r, max(color.g, color.b));
+    let contribution = max(brightness - param(0u), 0.0) / max(brightness, 0.0001);
+
+    return vec4f(color * contribution, 1.0);
+}
+
+@fragment
+fn fs_blur_horizontal(in: FullscreenOutput) -> @location(0) vec4f {
+    return blur(in.uv, vec2f(1.0 / input_size().x, 0.0) * param(2u));
+}
+
+@fragment
+fn fs_blur_vertical(in: FullscreenOutput) -> @location(0) vec4f {
+    return blur(in.uv, vec2f(0.0, 1.0 / input_size().y) * param(2u));
+}
+
+// 9 tap gaussian along `direction`
+fn blur(uv: vec2f, direction: vec2f) -> vec4f {
+    var weights = array<f32, 5>(0.227027, 0.1945946, 0.1216216, 0.054054, 0.016216);
+
+    var color = sample_input(uv).rgb * weights[0];
+    for (var i = 1; i < 5; i++) {
+        let offset = direction * f32(i);
+        color += (sample_input(uv + offset).rgb + sample_input(uv - offset).rgb) * weights[i];
+    }
+
+    return vec4f(color, 1.0);
+}
+
+@fragment
+fn fs_composite(in: FullscreenOutput) -> @location(0) vec4f {
+    let color = sample_input(in.uv);
+    let bloom = textureSampleLevel(bloom_texture, input_sampler, in.uv, 0.0).rgb;
+
+    return vec4f(color.rgb + bloom * param(1u), color.a);
+}
@@ -0,0 +1,14 @@
+// params: 0 strength, the red/blue offset at the corners as a fraction of the screen
+
+@fragment
+fn fs_main(in: FullscreenOutput) -> @location(0) vec4f {
+    let offset = (in.uv - 0.5) * param(0u);
+    let center = sample_input(in.uv);
+
+    return vec4f(
+        sample_input(in.uv + offset).r,
+        center.g,
+        sample_input(in.uv - offset).b,
+        center.a,
+    );
+}
@@ -0,0 +1,17 @@
+// params: 0 intensity. The LUT maps sRGB encoded colors to sRGB encoded colors.
+
+@group(2) @binding(0)
+var lut_texture: texture_3d<f32>;
+
+@fragment
+fn fs_main(in: FullscreenOutput) -> @location(0) vec4f {
+    let color = sample_input(in.uv);
+    let encoded = clamp(linear_to_srgb(max(color.rgb, vec3f(0.0))), vec3f(0.0), vec3f(1.0));
+
+    // sample texel centers, the outermost texels hold 0.0 and 1.0
+    let size = f32(textureDimensions(lut_texture).x);
+    let coords = encoded * ((size - 1.0) / size) + 0.5 / size;
+    let graded = srgb_to_linear(textureSampleLevel(lut_texture, input_sampler, coords, 0.0).rgb);
+
+    return vec4f(mix(color.rgb, graded, param(0u)), color.a);
+}
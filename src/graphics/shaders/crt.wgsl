@@ -0,0 +1,26 @@
+// params: 0 curvature, 1 scanline intensity, 2 scanline count (0 = one per pixel row),
+// 3 scanline scroll speed in lines per second
+
+@fragment
+fn fs_main(in: FullscreenOutput) -> @location(0) vec4f {
+    let uv = barrel(in.uv, param(0u));
+    if any(uv < vec2f(0.0)) || any(uv > vec2f(1.0)) {
+        return vec4f(0.0, 0.0, 0.0, 1.0);
+    }
+    let color = sample_input(uv);
+
+    var lines = param(2u);
+    if lines <= 0.0 {
+        lines = input_size().y;
+    }
+    let scanline = 0.5 + 0.5 * cos(TAU * (uv.y * lines + time_uniform.time * param(3u)));
+    let shade = mix(1.0, scanline, param(1u));
+
+    return vec4f(color.rgb * shade, color.a);
+}
+
+fn barrel(uv: vec2f, curvature: f32) -> vec2f {
+    let centered = uv * 2.0 - 1.0;
+    let distortion = centered.yx * centered.yx * curvature;
+    return (centered * (1.0 + distortion)) * 0.5 + 0.5;
+}
@@ -0,0 +1,15 @@
+struct FullscreenOutput {
+    @builtin(position) position: vec4f,
+    @location(0) uv: vec2f,
+}
+
+// one triangle covering the whole screen, no vertex buffer needed: draw(0..3, 0..1)
+@vertex
+fn vs_fullscreen(@builtin(vertex_index) vertex_index: u32) -> FullscreenOutput {
+    let corner = vec2f(f32((vertex_index << 1u) & 2u), f32(vertex_index & 2u));
+
+    var out: FullscreenOutput;
+    out.position = vec4f(corner * 2.0 - 1.0, 0, 1);
+    out.uv = vec2f(corner.x, 1.0 - corner.y);
+    return out;
+}
@@ -0,0 +1,66 @@
+// FXAA, "console" variant: one directional blur along the detected edge, rejected when it
+// leaves the local luma range.
+
+const FXAA_REDUCE_MIN = 1.0 / 128.0;
+const FXAA_REDUCE_MUL = 1.0 / 8.0;
+const FXAA_SPAN_MAX = 8.0;
+
+@group(0) @binding(0)
+var scene: texture_2d<f32>;
+
+@group(0) @binding(1)
+var scene_sampler: sampler;
+
+@fragment
+fn fs_fxaa(in: FullscreenOutput) -> @location(0) vec4f {
+    let texel = 1.0 / vec2f(textureDimensions(scene));
+
+    let center = textureSampleLevel(scene, scene_sampler, in.uv, 0.0);
+    let luma_nw = luma(sample_at(in.uv + vec2f(-1.0, -1.0) * texel));
+    let luma_ne = luma(sample_at(in.uv + vec2f(1.0, -1.0) * texel));
+    let luma_sw = luma(sample_at(in.uv + vec2f(-1.0, 1.0) * texel));
+    let luma_se = luma(sample_at(in.uv + vec2f(1.0, 1.0) * texel));
+    let luma_m = luma(center.rgb);
+
+    let luma_min = min(luma_m, min(min(luma_nw, luma_ne), min(luma_sw, luma_se)));
+    let luma_max = max(luma_m, max(max(luma_nw, luma_ne), max(luma_sw, luma_se)));
+
+    var direction = vec2f(
+        -((luma_nw + luma_ne) - (luma_sw + luma_se)),
+        (luma_nw + luma_sw) - (luma_ne + luma_se),
+    );
+    let direction_reduce = max(
+        (luma_nw + luma_ne + luma_sw + luma_se) * 0.25 * FXAA_REDUCE_MUL,
+        FXAA_REDUCE_MIN,
+    );
+    let inverse_direction_min = 1.0 / (min(abs(direction.x), abs(direction.y)) + direction_reduce);
+    direction = clamp(
+        direction * inverse_direction_min,
+        vec2f(-FXAA_SPAN_MAX),
+        vec2f(FXAA_SPAN_MAX),
+    ) * texel;
+
+    let rgb_a = 0.5 * (
+        sample_at(in.uv + direction * (1.0 / 3.0 - 0.5)) +
+        sample_at(in.uv + direction * (2.0 / 3.0 - 0.5))
+    );
+    let rgb_b = rgb_a * 0.5 + 0.25 * (
+        sample_at(in.uv - direction * 0.5) +
+        sample_at(in.uv + direction * 0.5)
+    );
+
+    let luma_b = luma(rgb_b);
+    if luma_b < luma_min || luma_b > luma_max {
+        return vec4f(rgb_a, center.a);
+    }
+    return vec4f(rgb_b, center.a);
+}
+
+fn sample_at(uv: vec2f) -> vec3f {
+    return textureSampleLevel(scene, scene_sampler, uv, 0.0).rgb;
+}
+
+// sqrt approximates gamma, edges are detected on perceived rather than linear brightness
+fn luma(color: vec3f) -> f32 {
+    return dot(sqrt(max(color, vec3f(0.0))), vec3f(0.299, 0.587, 0.114));
+}
@@ -0,0 +1,6 @@
+// Bars around a letterboxed view, appended to fullscreen.wgsl and drawn scissored to each bar.
+
+@fragment
+fn fs_bar() -> @location(0) vec4f {
+    return vec4f(0.0, 0.0, 0.0, 1.0);
+}
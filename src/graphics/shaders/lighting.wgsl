@@ -0,0 +1,126 @@
+// Light accumulation, appended to fullscreen.wgsl. Starting from the ambient color every light
+// adds its quad, skipping the pixels its occluders' shadows marked in the stencil buffer. The
+// sum then multiplies the scene where the normal buffer says it's lit.
+
+struct CameraUniform {
+    // half the view in world units, before the zoom
+    extent: vec2f,
+    position: vec2f,
+    rotation: f32,
+    zoom: f32,
+    // half the view in pixels while objects snap to them, zero otherwise
+    pixels: vec2f,
+}
+
+struct LightInput {
+    @builtin(vertex_index) vertex_index: u32,
+    @location(0) position: vec2f,
+    // linear color times intensity
+    @location(1) color: vec4f,
+    // radius, falloff, tangent of the elevation
+    @location(2) params: vec4f,
+    // direction, cosines of the inner and outer angle; points have an outer cosine below -1
+    @location(3) cone: vec4f,
+}
+
+struct LightOutput {
+    @builtin(position) position: vec4f,
+    @location(0) offset: vec2f,
+    @location(1) @interpolate(flat) color: vec3f,
+    @location(2) @interpolate(flat) params: vec4f,
+    @location(3) @interpolate(flat) cone: vec4f,
+}
+
+// set when the scene's format doesn't gamma encode on write
+override ENCODE_SRGB: bool = false;
+
+@group(0) @binding(0)
+var<uniform> camera: CameraUniform;
+
+@group(1) @binding(0)
+var normal_buffer: texture_2d<f32>;
+
+@group(2) @binding(0)
+var light_buffer: texture_2d<f32>;
+
+@vertex
+fn vs_shadow(@location(0) position: vec2f) -> @builtin(position) vec4f {
+    return vec4f(to_view(position), 0, 1);
+}
+
+// shadows only mark the stencil buffer
+@fragment
+fn fs_shadow() -> @location(0) vec4f {
+    return vec4f(0.0);
+}
+
+// quad around the light's radius, two triangles without a vertex buffer
+@vertex
+fn vs_light(in: LightInput) -> LightOutput {
+    let corners = array(
+        vec2f(-1.0, -1.0),
+        vec2f(1.0, -1.0),
+        vec2f(1.0, 1.0),
+        vec2f(-1.0, -1.0),
+        vec2f(1.0, 1.0),
+        vec2f(-1.0, 1.0),
+    );
+    let offset = corners[in.vertex_index] * in.params.x;
+
+    var out: LightOutput;
+    out.position = vec4f(to_view(in.position + offset), 0, 1);
+    out.offset = offset;
+    out.color = in.color.rgb;
+    out.params = in.params;
+    out.cone = in.cone;
+    return out;
+}
+
+@fragment
+fn fs_light(in: LightOutput) -> @location(0) vec4f {
+    let distance = length(in.offset);
+    let attenuation = pow(saturate(1.0 - distance / in.params.x), in.params.y);
+    let direction = select(vec2f(0.0), in.offset / distance, distance > 0.0);
+
+    var cone = 1.0;
+    if in.cone.w >= -1.0 {
+        cone = smoothstep(in.cone.w, in.cone.z, dot(direction, in.cone.xy));
+    }
+
+    // lit from a fixed elevation, relative to a flat surface
+    let packed = textureLoad(normal_buffer, vec2i(in.position.xy), 0);
+    let xy = packed.xy * 2.0 - 1.0;
+    let normal = vec3f(xy, sqrt(max(1.0 - dot(xy, xy), 0.0)));
+    let to_light = normalize(vec3f(-direction, in.params.z));
+    let diffuse = max(dot(normal, to_light), 0.0) / to_light.z;
+
+    return vec4f(in.color * attenuation * cone * diffuse, 1.0);
+}
+
+// multiplied into the scene by the blend state
+@fragment
+fn fs_composite(in: FullscreenOutput) -> @location(0) vec4f {
+    let pixel = vec2i(in.position.xy);
+    let lit = textureLoad(normal_buffer, pixel, 0).b;
+    let light = mix(vec3f(1.0), textureLoad(light_buffer, pixel, 0).rgb, lit);
+    if ENCODE_SRGB {
+        return vec4f(linear_to_srgb(light), 1.0);
+    }
+    return vec4f(light, 1.0);
+}
+
+fn to_view(world: vec2f) -> vec2f {
+    return rotate_2d(world - camera.position, -camera.rotation) * camera.zoom / camera.extent;
+}
+
+fn linear_to_srgb(color: vec3f) -> vec3f {
+    let low = color * 12.92;
+    let high = 1.055 * pow(color, vec3f(1.0 / 2.4)) - 0.055;
+    return select(high, low, color <= vec3f(0.0031308));
+}
+
+fn rotate_2d(v: vec2f, angle: f32) -> vec2f {
+    let c = cos(angle);
+    let s = sin(angle);
+    return vec2f(v.x * c - v.y * s, v.x * s + v.y * c);
+}
@@ -0,0 +1,106 @@
+// Normal buffer of the lighting: per pixel the world space normal of the topmost object, packed
+// as (x, y) in rg and whether it's lit in b, blended over by the object's coverage.
+
+struct CameraUniform {
+    // half the view in world units, before the zoom
+    extent: vec2f,
+    position: vec2f,
+    rotation: f32,
+    zoom: f32,
+    // half the view in pixels while objects snap to them, zero otherwise
+    pixels: vec2f,
+}
+
+struct TransformUniform {
+    position: vec2f,
+    rotation: f32,
+    scale: vec2f,
+}
+
+struct VertexInput {
+    @location(0) position: vec2f,
+    @location(1) color: vec4f,
+    @location(2) uv: vec2f,
+}
+
+struct NormalsOutput {
+    @builtin(position) position: vec4f,
+    @location(0) uv: vec2f,
+    @location(1) alpha: f32,
+    @location(2) rotation: f32,
+    // signs of the scale, mirrored objects mirror their normals
+    @location(3) flip: vec2f,
+}
+
+@group(0) @binding(0)
+var normal_map: texture_2d<f32>;
+
+@group(0) @binding(1)
+var normal_sampler: sampler;
+
+@group(1) @binding(0)
+var<uniform> transform_uniform: TransformUniform;
+
+@group(2) @binding(0)
+var object_texture: texture_2d<f32>;
+
+@group(2) @binding(1)
+var object_sampler: sampler;
+
+@group(3) @binding(0)
+var<uniform> camera: CameraUniform;
+
+// same placement as vs_main in basic.wgsl
+@vertex
+fn vs_normals(in: VertexInput) -> NormalsOutput {
+    let local = rotate_2d(in.position * transform_uniform.scale, transform_uniform.rotation);
+
+    var out: NormalsOutput;
+    out.position = vec4f(to_view(local), 0, 1);
+    out.uv = in.uv;
+    out.alpha = in.color.a;
+    out.rotation = transform_uniform.rotation;
+    out.flip = select(vec2f(1.0), vec2f(-1.0), transform_uniform.scale < vec2f(0.0));
+    return out;
+}
+
+@fragment
+fn fs_flat(in: NormalsOutput) -> @location(0) vec4f {
+    return vec4f(0.5, 0.5, 1.0, coverage(in));
+}
+
+@fragment
+fn fs_normal_mapped(in: NormalsOutput) -> @location(0) vec4f {
+    let local = textureSample(normal_map, normal_sampler, in.uv).xy * 2.0 - 1.0;
+    let normal = rotate_2d(local * in.flip, in.rotation);
+    return vec4f(normal * 0.5 + 0.5, 1.0, coverage(in));
+}
+
+@fragment
+fn fs_unlit(in: NormalsOutput) -> @location(0) vec4f {
+    return vec4f(0.5, 0.5, 0.0, coverage(in));
+}
+
+fn coverage(in: NormalsOutput) -> f32 {
+    return in.alpha * textureSample(object_texture, object_sampler, in.uv).a;
+}
+
+fn rotate_2d(v: vec2f, angle: f32) -> vec2f {
+    let c = cos(angle);
+    let s = sin(angle);
+    return vec2f(v.x * c - v.y * s, v.x * s + v.y * c);
+}
+
+// same as in basic.wgsl
+fn to_view(local: vec2f) -> vec2f {
+    let origin = rotate_2d(transform_uniform.position - camera.position, -camera.rotation);
+    let scale = camera.zoom / camera.extent;
+    return snap(origin * scale) + rotate_2d(local, -camera.rotation) * scale;
+}
+
+fn snap(view: vec2f) -> vec2f {
+    if camera.pixels.x <= 0.0 {
+        return view;
+    }
+    return round(view * camera.pixels) / camera.pixels;
+}
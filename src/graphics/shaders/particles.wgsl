@@ -0,0 +1,22 @@
+// Appended to basic.wgsl. Every instance is a copy of the object's mesh, sized,
+// rotated and moved to its particle before the object's own transform; fragments go through
+// fs_main.
+
+struct ParticleInput {
+    @location(3) position: vec2f,
+    @location(4) rotation: f32,
+    @location(5) size: f32,
+    @location(6) color: vec4f,
+}
+
+@vertex
+fn vs_particle(in: VertexInput, particle: ParticleInput) -> VertexOutput {
+    let offset = rotate_2d(in.position * particle.size, particle.rotation) + particle.position;
+    let local = rotate_2d(offset * transform_uniform.scale, transform_uniform.rotation);
+
+    var out: VertexOutput;
+    out.position = vec4f(to_view(local), 0, 1);
+    out.color = in.color * particle.color;
+    out.uv = in.uv;
+    return out;
+}
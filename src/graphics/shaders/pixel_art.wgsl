@@ -0,0 +1,13 @@
+// Scales the low resolution scene up to the window, appended to fullscreen.wgsl and drawn with
+// the viewport set to where the scene goes.
+
+@group(0) @binding(0)
+var scene_texture: texture_2d<f32>;
+
+@group(0) @binding(1)
+var scene_sampler: sampler;
+
+@fragment
+fn fs_upscale(in: FullscreenOutput) -> @location(0) vec4f {
+    return textureSample(scene_texture, scene_sampler, in.uv);
+}
@@ -0,0 +1,59 @@
+// Shared by every post-processing effect, custom effects included. Effects define
+// `fs_main(in: FullscreenOutput) -> @location(0) vec4f` and read their input with
+// `sample_input` and their parameters with `param`.
+
+const TAU = 6.283185307179586;
+
+struct TimeUniform {
+    time: f32,
+}
+
+struct SurfaceUniform {
+    size: vec2f,
+}
+
+// (value, amplitude, frequency, phase) per parameter
+struct EffectParams {
+    slots: array<vec4f, 8>,
+}
+
+@group(0) @binding(0)
+var<uniform> time_uniform: TimeUniform;
+
+@group(0) @binding(1)
+var<uniform> surface_uniform: SurfaceUniform;
+
+@group(1) @binding(0)
+var input_texture: texture_2d<f32>;
+
+@group(1) @binding(1)
+var input_sampler: sampler;
+
+@group(1) @binding(2)
+var<uniform> effect_params: EffectParams;
+
+// value + amplitude * sin(TAU * (frequency * time + phase))
+fn param(index: u32) -> f32 {
+    let slot = effect_params.slots[index];
+    return slot.x + slot.y * sin(TAU * (slot.z * time_uniform.time + slot.w));
+}
+
+fn sample_input(uv: vec2f) -> vec4f {
+    return textureSampleLevel(input_texture, input_sampler, uv, 0.0);
+}
+
+fn input_size() -> vec2f {
+    return vec2f(textureDimensions(input_texture));
+}
+
+fn linear_to_srgb(color: vec3f) -> vec3f {
+    let low = color * 12.92;
+    let high = 1.055 * pow(color, vec3f(1.0 / 2.4)) - 0.055;
+    return select(high, low, color <= vec3f(0.0031308));
+}
+
+fn srgb_to_linear(color: vec3f) -> vec3f {
+    let low = color / 12.92;
+    let high = pow((color + 0.055) / 1.055, vec3f(2.4));
+    return select(high, low, color <= vec3f(0.04045));
+}
@@ -0,0 +1,106 @@
+// Appended to basic.wgsl, reusing its vertex shader. Edges are anti-aliased over one pixel,
+// found with the screen space derivative of the distance.
+
+// shapes: params = (kind, size...), extra.x = outline width
+// text: params = (distance range, outline width, shadow softness), extra.xy = shadow offset,
+// all in texels of the distance field
+struct MaterialUniform {
+    params: vec4f,
+    extra: vec4f,
+    outline_color: vec4f,
+    shadow_color: vec4f,
+}
+
+@group(1) @binding(1)
+var<uniform> material: MaterialUniform;
+
+const SHAPE_CIRCLE: i32 = 0;
+const SHAPE_ROUNDED_BOX: i32 = 1;
+const SHAPE_CAPSULE: i32 = 2;
+
+@fragment
+fn fs_shape(in: VertexOutput) -> @location(0) vec4f {
+    let p = in.uv;
+    var distance: f32;
+    switch i32(material.params.x) {
+        case SHAPE_ROUNDED_BOX: {
+            let radius = material.params.w;
+            let q = abs(p) - material.params.yz + radius;
+            distance = length(max(q, vec2f(0.0))) + min(max(q.x, q.y), 0.0) - radius;
+        }
+        case SHAPE_CAPSULE: {
+            let half_length = material.params.y;
+            distance = length(vec2f(p.x - clamp(p.x, -half_length, half_length), p.y))
+                - material.params.z;
+        }
+        default: {
+            distance = length(p) - material.params.y;
+        }
+    }
+
+    // distance per pixel
+    let pixel = max(fwidth(distance), 1e-6);
+    let fill = coverage(distance / pixel);
+    let outline = coverage((distance - material.extra.x) / pixel);
+    let color = over(
+        vec4f(in.color.rgb, in.color.a * fill),
+        vec4f(material.outline_color.rgb, material.outline_color.a * outline),
+    );
+    return encode(color);
+}
+
+@fragment
+fn fs_msdf(in: VertexOutput) -> @location(0) vec4f {
+    let range = material.params.x;
+    let outline_width = material.params.y / range;
+    let texel = 1.0 / vec2f(textureDimensions(object_texture));
+
+    // screen pixels per unit of the normalized distance
+    let screen_texels = 1.0 / fwidth(in.uv);
+    let pixels = max(0.5 * dot(range * texel, screen_texels), 1.0);
+
+    let distance = field_distance(in.uv);
+    let fill = clamp(distance * pixels + 0.5, 0.0, 1.0);
+    let outline = clamp((distance + outline_width) * pixels + 0.5, 0.0, 1.0);
+
+    let shadow_distance = field_distance(in.uv - material.extra.xy * texel) + outline_width;
+    let softness = max(material.params.z / range, 0.5 / pixels);
+    let shadow = smoothstep(-softness, softness, shadow_distance);
+
+    let color = over(
+        vec4f(in.color.rgb, in.color.a * fill),
+        over(
+            vec4f(material.outline_color.rgb, material.outline_color.a * outline),
+            vec4f(material.shadow_color.rgb, material.shadow_color.a * shadow),
+        ),
+    );
+    return encode(color);
+}
+
+/// Signed distance in `-0.5..0.5`, positive inside.
+fn field_distance(uv: vec2f) -> f32 {
+    let s = textureSample(object_texture, object_sampler, uv).rgb;
+    return max(min(s.r, s.g), min(max(s.r, s.g), s.b)) - 0.5;
+}
+
+/// `distance` in pixels, negative inside.
+fn coverage(distance: f32) -> f32 {
+    return clamp(0.5 - distance, 0.0, 1.0);
+}
+
+/// Straight alpha `top` blended onto `bottom`.
+fn over(top: vec4f, bottom: vec4f) -> vec4f {
+    let alpha = top.a + bottom.a * (1.0 - top.a);
+    if alpha <= 0.0 {
+        return vec4f(0.0);
+    }
+    let rgb = (top.rgb * top.a + bottom.rgb * bottom.a * (1.0 - top.a)) / alpha;
+    return vec4f(rgb, alpha);
+}
+
+fn encode(color: vec4f) -> vec4f {
+    if ENCODE_SRGB {
+        return vec4f(linear_to_srgb(color.rgb), color.a);
+    }
+    return color;
+}
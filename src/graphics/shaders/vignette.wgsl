@@ -0,0 +1,14 @@
+// params: 0 intensity, 1 radius, 2 smoothness
+
+@fragment
+fn fs_main(in: FullscreenOutput) -> @location(0) vec4f {
+    let color = sample_input(in.uv);
+    let size = input_size();
+    let offset = (in.uv - 0.5) * vec2f(size.x / size.y, 1.0);
+
+    let radius = param(1u);
+    let smoothness = max(param(2u), 0.0001);
+    let vignette = 1.0 - smoothstep(radius - smoothness, radius, length(offset));
+
+    return vec4f(color.rgb * mix(1.0, vignette, param(0u)), color.a);
+}
@@ -0,0 +1,492 @@
+use crate::graphics::{Mesh, MeshUsage, TextureId, sprite::SpriteSheet};
+
+/// Frames shorter than this are stretched to it, so a long time step can't loop forever.
+const MIN_FRAME_DURATION: f32 = 1e-3;
+
+/// What happens after the last frame of a clip.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum PlaybackMode {
+    /// Starts over from the first frame.
+    #[default]
+    Loop,
+    /// Plays backwards to the first frame, then forwards again.
+    PingPong,
+    /// Stays on the last frame, see [`Animator::finished`].
+    Once,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct AnimationFrame {
+    /// Index into the [`SpriteSheet`].
+    pub sprite: usize,
+    /// Seconds the frame is shown for.
+    pub duration: f32,
+    /// Emitted whenever the frame is entered.
+    pub event: Option<String>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct AnimationClip {
+    pub name: String,
+    pub frames: Vec<AnimationFrame>,
+    pub mode: PlaybackMode,
+}
+impl AnimationClip {
+    /// Shows `sprites` in order, each for `frame_duration` seconds.
+    pub fn new(name: &str, sprites: impl IntoIterator<Item = usize>, frame_duration: f32) -> Self {
+        Self {
+            name: name.to_owned(),
+            frames: sprites
+                .into_iter()
+                .map(|sprite| AnimationFrame {
+                    sprite,
+                    duration: frame_duration,
+                    event: None,
+                })
+                .collect(),
+            mode: PlaybackMode::default(),
+        }
+    }
+
+    pub fn with_mode(mut self, mode: PlaybackMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    /// Overrides the duration of the `frame`th frame of the clip.
+    pub fn with_frame_duration(mut self, frame: usize, duration: f32) -> Self {
+        match self.frames.get_mut(frame) {
+            Some(frame) => frame.duration = duration,
+            None => log::warn!("Clip {} has no frame {frame}", self.name),
+        }
+        self
+    }
+
+    /// Emits `event` whenever the `frame`th frame of the clip is entered.
+    pub fn with_event(mut self, frame: usize, event: &str) -> Self {
+        match self.frames.get_mut(frame) {
+            Some(frame) => frame.event = Some(event.to_owned()),
+            None => log::warn!("Clip {} has no frame {frame}", self.name),
+        }
+        self
+    }
+
+    /// Seconds from the first frame to the end of the last one.
+    #[must_use]
+    pub fn duration(&self) -> f32 {
+        self.frames.iter().map(|frame| frame.duration).sum()
+    }
+}
+
+/// Frame event of a clip, collected by [`crate::app::State::drain_animation_events`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct AnimationEvent {
+    pub clip: String,
+    pub event: String,
+    /// Index of the frame within the clip.
+    pub frame: usize,
+}
+
+#[derive(Clone, Copy, Debug)]
+struct Playback {
+    clip: usize,
+    frame: usize,
+    /// Seconds spent on `frame` so far.
+    time: f32,
+    reverse: bool,
+    finished: bool,
+}
+
+/// Plays [`AnimationClip`]s of a [`SpriteSheet`] on a [`crate::graphics::RenderObject`], see
+/// [`crate::graphics::RenderObject::sprite`]. Advanced from the engine clock by
+/// [`crate::app::State::tick`], which also points the object's uvs at the current sprite.
+#[derive(Clone, Debug)]
+pub struct Animator {
+    sheet: SpriteSheet,
+    clips: Vec<AnimationClip>,
+    playback: Option<Playback>,
+    /// Multiplier of the clock, `0.0` pauses.
+    pub speed: f32,
+    events: Vec<AnimationEvent>,
+    /// The mesh's uvs before any sprite was applied, spanning the whole texture.
+    base_uvs: Vec<[f32; 2]>,
+    shown_sprite: Option<usize>,
+}
+impl Animator {
+    /// Shows the first sprite of `sheet` until a clip is played.
+    pub fn new(sheet: SpriteSheet) -> Self {
+        Self {
+            sheet,
+            clips: Vec::new(),
+            playback: None,
+            speed: 1.0,
+            events: Vec::new(),
+            base_uvs: Vec::new(),
+            shown_sprite: None,
+        }
+    }
+
+    /// Adds `clip`, replacing one of the same name.
+    pub fn with_clip(mut self, clip: AnimationClip) -> Self {
+        self.add_clip(clip);
+        self
+    }
+
+    /// Adds `clip`, replacing one of the same name. A replaced clip that is playing restarts,
+    /// its frames may have changed.
+    pub fn add_clip(&mut self, clip: AnimationClip) {
+        let Some(index) = self.clips.iter().position(|other| other.name == clip.name) else {
+            self.clips.push(clip);
+            return;
+        };
+        self.clips[index] = clip;
+        if self.playback.is_some_and(|playback| playback.clip == index) {
+            self.playback = None;
+            let name = self.clips[index].name.clone();
+            self.restart(&name);
+        }
+    }
+
+    #[must_use]
+    pub fn sheet(&self) -> &SpriteSheet {
+        &self.sheet
+    }
+
+    /// Switches to the clip called `name` from its first frame, unless it's already playing.
+    /// Returns `false` for unknown clips and clips without frames.
+    pub fn play(&mut self, name: &str) -> bool {
+        let playing = self
+            .playback
+            .is_some_and(|playback| !playback.finished && self.clips[playback.clip].name == name);
+        playing || self.restart(name)
+    }
+
+    /// Plays the clip called `name` from its first frame. Returns `false` for unknown clips and
+    /// clips without frames.
+    pub fn restart(&mut self, name: &str) -> bool {
+        let Some(clip) = self.clips.iter().position(|clip| clip.name == name) else {
+            log::warn!("Unknown animation clip {name}");
+            return false;
+        };
+        if self.clips[clip].frames.is_empty() {
+            log::warn!("Animation clip {name} has no frames");
+            return false;
+        }
+        self.playback = Some(Playback {
+            clip,
+            frame: 0,
+            time: 0.0,
+            reverse: false,
+            finished: false,
+        });
+        self.emit_event(clip, 0);
+        true
+    }
+
+    /// Stays on the current sprite.
+    pub fn stop(&mut self) {
+        self.playback = None;
+    }
+
+    /// Name of the clip being played, also after a [`PlaybackMode::Once`] clip finished.
+    #[must_use]
+    pub fn clip(&self) -> Option<&str> {
+        self.playback
+            .map(|playback| self.clips[playback.clip].name.as_str())
+    }
+
+    /// Index of the current frame within the clip.
+    #[must_use]
+    pub fn frame(&self) -> Option<usize> {
+        self.playback.map(|playback| playback.frame)
+    }
+
+    /// Whether a [`PlaybackMode::Once`] clip reached the end of its last frame.
+    #[must_use]
+    pub fn finished(&self) -> bool {
+        self.playback.is_some_and(|playback| playback.finished)
+    }
+
+    /// Index into the sheet of the sprite to show.
+    #[must_use]
+    pub fn sprite(&self) -> usize {
+        self.playback
+            .and_then(|playback| self.clips[playback.clip].frames.get(playback.frame))
+            .map_or(self.shown_sprite.unwrap_or(0), |frame| frame.sprite)
+    }
+
+    /// Moves the current clip `seconds` (times [`Animator::speed`]) ahead, emitting the events
+    /// of every frame entered on the way.
+    pub fn advance(&mut self, seconds: f32) {
+        let Some(mut playback) = self.playback else {
+            return;
+        };
+        let frame_count = self.clips[playback.clip].frames.len();
+        if playback.finished || frame_count == 0 || self.speed <= 0.0 {
+            return;
+        }
+
+        playback.time += seconds * self.speed;
+        loop {
+            let clip = &self.clips[playback.clip];
+            let Some(frame) = clip.frames.get(playback.frame) else {
+                break;
+            };
+            let duration = frame.duration.max(MIN_FRAME_DURATION);
+            if playback.time < duration {
+                break;
+            }
+            let last = frame_count - 1;
+            let next = match clip.mode {
+                PlaybackMode::Loop => (playback.frame + 1) % frame_count,
+                PlaybackMode::Once if playback.frame == last => {
+                    playback.finished = true;
+                    playback.time = duration;
+                    break;
+                }
+                PlaybackMode::Once => playback.frame + 1,
+                PlaybackMode::PingPong if frame_count == 1 => 0,
+                PlaybackMode::PingPong => {
+                    if playback.frame == last {
+                        playback.reverse = true;
+                    } else if playback.frame == 0 {
+                        playback.reverse = false;
+                    }
+                    if playback.reverse {
+                        playback.frame - 1
+                    } else {
+                        playback.frame + 1
+                    }
+                }
+            };
+            playback.time -= duration;
+            playback.frame = next;
+            self.emit_event(playback.clip, next);
+        }
+        self.playback = Some(playback);
+    }
+
+    /// Events emitted since the last call, oldest first.
+    pub fn drain_events(&mut self) -> impl Iterator<Item = AnimationEvent> + '_ {
+        self.events.drain(..)
+    }
+
+    /// Points `mesh`'s uvs at the current sprite and `texture` at the sheet. The uvs the mesh
+    /// had at first are taken to span the whole texture.
+    pub fn apply(&mut self, mesh: &mut Mesh, texture: &mut Option<TextureId>) {
+        *texture = Some(self.sheet.texture());
+        let sprite = self.sprite();
+        if self.shown_sprite == Some(sprite) && self.base_uvs.len() == mesh.vertices().len() {
+            return;
+        }
+        let Some(rect) = self.sheet.sprite(sprite) else {
+            log::warn!("Sprite sheet has no sprite {sprite}");
+            return;
+        };
+
+        if self.base_uvs.len() != mesh.vertices().len() {
+            self.base_uvs = mesh.vertices().iter().map(|vertex| vertex.uv()).collect();
+        }
+        if mesh.usage() == MeshUsage::Static {
+            mesh.set_usage(MeshUsage::Dynamic);
+        }
+        for (vertex, &uv) in mesh.vertices_mut().iter_mut().zip(&self.base_uvs) {
+            *vertex = vertex.with_uv(rect.map(uv));
+        }
+        self.shown_sprite = Some(sprite);
+    }
+
+    fn emit_event(&mut self, clip: usize, frame: usize) {
+        let clip = &self.clips[clip];
+        if let Some(event) = clip
+            .frames
+            .get(frame)
+            .and_then(|frame| frame.event.as_ref())
+        {
+            self.events.push(AnimationEvent {
+                clip: clip.name.clone(),
+                event: event.clone(),
+                frame,
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graphics::TextureId;
+
+    const FRAME: f32 = 0.25;
+
+    fn animator(clip: AnimationClip) -> Animator {
+        let mut animator =
+            Animator::new(SpriteSheet::grid(TextureId::for_tests(0), 4, 1)).with_clip(clip);
+        animator.play("clip");
+        animator
+    }
+
+    /// Frame after each of `steps` advances by one frame duration.
+    fn frames(animator: &mut Animator, steps: usize) -> Vec<usize> {
+        (0..steps)
+            .map(|_| {
+                animator.advance(FRAME);
+                animator.frame().unwrap()
+            })
+            .collect()
+    }
+
+    fn events(animator: &mut Animator) -> Vec<(String, usize)> {
+        animator
+            .drain_events()
+            .map(|event| (event.event, event.frame))
+            .collect()
+    }
+
+    #[test]
+    fn loop_wraps_to_the_first_frame() {
+        let mut animator = animator(AnimationClip::new("clip", 0..4, FRAME));
+        assert_eq!(frames(&mut animator, 6), [1, 2, 3, 0, 1, 2]);
+        assert!(!animator.finished());
+    }
+
+    #[test]
+    fn ping_pong_turns_at_both_ends() {
+        let mut animator =
+            animator(AnimationClip::new("clip", 0..4, FRAME).with_mode(PlaybackMode::PingPong));
+        assert_eq!(frames(&mut animator, 8), [1, 2, 3, 2, 1, 0, 1, 2]);
+    }
+
+    #[test]
+    fn ping_pong_of_a_single_frame_stays() {
+        let mut animator =
+            animator(AnimationClip::new("clip", [2], FRAME).with_mode(PlaybackMode::PingPong));
+        assert_eq!(frames(&mut animator, 3), [0, 0, 0]);
+        assert_eq!(animator.sprite(), 2);
+    }
+
+    #[test]
+    fn once_stops_on_the_last_frame() {
+        let mut animator =
+            animator(AnimationClip::new("clip", 0..3, FRAME).with_mode(PlaybackMode::Once));
+        assert_eq!(frames(&mut animator, 2), [1, 2]);
+        assert!(!animator.finished());
+        assert_eq!(frames(&mut animator, 3), [2, 2, 2]);
+        assert!(animator.finished());
+        assert_eq!(animator.clip(), Some("clip"));
+    }
+
+    #[test]
+    fn play_keeps_a_playing_clip_and_restarts_a_finished_one() {
+        let mut animator =
+            animator(AnimationClip::new("clip", 0..2, FRAME).with_mode(PlaybackMode::Once));
+        animator.advance(FRAME);
+        assert!(animator.play("clip"));
+        assert_eq!(animator.frame(), Some(1));
+        animator.advance(FRAME);
+        assert!(animator.finished());
+        assert!(animator.play("clip"));
+        assert_eq!(animator.frame(), Some(0));
+        assert!(!animator.finished());
+    }
+
+    #[test]
+    fn events_fire_whenever_their_frame_is_entered() {
+        let mut animator = animator(
+            AnimationClip::new("clip", 0..4, FRAME)
+                .with_mode(PlaybackMode::PingPong)
+                .with_event(0, "start")
+                .with_event(3, "peak"),
+        );
+        assert_eq!(events(&mut animator), [("start".to_owned(), 0)]);
+        frames(&mut animator, 6);
+        assert_eq!(
+            events(&mut animator),
+            [("peak".to_owned(), 3), ("start".to_owned(), 0)]
+        );
+    }
+
+    #[test]
+    fn a_long_step_enters_every_frame_on_the_way() {
+        let mut animator = animator(
+            AnimationClip::new("clip", 0..4, FRAME)
+                .with_event(1, "one")
+                .with_event(2, "two")
+                .with_frame_duration(2, 1.0),
+        );
+        events(&mut animator);
+        animator.advance(FRAME * 2.0 + 0.5);
+        assert_eq!(animator.frame(), Some(2));
+        assert_eq!(
+            events(&mut animator),
+            [("one".to_owned(), 1), ("two".to_owned(), 2)]
+        );
+        animator.advance(0.5);
+        assert_eq!(animator.frame(), Some(3));
+    }
+
+    #[test]
+    fn speed_scales_and_pauses_the_clock() {
+        let mut animator = animator(AnimationClip::new("clip", 0..4, FRAME));
+        animator.speed = 2.0;
+        animator.advance(FRAME);
+        assert_eq!(animator.frame(), Some(2));
+        animator.speed = 0.0;
+        animator.advance(FRAME * 10.0);
+        assert_eq!(animator.frame(), Some(2));
+    }
+
+    #[test]
+    fn clips_without_frames_are_refused() {
+        let mut animator = Animator::new(SpriteSheet::grid(TextureId::for_tests(0), 4, 1))
+            .with_clip(AnimationClip::new("empty", [], FRAME));
+        assert!(!animator.play("empty"));
+        assert!(!animator.restart("empty"));
+        assert_eq!(animator.clip(), None);
+        animator.advance(FRAME);
+        assert_eq!(animator.sprite(), 0);
+    }
+
+    #[test]
+    fn replacing_the_playing_clip_restarts_it() {
+        let mut animator = animator(AnimationClip::new("clip", 0..4, FRAME).with_event(0, "start"));
+        frames(&mut animator, 3);
+        events(&mut animator);
+
+        animator.add_clip(
+            AnimationClip::new("clip", [3, 2], FRAME)
+                .with_mode(PlaybackMode::PingPong)
+                .with_event(0, "start"),
+        );
+        assert_eq!(animator.frame(), Some(0));
+        assert_eq!(animator.sprite(), 3);
+        assert_eq!(events(&mut animator), [("start".to_owned(), 0)]);
+        assert_eq!(frames(&mut animator, 4), [1, 0, 1, 0]);
+    }
+
+    #[test]
+    fn replacing_the_playing_clip_with_an_empty_one_stops() {
+        let mut animator = animator(AnimationClip::new("clip", 0..4, FRAME));
+        frames(&mut animator, 2);
+        animator.add_clip(AnimationClip::new("clip", [], FRAME));
+        assert_eq!(animator.clip(), None);
+        animator.advance(FRAME);
+        assert_eq!(animator.sprite(), 0);
+    }
+
+    #[test]
+    fn replacing_another_clip_keeps_playing() {
+        let mut animator = animator(AnimationClip::new("clip", 0..4, FRAME));
+        frames(&mut animator, 2);
+        animator.add_clip(AnimationClip::new("other", [0], FRAME));
+        animator.add_clip(AnimationClip::new("other", [1], FRAME));
+        assert_eq!(animator.frame(), Some(2));
+    }
+
+    #[test]
+    fn unknown_clips_are_refused() {
+        let mut animator = animator(AnimationClip::new("clip", 0..4, FRAME));
+        assert!(!animator.play("missing"));
+        assert_eq!(animator.clip(), Some("clip"));
+    }
+}
@@ -0,0 +1,190 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    path::Path,
+};
+
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+
+use crate::graphics::{
+    Color, GraphicsContext, Mesh, RenderObject, TextureId, Transform, Vertex,
+    image::Image,
+    sprite::{SpriteSheet, UvRect},
+};
+
+/// Contents of the JSON file the atlas packer writes next to its pages.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct AtlasMetadata {
+    pub pages: Vec<AtlasPage>,
+    /// Sorted by name, which is the path of the PNG relative to the packed folder, without
+    /// extension and with `/` separators.
+    pub sprites: BTreeMap<String, AtlasEntry>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct AtlasPage {
+    /// PNG file name, relative to the metadata file.
+    pub image: String,
+    pub width: u32,
+    pub height: u32,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct AtlasEntry {
+    pub page: usize,
+    /// `[x, y, width, height]` in pixels of the page, the sprite without its transparent
+    /// border.
+    pub rect: [u32; 4],
+    pub uv: UvRect,
+    /// Size of the original image.
+    pub source_size: [u32; 2],
+    /// Where `rect` was in the original image, from its top left corner.
+    pub trim_offset: [u32; 2],
+}
+
+/// Sprite of a [`SpriteAtlas`], resolved by name.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct AtlasSprite {
+    pub texture: TextureId,
+    pub page: usize,
+    /// Index into the page's [`SpriteSheet`], for [`crate::graphics::AnimationClip`]s.
+    pub index: usize,
+    pub uv: UvRect,
+    /// Size of the trimmed sprite in pixels.
+    pub size: [u32; 2],
+    pub source_size: [u32; 2],
+    pub trim_offset: [u32; 2],
+}
+impl AtlasSprite {
+    /// Part of the original image the trimmed sprite covers, `[0.0, 0.0]` being its top left
+    /// and `[1.0, 1.0]` its bottom right corner.
+    #[must_use]
+    pub fn trimmed_area(&self) -> UvRect {
+        let [x, y] = self.trim_offset;
+        let [width, height] = self.size;
+        let [source_width, source_height] = self.source_size;
+        UvRect::from_pixels([x, y, width, height], (source_width, source_height))
+    }
+}
+
+/// Sprites packed into one or more textures by `pack-atlas`, see
+/// [`crate::graphics::pack_atlas`].
+#[derive(Clone, Debug)]
+pub struct SpriteAtlas {
+    pages: Vec<SpriteSheet>,
+    sprites: HashMap<String, AtlasSprite>,
+}
+impl SpriteAtlas {
+    /// Reads the metadata at `path` and uploads the pages next to it.
+    pub fn load(graphics_context: &mut GraphicsContext, path: &Path) -> anyhow::Result<Self> {
+        let json = std::fs::read_to_string(path)
+            .with_context(|| format!("Unable to read {}", path.display()))?;
+        let metadata: AtlasMetadata = serde_json::from_str(&json)
+            .with_context(|| format!("Unable to parse {}", path.display()))?;
+        let directory = path.parent().unwrap_or(Path::new(""));
+
+        let mut textures = Vec::with_capacity(metadata.pages.len());
+        for page in &metadata.pages {
+            let image = Image::load_png(&directory.join(&page.image))?;
+            if (image.width(), image.height()) != (page.width, page.height) {
+                log::warn!(
+                    "Atlas page {} is {}x{}, expected {}x{}",
+                    page.image,
+                    image.width(),
+                    image.height(),
+                    page.width,
+                    page.height
+                );
+            }
+            let (width, height) = (image.width(), image.height());
+            textures.push(graphics_context.create_texture(width, height, image.into_pixels())?);
+        }
+
+        let mut rects = vec![Vec::new(); textures.len()];
+        let mut sprites = HashMap::with_capacity(metadata.sprites.len());
+        for (name, entry) in metadata.sprites {
+            let Some(&texture) = textures.get(entry.page) else {
+                log::warn!("Sprite {name} is on missing page {}", entry.page);
+                continue;
+            };
+            let page_rects = &mut rects[entry.page];
+            sprites.insert(
+                name,
+                AtlasSprite {
+                    texture,
+                    page: entry.page,
+                    index: page_rects.len(),
+                    uv: entry.uv,
+                    size: [entry.rect[2], entry.rect[3]],
+                    source_size: entry.source_size,
+                    trim_offset: entry.trim_offset,
+                },
+            );
+            page_rects.push(entry.uv);
+        }
+
+        Ok(Self {
+            pages: textures
+                .into_iter()
+                .zip(rects)
+                .map(|(texture, rects)| SpriteSheet::from_rects(texture, rects))
+                .collect(),
+            sprites,
+        })
+    }
+
+    #[must_use]
+    pub fn sprite(&self, name: &str) -> Option<&AtlasSprite> {
+        self.sprites.get(name)
+    }
+
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.sprites.keys().map(String::as_str)
+    }
+
+    /// Sprites of the `page`th texture, indexed by [`AtlasSprite::index`]. An
+    /// [`crate::graphics::Animator`] stretches trimmed sprites over its whole rectangle, so
+    /// animation frames are best packed with `--no-trim`.
+    #[must_use]
+    pub fn page(&self, page: usize) -> Option<&SpriteSheet> {
+        self.pages.get(page)
+    }
+
+    #[must_use]
+    pub fn page_count(&self) -> usize {
+        self.pages.len()
+    }
+
+    /// `width` x `height` rectangle showing the sprite called `sprite` like the untrimmed image
+    /// would, i.e. only the trimmed part of it is covered by the mesh.
+    pub fn object(
+        &self,
+        sprite: &str,
+        (width, height): (f32, f32),
+        name: Option<&str>,
+        transform: Transform,
+    ) -> Option<RenderObject> {
+        let Some(sprite) = self.sprite(sprite) else {
+            log::warn!("Atlas has no sprite {sprite}");
+            return None;
+        };
+        let area = sprite.trimmed_area();
+        // corner of the trimmed sprite, positioned where it was in the original image
+        let corner = |[u, v]: [f32; 2]| {
+            let [x, y] = area.map([u, v]);
+            Vertex::new([(x - 0.5) * width, (0.5 - y) * height], Color::WHITE)
+                .with_uv(sprite.uv.map([u, v]))
+        };
+        let mesh = Mesh::new(
+            vec![
+                corner([0.0, 0.0]),
+                corner([0.0, 1.0]),
+                corner([1.0, 1.0]),
+                corner([1.0, 0.0]),
+            ],
+            vec![0, 1, 2, 0, 2, 3],
+        );
+
+        Some(RenderObject::new(mesh, name, transform).with_texture(sprite.texture))
+    }
+}
@@ -0,0 +1,87 @@
+pub mod animation;
+pub mod atlas;
+pub mod packer;
+
+use serde::{Deserialize, Serialize};
+
+use crate::graphics::TextureId;
+
+/// Part of a texture in texture coordinates, `[0.0, 0.0]` being the top left corner.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct UvRect {
+    pub min: [f32; 2],
+    pub max: [f32; 2],
+}
+impl UvRect {
+    /// `[x, y, width, height]` in pixels of a `texture_width` x `texture_height` texture.
+    #[must_use]
+    pub fn from_pixels(
+        [x, y, width, height]: [u32; 4],
+        (texture_width, texture_height): (u32, u32),
+    ) -> Self {
+        let (texture_width, texture_height) =
+            (texture_width.max(1) as f32, texture_height.max(1) as f32);
+        Self {
+            min: [x as f32 / texture_width, y as f32 / texture_height],
+            max: [
+                (x + width) as f32 / texture_width,
+                (y + height) as f32 / texture_height,
+            ],
+        }
+    }
+
+    /// Maps `uv` of the whole texture into this rect.
+    #[must_use]
+    pub fn map(&self, [u, v]: [f32; 2]) -> [f32; 2] {
+        [
+            self.min[0] + u * (self.max[0] - self.min[0]),
+            self.min[1] + v * (self.max[1] - self.min[1]),
+        ]
+    }
+}
+
+/// Sprites sharing one texture, addressed by index.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SpriteSheet {
+    texture: TextureId,
+    sprites: Vec<UvRect>,
+}
+impl SpriteSheet {
+    /// `columns` x `rows` equally sized cells, numbered row by row from the top left.
+    #[must_use]
+    pub fn grid(texture: TextureId, columns: u32, rows: u32) -> Self {
+        let (columns, rows) = (columns.max(1), rows.max(1));
+        let sprites = (0..rows)
+            .flat_map(|row| (0..columns).map(move |column| (column, row)))
+            .map(|(column, row)| UvRect::from_pixels([column, row, 1, 1], (columns, rows)))
+            .collect();
+
+        Self { texture, sprites }
+    }
+
+    /// Sprites anywhere in the texture, e.g. packed into an atlas.
+    #[must_use]
+    pub fn from_rects(texture: TextureId, sprites: Vec<UvRect>) -> Self {
+        Self { texture, sprites }
+    }
+
+    #[must_use]
+    pub fn texture(&self) -> TextureId {
+        self.texture
+    }
+
+    #[must_use]
+    pub fn sprite(&self, index: usize) -> Option<UvRect> {
+        self.sprites.get(index).copied()
+    }
+
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.sprites.len()
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.sprites.is_empty()
+    }
+}
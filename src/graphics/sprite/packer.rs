@@ -0,0 +1,469 @@
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
+
+use anyhow::Context;
+
+use crate::graphics::{
+    image::Image,
+    sprite::{
+        UvRect,
+        atlas::{AtlasEntry, AtlasMetadata, AtlasPage},
+    },
+};
+
+const USAGE: &str = "Usage: pack-atlas <input folder> <output prefix> [--max-size <pixels>] \
+                     [--padding <pixels>] [--extrude <pixels>] [--no-trim]";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PackOptions {
+    /// Largest width and height of a page.
+    pub max_size: u32,
+    /// Transparent pixels between sprites, on top of `extrude`.
+    pub padding: u32,
+    /// Times the edge pixels of a sprite are repeated around it, so filtering at its edge
+    /// doesn't pick up its neighbours.
+    pub extrude: u32,
+    /// Whether fully transparent borders are cut off, see
+    /// [`super::atlas::AtlasSprite::trimmed_area`].
+    pub trim: bool,
+}
+impl Default for PackOptions {
+    fn default() -> Self {
+        Self {
+            max_size: 2048,
+            padding: 2,
+            extrude: 1,
+            trim: true,
+        }
+    }
+}
+
+/// Entry point of the `pack-atlas` command, `args` being the ones after it.
+pub fn pack_atlas(args: &[String]) -> anyhow::Result<()> {
+    let mut options = PackOptions::default();
+    let mut paths = Vec::new();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let mut value = |name: &str| -> anyhow::Result<u32> {
+            let value = args
+                .next()
+                .ok_or_else(|| anyhow::anyhow!("Missing value of {name}\n{USAGE}"))?;
+            value
+                .parse()
+                .with_context(|| format!("Invalid value of {name}: {value}"))
+        };
+        match arg.as_str() {
+            "--max-size" => options.max_size = value(arg)?.max(1),
+            "--padding" => options.padding = value(arg)?,
+            "--extrude" => options.extrude = value(arg)?,
+            "--no-trim" => options.trim = false,
+            flag if flag.starts_with("--") => anyhow::bail!("Unknown option {flag}\n{USAGE}"),
+            path => paths.push(PathBuf::from(path)),
+        }
+    }
+    let [input, output] = paths.as_slice() else {
+        anyhow::bail!(USAGE);
+    };
+
+    let metadata = pack_folder(input, output, &options)?;
+    println!(
+        "Packed {} sprites into {} page(s): {}.json",
+        metadata.sprites.len(),
+        metadata.pages.len(),
+        output.display()
+    );
+
+    Ok(())
+}
+
+/// Packs every PNG below `input` and writes `<output>.json` plus one `<output>_<page>.png` per
+/// page next to it.
+pub fn pack_folder(
+    input: &Path,
+    output: &Path,
+    options: &PackOptions,
+) -> anyhow::Result<AtlasMetadata> {
+    let mut files = Vec::new();
+    find_pngs(input, &mut files)?;
+    if files.is_empty() {
+        anyhow::bail!("No PNGs in {}", input.display());
+    }
+    let images = files
+        .into_iter()
+        .map(|path| {
+            let name = path
+                .strip_prefix(input)
+                .unwrap_or(&path)
+                .with_extension("")
+                .components()
+                .map(|component| component.as_os_str().to_string_lossy())
+                .collect::<Vec<_>>()
+                .join("/");
+            Ok((name, Image::load_png(&path)?))
+        })
+        .collect::<anyhow::Result<Vec<_>>>()?;
+
+    let (pages, sprites) = pack(images, options)?;
+
+    let directory = output.parent().unwrap_or(Path::new(""));
+    if !directory.as_os_str().is_empty() {
+        std::fs::create_dir_all(directory)
+            .with_context(|| format!("Unable to create {}", directory.display()))?;
+    }
+    let stem = output
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .ok_or_else(|| anyhow::anyhow!("Invalid output prefix {}", output.display()))?;
+    let mut metadata = AtlasMetadata {
+        pages: Vec::with_capacity(pages.len()),
+        sprites,
+    };
+    for (index, page) in pages.iter().enumerate() {
+        let image = format!("{stem}_{index}.png");
+        page.save_png(&directory.join(&image))?;
+        metadata.pages.push(AtlasPage {
+            image,
+            width: page.width(),
+            height: page.height(),
+        });
+    }
+    let json_path = directory.join(format!("{stem}.json"));
+    std::fs::write(&json_path, serde_json::to_string_pretty(&metadata)?)
+        .with_context(|| format!("Unable to write {}", json_path.display()))?;
+
+    Ok(metadata)
+}
+
+/// Packs named `images` into as few pages as fit, largest first.
+pub fn pack(
+    images: Vec<(String, Image)>,
+    options: &PackOptions,
+) -> anyhow::Result<(Vec<Image>, BTreeMap<String, AtlasEntry>)> {
+    let border = options.extrude * 2 + options.padding;
+    // the padding after the last sprite of a row or column isn't needed
+    let bin_size = options.max_size + options.padding;
+    let trims = images
+        .iter()
+        .map(|(name, image)| {
+            let full = [0, 0, image.width(), image.height()];
+            let trim = if options.trim {
+                image.opaque_bounds().unwrap_or([0, 0, 1, 1])
+            } else {
+                full
+            };
+            if trim[2] + border > bin_size || trim[3] + border > bin_size {
+                anyhow::bail!(
+                    "{name} is {}x{}, too large for {}px pages",
+                    trim[2],
+                    trim[3],
+                    options.max_size
+                );
+            }
+            Ok(trim)
+        })
+        .collect::<anyhow::Result<Vec<_>>>()?;
+
+    let mut order: Vec<usize> = (0..images.len()).collect();
+    order.sort_by_key(|&index| {
+        let [.., width, height] = trims[index];
+        (
+            std::cmp::Reverse(width.max(height)),
+            std::cmp::Reverse(width * height),
+            &images[index].0,
+        )
+    });
+
+    let mut bins: Vec<MaxRects> = Vec::new();
+    let mut placements = vec![(0, Rect::default()); images.len()];
+    for index in order {
+        let [.., width, height] = trims[index];
+        let (width, height) = (width + border, height + border);
+        let placed = bins
+            .iter_mut()
+            .enumerate()
+            .find_map(|(page, bin)| Some((page, bin.insert(width, height)?)));
+        placements[index] = match placed {
+            Some(placement) => placement,
+            None => {
+                let mut bin = MaxRects::new(bin_size, bin_size);
+                let rect = bin
+                    .insert(width, height)
+                    .expect("sprite was checked to fit an empty page");
+                bins.push(bin);
+                (bins.len() - 1, rect)
+            }
+        };
+    }
+
+    // pages are cropped to the sprites on them
+    let mut sizes = vec![(1, 1); bins.len()];
+    for &(page, rect) in &placements {
+        let (width, height) = &mut sizes[page];
+        *width = (*width).max(rect.x + rect.width - options.padding);
+        *height = (*height).max(rect.y + rect.height - options.padding);
+    }
+    let mut pages: Vec<Image> = sizes
+        .iter()
+        .map(|&(width, height)| Image::new(width, height))
+        .collect();
+
+    let mut sprites = BTreeMap::new();
+    for (((name, image), trim), (page, rect)) in images.into_iter().zip(trims).zip(placements) {
+        let (x, y) = (rect.x + options.extrude, rect.y + options.extrude);
+        pages[page].blit(&image, trim, (x, y), options.extrude);
+        let rect = [x, y, trim[2], trim[3]];
+        sprites.insert(
+            name,
+            AtlasEntry {
+                page,
+                rect,
+                uv: UvRect::from_pixels(rect, sizes[page]),
+                source_size: [image.width(), image.height()],
+                trim_offset: [trim[0], trim[1]],
+            },
+        );
+    }
+
+    Ok((pages, sprites))
+}
+
+fn find_pngs(directory: &Path, files: &mut Vec<PathBuf>) -> anyhow::Result<()> {
+    let entries = std::fs::read_dir(directory)
+        .with_context(|| format!("Unable to read {}", directory.display()))?;
+    let mut paths = entries
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<Result<Vec<_>, _>>()?;
+    paths.sort();
+    for path in paths {
+        if path.is_dir() {
+            find_pngs(&path, files)?;
+        } else if path
+            .extension()
+            .is_some_and(|extension| extension.eq_ignore_ascii_case("png"))
+        {
+            files.push(path);
+        }
+    }
+
+    Ok(())
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+struct Rect {
+    x: u32,
+    y: u32,
+    width: u32,
+    height: u32,
+}
+impl Rect {
+    fn right(&self) -> u32 {
+        self.x + self.width
+    }
+
+    fn bottom(&self) -> u32 {
+        self.y + self.height
+    }
+
+    fn contains(&self, other: &Rect) -> bool {
+        other.x >= self.x
+            && other.y >= self.y
+            && other.right() <= self.right()
+            && other.bottom() <= self.bottom()
+    }
+
+    fn intersects(&self, other: &Rect) -> bool {
+        self.x < other.right()
+            && other.x < self.right()
+            && self.y < other.bottom()
+            && other.y < self.bottom()
+    }
+}
+
+/// Rectangle bin keeping every maximal free rectangle, placing with the best short side fit.
+struct MaxRects {
+    free: Vec<Rect>,
+}
+impl MaxRects {
+    fn new(width: u32, height: u32) -> Self {
+        Self {
+            free: vec![Rect {
+                x: 0,
+                y: 0,
+                width,
+                height,
+            }],
+        }
+    }
+
+    fn insert(&mut self, width: u32, height: u32) -> Option<Rect> {
+        let placed = self
+            .free
+            .iter()
+            .filter(|free| free.width >= width && free.height >= height)
+            .min_by_key(|free| {
+                let (left_x, left_y) = (free.width - width, free.height - height);
+                (left_x.min(left_y), left_x.max(left_y), free.y, free.x)
+            })
+            .map(|free| Rect {
+                x: free.x,
+                y: free.y,
+                width,
+                height,
+            })?;
+
+        let mut split = Vec::with_capacity(self.free.len() + 4);
+        for free in self.free.drain(..) {
+            if !free.intersects(&placed) {
+                split.push(free);
+                continue;
+            }
+            if placed.x > free.x {
+                split.push(Rect {
+                    width: placed.x - free.x,
+                    ..free
+                });
+            }
+            if placed.right() < free.right() {
+                split.push(Rect {
+                    x: placed.right(),
+                    width: free.right() - placed.right(),
+                    ..free
+                });
+            }
+            if placed.y > free.y {
+                split.push(Rect {
+                    height: placed.y - free.y,
+                    ..free
+                });
+            }
+            if placed.bottom() < free.bottom() {
+                split.push(Rect {
+                    y: placed.bottom(),
+                    height: free.bottom() - placed.bottom(),
+                    ..free
+                });
+            }
+        }
+        // keeps the first of identical rectangles
+        self.free = split
+            .iter()
+            .enumerate()
+            .filter(|&(index, rect)| {
+                !split.iter().enumerate().any(|(other_index, other)| {
+                    other_index != index
+                        && other.contains(rect)
+                        && (other != rect || other_index < index)
+                })
+            })
+            .map(|(_, rect)| *rect)
+            .collect();
+
+        Some(placed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rect(x: u32, y: u32, width: u32, height: u32) -> Rect {
+        Rect {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    /// Every free rectangle is inside the bin, misses the placed ones and isn't contained in
+    /// another.
+    fn check_free(bin: &MaxRects, bounds: Rect, placed: &[Rect]) {
+        for (index, free) in bin.free.iter().enumerate() {
+            assert!(bounds.contains(free), "{free:?} outside the bin");
+            assert!(free.width > 0 && free.height > 0, "empty {free:?}");
+            for used in placed {
+                assert!(!free.intersects(used), "{free:?} overlaps placed {used:?}");
+            }
+            for (other_index, other) in bin.free.iter().enumerate() {
+                assert!(
+                    other_index == index || !other.contains(free),
+                    "{free:?} is inside {other:?}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn first_rect_goes_to_the_top_left_and_splits_the_rest() {
+        let mut bin = MaxRects::new(64, 32);
+        assert_eq!(bin.insert(16, 8), Some(rect(0, 0, 16, 8)));
+        assert_eq!(bin.free, vec![rect(16, 0, 48, 32), rect(0, 8, 64, 24)]);
+    }
+
+    #[test]
+    fn packed_rects_never_overlap() {
+        let bounds = rect(0, 0, 128, 128);
+        let mut bin = MaxRects::new(bounds.width, bounds.height);
+        let mut placed = Vec::new();
+        // a fixed mix of sizes, more than fit
+        let mut seed = 7_u32;
+        for _ in 0..200 {
+            seed = seed.wrapping_mul(1_103_515_245).wrapping_add(12_345);
+            let (width, height) = (1 + (seed >> 16) % 24, 1 + (seed >> 8) % 24);
+            let Some(rect) = bin.insert(width, height) else {
+                continue;
+            };
+            assert_eq!((rect.width, rect.height), (width, height));
+            assert!(bounds.contains(&rect), "{rect:?} outside the bin");
+            for other in &placed {
+                assert!(!rect.intersects(other), "{rect:?} overlaps {other:?}");
+            }
+            placed.push(rect);
+            check_free(&bin, bounds, &placed);
+        }
+        assert!(placed.len() > 20);
+    }
+
+    #[test]
+    fn fills_the_bin_exactly() {
+        let mut bin = MaxRects::new(32, 32);
+        let placed: Vec<Rect> = (0..16).map_while(|_| bin.insert(8, 8)).collect();
+        assert_eq!(placed.len(), 16);
+        assert!(bin.free.is_empty());
+        assert_eq!(bin.insert(1, 1), None);
+    }
+
+    #[test]
+    fn contained_free_rects_are_pruned() {
+        let mut bin = MaxRects::new(32, 32);
+        bin.insert(8, 8);
+        assert_eq!(bin.insert(8, 8), Some(rect(8, 0, 8, 8)));
+        // splitting the right strip leaves (8, 8, 24, 24), inside the strip below the first
+        assert_eq!(bin.free, vec![rect(16, 0, 16, 32), rect(0, 8, 32, 24)]);
+    }
+
+    #[test]
+    fn duplicate_free_rects_are_pruned() {
+        let mut bin = MaxRects::new(32, 32);
+        bin.free = vec![rect(0, 0, 32, 32); 2];
+        bin.insert(8, 8);
+        assert_eq!(bin.free, vec![rect(8, 0, 24, 32), rect(0, 8, 32, 24)]);
+    }
+
+    #[test]
+    fn picks_the_best_short_side_fit() {
+        let mut bin = MaxRects::new(32, 32);
+        bin.free = vec![rect(0, 0, 20, 20), rect(20, 0, 12, 32)];
+        assert_eq!(bin.insert(10, 10).map(|rect| rect.x), Some(20));
+    }
+
+    #[test]
+    fn too_large_rects_are_refused() {
+        let mut bin = MaxRects::new(16, 16);
+        assert_eq!(bin.insert(17, 1), None);
+        assert_eq!(bin.insert(1, 17), None);
+        assert_eq!(bin.free, vec![rect(0, 0, 16, 16)]);
+    }
+}
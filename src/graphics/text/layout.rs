@@ -0,0 +1,137 @@
+use ab_glyph::{Font, FontArc, GlyphId, PxScale, PxScaleFont, ScaleFont};
+
+use crate::graphics::text::{Text, TextAlign};
+
+/// Glyph placed by [`layout`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct LaidOutGlyph {
+    pub id: GlyphId,
+    /// Pen position on the baseline in pixels, y pointing down from the top of the text.
+    pub position: [f32; 2],
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct TextLayout {
+    pub glyphs: Vec<LaidOutGlyph>,
+    /// Extent in pixels, the widest line by the distance from the first ascender to the last
+    /// descender.
+    pub size: [f32; 2],
+}
+
+type ScaledFont<'a> = PxScaleFont<&'a FontArc>;
+
+#[derive(Default)]
+struct Line {
+    glyphs: Vec<(GlyphId, f32)>,
+    width: f32,
+    previous: Option<GlyphId>,
+}
+
+/// Breaks `text` into lines and places its glyphs for a font size of `px` pixels.
+#[must_use]
+pub fn layout(font: &FontArc, text: &Text, px: f32) -> TextLayout {
+    let font = font.as_scaled(PxScale::from(px));
+    let max_width = text.max_width.map(|max_width| max_width * px / text.size);
+
+    let mut lines = Vec::new();
+    for paragraph in text.content.split('\n') {
+        let paragraph = paragraph.strip_suffix('\r').unwrap_or(paragraph);
+        let mut line = Line::default();
+        // whitespace is only placed once the word after it is known to fit the line
+        let mut pending: Vec<GlyphId> = Vec::new();
+        for (is_space, token) in tokens(paragraph) {
+            let ids = token.chars().map(|c| font.glyph_id(c));
+            if is_space {
+                pending.extend(ids);
+                continue;
+            }
+
+            let word: Vec<GlyphId> = ids.collect();
+            let word_width = measure(&font, &word, text.kerning);
+            if let Some(max_width) = max_width {
+                let spaces_width = measure(&font, &pending, false);
+                if !line.glyphs.is_empty() && line.width + spaces_width + word_width > max_width {
+                    lines.push(std::mem::take(&mut line));
+                    pending.clear();
+                }
+            }
+            for id in pending.drain(..) {
+                line.push(&font, id, text.kerning);
+            }
+            for id in word {
+                let split = max_width.is_some_and(|max_width| {
+                    word_width > max_width
+                        && !line.glyphs.is_empty()
+                        && line.width + font.h_advance(id) > max_width
+                });
+                if split {
+                    lines.push(std::mem::take(&mut line));
+                }
+                line.push(&font, id, text.kerning);
+            }
+        }
+        for id in pending {
+            line.push(&font, id, text.kerning);
+        }
+        lines.push(line);
+    }
+
+    let line_height = (font.height() + font.line_gap()) * text.line_spacing;
+    let mut layout = TextLayout {
+        glyphs: Vec::new(),
+        size: [
+            lines.iter().map(|line| line.width).fold(0.0, f32::max),
+            font.height() + line_height * (lines.len() - 1) as f32,
+        ],
+    };
+    for (index, line) in lines.into_iter().enumerate() {
+        let offset = match text.align {
+            TextAlign::Left => 0.0,
+            TextAlign::Center => -line.width * 0.5,
+            TextAlign::Right => -line.width,
+        };
+        let baseline = font.ascent() + line_height * index as f32;
+        layout
+            .glyphs
+            .extend(line.glyphs.into_iter().map(|(id, x)| LaidOutGlyph {
+                id,
+                position: [x + offset, baseline],
+            }));
+    }
+
+    layout
+}
+
+impl Line {
+    fn push(&mut self, font: &ScaledFont<'_>, id: GlyphId, kerning: bool) {
+        if let (true, Some(previous)) = (kerning, self.previous) {
+            self.width += font.kern(previous, id);
+        }
+        self.glyphs.push((id, self.width));
+        self.width += font.h_advance(id);
+        self.previous = Some(id);
+    }
+}
+
+fn measure(font: &ScaledFont<'_>, ids: &[GlyphId], kerning: bool) -> f32 {
+    let mut line = Line::default();
+    for &id in ids {
+        line.push(font, id, kerning);
+    }
+    line.width
+}
+
+/// Runs of whitespace and of everything else, in order.
+fn tokens(paragraph: &str) -> impl Iterator<Item = (bool, &str)> {
+    let mut rest = paragraph;
+    std::iter::from_fn(move || {
+        let first = rest.chars().next()?;
+        let is_space = first.is_whitespace();
+        let end = rest
+            .find(|c: char| c.is_whitespace() != is_space)
+            .unwrap_or(rest.len());
+        let (token, tail) = rest.split_at(end);
+        rest = tail;
+        Some((is_space, token))
+    })
+}
@@ -0,0 +1,118 @@
+mod layout;
+
+pub use layout::layout;
+
+use crate::graphics::{Color, Outline, Shadow};
+
+/// Handle of a font loaded with [`crate::graphics::GraphicsContext::load_font`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct FontId(pub(crate) u32);
+
+/// Horizontal placement of the lines relative to the object's origin.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum TextAlign {
+    /// Lines start at the origin.
+    #[default]
+    Left,
+    /// Lines are centered on the origin.
+    Center,
+    /// Lines end at the origin.
+    Right,
+}
+
+/// How glyphs are turned into texels.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum TextRendering {
+    /// Coverage rasterized at the size the text covers on screen, rebuilt when that changes.
+    #[default]
+    Bitmap,
+    /// Multi-channel signed distance fields rasterized once, sharp at any scale. The only mode
+    /// drawing outlines and shadows.
+    Msdf,
+}
+
+/// Text drawn by a [`crate::graphics::RenderObject`], see
+/// [`crate::graphics::RenderObject::text`]. The origin is at the top of the first line, sizes
+/// are in the same units as [`crate::graphics::Transform::position`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct Text {
+    pub content: String,
+    pub font: FontId,
+    /// Distance from the highest ascender to the lowest descender.
+    pub size: f32,
+    pub color: Color,
+    pub align: TextAlign,
+    /// Lines are wrapped between words to stay this narrow, words wider than that are split.
+    /// `None` only breaks lines at `\n`.
+    pub max_width: Option<f32>,
+    /// Multiplier of the font's line height.
+    pub line_spacing: f32,
+    /// Applies the font's `kern` table between neighbouring glyphs.
+    pub kerning: bool,
+    pub rendering: TextRendering,
+    /// Widths up to about a twelfth of `size` are drawn in full.
+    pub outline: Option<Outline>,
+    /// Offsets and softness up to about a sixth of `size` are drawn in full.
+    pub shadow: Option<Shadow>,
+}
+impl Text {
+    pub fn new(content: &str, font: FontId, size: f32) -> Self {
+        Self {
+            content: content.to_owned(),
+            font,
+            size,
+            color: Color::WHITE,
+            align: TextAlign::default(),
+            max_width: None,
+            line_spacing: 1.0,
+            kerning: true,
+            rendering: TextRendering::default(),
+            outline: None,
+            shadow: None,
+        }
+    }
+
+    pub fn with_color(mut self, color: Color) -> Self {
+        self.color = color;
+        self
+    }
+
+    pub fn with_align(mut self, align: TextAlign) -> Self {
+        self.align = align;
+        self
+    }
+
+    pub fn with_max_width(mut self, max_width: f32) -> Self {
+        self.max_width = Some(max_width);
+        self
+    }
+
+    pub fn with_line_spacing(mut self, line_spacing: f32) -> Self {
+        self.line_spacing = line_spacing;
+        self
+    }
+
+    pub fn with_kerning(mut self, kerning: bool) -> Self {
+        self.kerning = kerning;
+        self
+    }
+
+    pub fn with_rendering(mut self, rendering: TextRendering) -> Self {
+        self.rendering = rendering;
+        self
+    }
+
+    /// Switches to [`TextRendering::Msdf`], which outlines need.
+    pub fn with_outline(mut self, outline: Outline) -> Self {
+        self.outline = Some(outline);
+        self.rendering = TextRendering::Msdf;
+        self
+    }
+
+    /// Switches to [`TextRendering::Msdf`], which shadows need.
+    pub fn with_shadow(mut self, shadow: Shadow) -> Self {
+        self.shadow = Some(shadow);
+        self.rendering = TextRendering::Msdf;
+        self
+    }
+}
@@ -0,0 +1,251 @@
+use std::{
+    io::Read,
+    path::{Path, PathBuf},
+};
+
+use crate::graphics::{
+    Color, GraphicsContext,
+    image::Image,
+    tilemap::{Tile, TileFrame, TileLayer, Tilemap, Tileset, ldtk, tiled},
+};
+
+/// Tileset of an imported map, before its image is uploaded.
+#[derive(Clone, Debug, Default)]
+pub(super) struct TilesetData {
+    pub image: PathBuf,
+    pub tile_size: (u32, u32),
+    pub margin: u32,
+    pub spacing: u32,
+    pub animations: Vec<(u32, Vec<TileFrame>)>,
+}
+
+#[derive(Clone, Debug)]
+pub(super) struct LayerData {
+    pub name: String,
+    pub tileset: usize,
+    pub width: u32,
+    pub height: u32,
+    pub tiles: Vec<Option<Tile>>,
+    /// Pixels, `y` down like in the editors.
+    pub offset: [f32; 2],
+    pub opacity: f32,
+    pub visible: bool,
+}
+impl LayerData {
+    pub fn new(name: &str, tileset: usize, (width, height): (u32, u32)) -> Self {
+        Self {
+            name: name.to_owned(),
+            tileset,
+            width,
+            height,
+            tiles: vec![None; width as usize * height as usize],
+            offset: [0.0, 0.0],
+            opacity: 1.0,
+            visible: true,
+        }
+    }
+}
+
+/// Map as read from an editor's file, sizes in pixels.
+#[derive(Clone, Debug, Default)]
+pub(super) struct MapData {
+    pub tile_size: (u32, u32),
+    pub tilesets: Vec<TilesetData>,
+    /// Bottom to top.
+    pub layers: Vec<LayerData>,
+}
+
+impl Tilemap {
+    /// Imports a Tiled map (`.tmx`, or `.tmj` / `.json`) or the first level of an LDtk project
+    /// (`.ldtk`), uploading the tilesets' PNGs. Tiles are `tile_width` world units wide.
+    pub fn load(
+        graphics_context: &mut GraphicsContext,
+        path: &Path,
+        tile_width: f32,
+    ) -> anyhow::Result<Self> {
+        let extension = path
+            .extension()
+            .map(|extension| extension.to_string_lossy().to_lowercase());
+        let data = match extension.as_deref() {
+            Some("tmx") => tiled::load_tmx(path)?,
+            Some("tmj" | "json") => tiled::load_json(path)?,
+            Some("ldtk") => ldtk::load(path, None)?,
+            _ => anyhow::bail!("Unknown map format {}", path.display()),
+        };
+        data.upload(graphics_context, tile_width)
+    }
+
+    /// Imports the level called `level` of an LDtk project, see [`Tilemap::load`].
+    pub fn load_ldtk_level(
+        graphics_context: &mut GraphicsContext,
+        path: &Path,
+        level: &str,
+        tile_width: f32,
+    ) -> anyhow::Result<Self> {
+        ldtk::load(path, Some(level))?.upload(graphics_context, tile_width)
+    }
+}
+
+impl MapData {
+    fn upload(
+        self,
+        graphics_context: &mut GraphicsContext,
+        tile_width: f32,
+    ) -> anyhow::Result<Tilemap> {
+        let units_per_pixel = tile_width / self.tile_size.0.max(1) as f32;
+        let mut tilemap = Tilemap::new([tile_width, self.tile_size.1 as f32 * units_per_pixel]);
+
+        for data in self.tilesets {
+            let image = Image::load_png(&data.image)?;
+            let size = (image.width(), image.height());
+            let texture = graphics_context.create_texture(size.0, size.1, image.into_pixels())?;
+            let tileset = data.animations.into_iter().fold(
+                Tileset::new(texture, size, data.tile_size).with_spacing(data.margin, data.spacing),
+                |tileset, (tile, frames)| tileset.with_animation(tile, frames),
+            );
+            tilemap.add_tileset(tileset);
+        }
+        for layer in self.layers {
+            tilemap.add_layer(
+                TileLayer::new(&layer.name, layer.tileset, layer.width, layer.height)
+                    .with_tiles(layer.tiles)
+                    .with_offset([
+                        layer.offset[0] * units_per_pixel,
+                        -layer.offset[1] * units_per_pixel,
+                    ])
+                    .with_tint(Color::linear_rgba(1.0, 1.0, 1.0, layer.opacity))
+                    .with_visible(layer.visible),
+            );
+        }
+
+        Ok(tilemap)
+    }
+}
+
+/// Standard base64, whitespace is skipped.
+pub(super) fn decode_base64(text: &str) -> anyhow::Result<Vec<u8>> {
+    let mut bytes = Vec::with_capacity(text.len() / 4 * 3);
+    let (mut bits, mut bit_count) = (0_u32, 0);
+    for char in text.bytes() {
+        let value = match char {
+            b'A'..=b'Z' => char - b'A',
+            b'a'..=b'z' => char - b'a' + 26,
+            b'0'..=b'9' => char - b'0' + 52,
+            b'+' => 62,
+            b'/' => 63,
+            b'=' => break,
+            char if char.is_ascii_whitespace() => continue,
+            char => anyhow::bail!("Invalid base64 character {:?}", char as char),
+        };
+        bits = bits << 6 | value as u32;
+        bit_count += 6;
+        if bit_count >= 8 {
+            bit_count -= 8;
+            bytes.push((bits >> bit_count) as u8);
+        }
+    }
+
+    Ok(bytes)
+}
+
+/// Tile ids stored as base64 of little endian `u32`s, optionally compressed.
+pub(super) fn decode_tile_data(text: &str, compression: Option<&str>) -> anyhow::Result<Vec<u32>> {
+    let compressed = decode_base64(text)?;
+    let mut bytes = Vec::new();
+    match compression.unwrap_or("") {
+        "" => bytes = compressed,
+        "zlib" => {
+            flate2::read::ZlibDecoder::new(compressed.as_slice()).read_to_end(&mut bytes)?;
+        }
+        "gzip" => {
+            flate2::read::GzDecoder::new(compressed.as_slice()).read_to_end(&mut bytes)?;
+        }
+        compression => anyhow::bail!("Unsupported tile data compression {compression}"),
+    }
+
+    Ok(bytes
+        .chunks_exact(4)
+        .map(|id| u32::from_le_bytes([id[0], id[1], id[2], id[3]]))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use super::*;
+
+    const IDS: [u32; 3] = [1, 0x8000_0002, 300];
+
+    fn encode_base64(bytes: &[u8]) -> String {
+        const ALPHABET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+        let mut text = String::new();
+        for chunk in bytes.chunks(3) {
+            let bits = chunk.iter().enumerate().fold(0_u32, |bits, (i, &byte)| {
+                bits | (byte as u32) << (16 - 8 * i)
+            });
+            for i in 0..4 {
+                if i <= chunk.len() {
+                    text.push(ALPHABET[(bits >> (18 - 6 * i) & 63) as usize] as char);
+                } else {
+                    text.push('=');
+                }
+            }
+        }
+        text
+    }
+
+    fn id_bytes() -> Vec<u8> {
+        IDS.iter().flat_map(|id| id.to_le_bytes()).collect()
+    }
+
+    #[test]
+    fn base64_decodes_with_padding_and_whitespace() {
+        assert_eq!(decode_base64("TWFu").unwrap(), b"Man");
+        assert_eq!(decode_base64("TWE=").unwrap(), b"Ma");
+        assert_eq!(decode_base64("TQ==").unwrap(), b"M");
+        assert_eq!(decode_base64("\n  TW\tFu\n").unwrap(), b"Man");
+        assert_eq!(decode_base64("+/+/").unwrap(), [0xfb, 0xff, 0xbf]);
+        assert!(decode_base64("").unwrap().is_empty());
+    }
+
+    #[test]
+    fn base64_rejects_other_characters() {
+        assert!(decode_base64("TW-u").is_err());
+        assert!(decode_base64("TWFu!").is_err());
+    }
+
+    #[test]
+    fn plain_tile_data_decodes() {
+        let text = encode_base64(&id_bytes());
+
+        assert_eq!(decode_tile_data(&text, None).unwrap(), IDS);
+        assert_eq!(decode_tile_data(&text, Some("")).unwrap(), IDS);
+    }
+
+    #[test]
+    fn zlib_tile_data_decodes() {
+        let mut encoder =
+            flate2::write::ZlibEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(&id_bytes()).unwrap();
+        let text = encode_base64(&encoder.finish().unwrap());
+
+        assert_eq!(decode_tile_data(&text, Some("zlib")).unwrap(), IDS);
+    }
+
+    #[test]
+    fn gzip_tile_data_decodes() {
+        let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(&id_bytes()).unwrap();
+        let text = encode_base64(&encoder.finish().unwrap());
+
+        assert_eq!(decode_tile_data(&text, Some("gzip")).unwrap(), IDS);
+    }
+
+    #[test]
+    fn unknown_compression_is_an_error() {
+        let text = encode_base64(&id_bytes());
+
+        assert!(decode_tile_data(&text, Some("zstd")).is_err());
+    }
+}
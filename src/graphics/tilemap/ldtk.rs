@@ -0,0 +1,260 @@
+use std::path::Path;
+
+use anyhow::Context;
+use serde::Deserialize;
+
+use crate::graphics::tilemap::{
+    Tile,
+    import::{LayerData, MapData, TilesetData},
+};
+
+/// Reads the level called `level`, or the first one.
+pub(super) fn load(path: &Path, level: Option<&str>) -> anyhow::Result<MapData> {
+    let project: Project = read_json(path)?;
+    let directory = path.parent().unwrap_or(Path::new(""));
+    let level_data = match level {
+        Some(name) => project
+            .levels
+            .into_iter()
+            .find(|level| level.identifier == name)
+            .ok_or_else(|| anyhow::anyhow!("{} has no level {name}", path.display()))?,
+        None => project
+            .levels
+            .into_iter()
+            .next()
+            .ok_or_else(|| anyhow::anyhow!("{} has no levels", path.display()))?,
+    };
+    let instances = match (level_data.layer_instances, &level_data.external_rel_path) {
+        (Some(instances), _) => instances,
+        (None, Some(external)) => {
+            let level: Level = read_json(&directory.join(external))?;
+            level.layer_instances.unwrap_or_default()
+        }
+        (None, None) => Vec::new(),
+    };
+
+    let mut data = MapData::default();
+    let mut tileset_uids = Vec::new();
+    // the first instance is the top layer
+    for instance in instances.iter().rev() {
+        let tiles = if instance.grid_tiles.is_empty() {
+            &instance.auto_layer_tiles
+        } else {
+            &instance.grid_tiles
+        };
+        let Some(uid) = instance.tileset_def_uid else {
+            continue;
+        };
+        if tiles.is_empty() {
+            continue;
+        }
+        let grid_size = instance.grid_size.max(1);
+        if data.tile_size == (0, 0) {
+            data.tile_size = (grid_size, grid_size);
+        } else if data.tile_size != (grid_size, grid_size) {
+            log::warn!(
+                "Skipping layer {}, its {grid_size}px grid differs from the map's",
+                instance.identifier
+            );
+            continue;
+        }
+
+        let tileset = match tileset_uids.iter().position(|&other| other == uid) {
+            Some(tileset) => tileset,
+            None => {
+                let Some(definition) = project.defs.tilesets.iter().find(|def| def.uid == uid)
+                else {
+                    log::warn!("Skipping layer {}, it has no tileset", instance.identifier);
+                    continue;
+                };
+                let Some(rel_path) = &definition.rel_path else {
+                    log::warn!(
+                        "Skipping layer {}, tileset {} has no image",
+                        instance.identifier,
+                        definition.identifier
+                    );
+                    continue;
+                };
+                data.tilesets.push(TilesetData {
+                    image: directory.join(rel_path),
+                    tile_size: (definition.tile_grid_size, definition.tile_grid_size),
+                    margin: definition.padding,
+                    spacing: definition.spacing,
+                    animations: Vec::new(),
+                });
+                tileset_uids.push(uid);
+                tileset_uids.len() - 1
+            }
+        };
+
+        // tiles stacked in one cell go to extra layers above
+        let first_layer = data.layers.len();
+        let size = (instance.c_wid, instance.c_hei);
+        for tile in tiles {
+            let (x, y) = (tile.px[0] / grid_size as i32, tile.px[1] / grid_size as i32);
+            if x < 0 || y < 0 || x as u32 >= size.0 || y as u32 >= size.1 {
+                continue;
+            }
+            let cell = y as usize * size.0 as usize + x as usize;
+            let layer = match data.layers[first_layer..]
+                .iter()
+                .position(|layer| layer.tiles[cell].is_none())
+            {
+                Some(layer) => first_layer + layer,
+                None => {
+                    let mut layer = LayerData::new(&instance.identifier, tileset, size);
+                    layer.offset = [
+                        instance.px_total_offset_x as f32,
+                        instance.px_total_offset_y as f32,
+                    ];
+                    layer.opacity = instance.opacity;
+                    layer.visible = instance.visible;
+                    data.layers.push(layer);
+                    data.layers.len() - 1
+                }
+            };
+            data.layers[layer].tiles[cell] =
+                Some(Tile::new(tile.t).with_flip(tile.f & 1 != 0, tile.f & 2 != 0));
+        }
+    }
+
+    Ok(data)
+}
+
+fn read_json<T: for<'de> Deserialize<'de>>(path: &Path) -> anyhow::Result<T> {
+    let text = std::fs::read_to_string(path)
+        .with_context(|| format!("Unable to read {}", path.display()))?;
+    serde_json::from_str(&text).with_context(|| format!("Unable to parse {}", path.display()))
+}
+
+#[derive(Deserialize)]
+struct Project {
+    defs: Definitions,
+    levels: Vec<Level>,
+}
+
+#[derive(Deserialize)]
+struct Definitions {
+    tilesets: Vec<TilesetDefinition>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TilesetDefinition {
+    uid: i64,
+    identifier: String,
+    rel_path: Option<String>,
+    tile_grid_size: u32,
+    #[serde(default)]
+    spacing: u32,
+    #[serde(default)]
+    padding: u32,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Level {
+    #[serde(default)]
+    identifier: String,
+    layer_instances: Option<Vec<LayerInstance>>,
+    external_rel_path: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct LayerInstance {
+    #[serde(rename = "__identifier")]
+    identifier: String,
+    #[serde(rename = "__cWid")]
+    c_wid: u32,
+    #[serde(rename = "__cHei")]
+    c_hei: u32,
+    #[serde(rename = "__gridSize")]
+    grid_size: u32,
+    #[serde(rename = "__tilesetDefUid")]
+    tileset_def_uid: Option<i64>,
+    #[serde(rename = "__opacity", default = "full_opacity")]
+    opacity: f32,
+    #[serde(rename = "__pxTotalOffsetX", default)]
+    px_total_offset_x: i32,
+    #[serde(rename = "__pxTotalOffsetY", default)]
+    px_total_offset_y: i32,
+    #[serde(default = "visible")]
+    visible: bool,
+    #[serde(default)]
+    grid_tiles: Vec<TileInstance>,
+    #[serde(default)]
+    auto_layer_tiles: Vec<TileInstance>,
+}
+
+#[derive(Deserialize)]
+struct TileInstance {
+    /// Top left corner in the layer, pixels.
+    px: [i32; 2],
+    /// Bit 0 flips x, bit 1 flips y.
+    f: u8,
+    t: u32,
+}
+
+fn full_opacity() -> f32 {
+    1.0
+}
+
+fn visible() -> bool {
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stacked_tiles_go_to_extra_layers() {
+        let project = r#"{
+            "defs": { "tilesets": [
+                { "uid": 7, "identifier": "Terrain", "relPath": "terrain.png", "tileGridSize": 16 }
+            ] },
+            "levels": [{
+                "identifier": "Level_0",
+                "layerInstances": [{
+                    "__identifier": "Ground",
+                    "__cWid": 2,
+                    "__cHei": 1,
+                    "__gridSize": 16,
+                    "__tilesetDefUid": 7,
+                    "gridTiles": [
+                        { "px": [0, 0], "f": 0, "t": 1 },
+                        { "px": [0, 0], "f": 1, "t": 2 },
+                        { "px": [16, 0], "f": 2, "t": 3 },
+                        { "px": [0, 0], "f": 3, "t": 4 }
+                    ]
+                }]
+            }]
+        }"#;
+        let path = std::env::temp_dir().join(format!("ldtk-stacked-{}.ldtk", std::process::id()));
+        std::fs::write(&path, project).unwrap();
+        let data = load(&path, None);
+        std::fs::remove_file(&path).unwrap();
+        let data = data.unwrap();
+
+        assert_eq!(data.tile_size, (16, 16));
+        assert_eq!(data.tilesets.len(), 1);
+        assert_eq!(data.layers.len(), 3);
+        assert!(data.layers.iter().all(|layer| layer.name == "Ground"));
+        assert_eq!(
+            data.layers[0].tiles,
+            [
+                Some(Tile::new(1)),
+                Some(Tile::new(3).with_flip(false, true))
+            ]
+        );
+        assert_eq!(
+            data.layers[1].tiles,
+            [Some(Tile::new(2).with_flip(true, false)), None]
+        );
+        assert_eq!(
+            data.layers[2].tiles,
+            [Some(Tile::new(4).with_flip(true, true)), None]
+        );
+    }
+}
@@ -0,0 +1,602 @@
+mod import;
+mod ldtk;
+mod tiled;
+
+use std::collections::HashMap;
+
+use crate::graphics::{Color, TextureId, Vertex, sprite::UvRect};
+
+/// Tiles per side of a chunk, each chunk being one mesh and one draw.
+pub const DEFAULT_CHUNK_SIZE: u32 = 32;
+
+/// Cell of a [`TileLayer`], flips are applied in the order Tiled does: diagonal first.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Tile {
+    /// Index into the layer's [`Tileset`], row by row from the top left.
+    pub index: u32,
+    pub flip_x: bool,
+    pub flip_y: bool,
+    /// Swaps x and y, together with the other flips this rotates by 90° steps.
+    pub flip_diagonal: bool,
+}
+impl Tile {
+    #[must_use]
+    pub fn new(index: u32) -> Self {
+        Self {
+            index,
+            ..Self::default()
+        }
+    }
+
+    #[must_use]
+    pub fn with_flip(mut self, flip_x: bool, flip_y: bool) -> Self {
+        self.flip_x = flip_x;
+        self.flip_y = flip_y;
+        self
+    }
+
+    #[must_use]
+    pub fn with_flip_diagonal(mut self, flip_diagonal: bool) -> Self {
+        self.flip_diagonal = flip_diagonal;
+        self
+    }
+}
+
+/// Step of an animated tile.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TileFrame {
+    /// Tile shown instead of the animated one.
+    pub tile: u32,
+    /// Seconds the frame is shown for.
+    pub duration: f32,
+}
+
+/// Image cut into equally sized tiles.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Tileset {
+    texture: TextureId,
+    image_size: (u32, u32),
+    tile_size: (u32, u32),
+    margin: u32,
+    spacing: u32,
+    columns: u32,
+    /// Looping animations played by every tile with the key as index, in sync across the map.
+    animations: HashMap<u32, Vec<TileFrame>>,
+}
+impl Tileset {
+    /// `tile_size` and `image_size` in pixels of `texture`.
+    #[must_use]
+    pub fn new(texture: TextureId, image_size: (u32, u32), tile_size: (u32, u32)) -> Self {
+        Self {
+            texture,
+            image_size,
+            tile_size: (tile_size.0.max(1), tile_size.1.max(1)),
+            margin: 0,
+            spacing: 0,
+            columns: 0,
+            animations: HashMap::new(),
+        }
+        .with_spacing(0, 0)
+    }
+
+    /// Pixels around the tiles (`margin`) and between them (`spacing`).
+    #[must_use]
+    pub fn with_spacing(mut self, margin: u32, spacing: u32) -> Self {
+        self.margin = margin;
+        self.spacing = spacing;
+        let stride = self.tile_size.0 + spacing;
+        self.columns = (self.image_size.0.saturating_sub(margin * 2) + spacing) / stride;
+        self
+    }
+
+    #[must_use]
+    pub fn with_animation(mut self, tile: u32, frames: Vec<TileFrame>) -> Self {
+        if frames.is_empty() {
+            self.animations.remove(&tile);
+        } else {
+            self.animations.insert(tile, frames);
+        }
+        self
+    }
+
+    #[must_use]
+    pub fn texture(&self) -> TextureId {
+        self.texture
+    }
+
+    #[must_use]
+    pub fn tile_size(&self) -> (u32, u32) {
+        self.tile_size
+    }
+
+    #[must_use]
+    pub fn columns(&self) -> u32 {
+        self.columns
+    }
+
+    #[must_use]
+    pub fn tile_count(&self) -> u32 {
+        let rows = (self.image_size.1.saturating_sub(self.margin * 2) + self.spacing)
+            / (self.tile_size.1 + self.spacing);
+        self.columns * rows
+    }
+
+    #[must_use]
+    pub fn is_animated(&self, tile: u32) -> bool {
+        self.animations.contains_key(&tile)
+    }
+
+    /// Part of the texture showing tile `index`.
+    #[must_use]
+    pub fn uv(&self, index: u32) -> UvRect {
+        let columns = self.columns.max(1);
+        let (width, height) = self.tile_size;
+        let x = self.margin + index % columns * (width + self.spacing);
+        let y = self.margin + index / columns * (height + self.spacing);
+        UvRect::from_pixels([x, y, width, height], self.image_size)
+    }
+
+    /// Tile shown for `tile` `clock` seconds into its animation.
+    fn frame_at(&self, tile: u32, clock: f32) -> u32 {
+        let Some(frames) = self.animations.get(&tile) else {
+            return tile;
+        };
+        let duration: f32 = frames.iter().map(|frame| frame.duration.max(0.0)).sum();
+        if duration <= 0.0 {
+            return frames[0].tile;
+        }
+        let mut time = clock.rem_euclid(duration);
+        for frame in frames {
+            if time < frame.duration {
+                return frame.tile;
+            }
+            time -= frame.duration.max(0.0);
+        }
+        frames[frames.len() - 1].tile
+    }
+
+    /// Index of the frame `clock` seconds into every animation, to tell when one advanced.
+    fn animation_state(&self, clock: f32) -> Vec<(u32, u32)> {
+        let mut state: Vec<_> = self
+            .animations
+            .keys()
+            .map(|&tile| (tile, self.frame_at(tile, clock)))
+            .collect();
+        state.sort_unstable();
+        state
+    }
+}
+
+/// Grid of tiles from one [`Tileset`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct TileLayer {
+    pub name: String,
+    tileset: usize,
+    width: u32,
+    height: u32,
+    tiles: Vec<Option<Tile>>,
+    offset: [f32; 2],
+    tint: Color,
+    visible: bool,
+}
+impl TileLayer {
+    /// Empty `width` x `height` layer drawing from the `tileset`th tileset of its map.
+    #[must_use]
+    pub fn new(name: &str, tileset: usize, width: u32, height: u32) -> Self {
+        Self {
+            name: name.to_owned(),
+            tileset,
+            width,
+            height,
+            tiles: vec![None; width as usize * height as usize],
+            offset: [0.0, 0.0],
+            tint: Color::WHITE,
+            visible: true,
+        }
+    }
+
+    /// Cells row by row from the top left, missing ones are left empty.
+    #[must_use]
+    pub fn with_tiles(mut self, tiles: impl IntoIterator<Item = Option<Tile>>) -> Self {
+        for (cell, tile) in self.tiles.iter_mut().zip(tiles) {
+            *cell = tile;
+        }
+        self
+    }
+
+    /// Displacement from the map's position in world units, `y` up.
+    #[must_use]
+    pub fn with_offset(mut self, offset: [f32; 2]) -> Self {
+        self.offset = offset;
+        self
+    }
+
+    /// Multiplied with every tile, e.g. to fade the layer with the alpha.
+    #[must_use]
+    pub fn with_tint(mut self, tint: Color) -> Self {
+        self.tint = tint;
+        self
+    }
+
+    #[must_use]
+    pub fn with_visible(mut self, visible: bool) -> Self {
+        self.visible = visible;
+        self
+    }
+
+    #[must_use]
+    pub fn tileset(&self) -> usize {
+        self.tileset
+    }
+
+    #[must_use]
+    pub fn size(&self) -> (u32, u32) {
+        (self.width, self.height)
+    }
+
+    #[must_use]
+    pub fn tile(&self, x: u32, y: u32) -> Option<Tile> {
+        self.cell(x, y).and_then(|cell| self.tiles[cell])
+    }
+
+    #[must_use]
+    pub fn offset(&self) -> [f32; 2] {
+        self.offset
+    }
+
+    #[must_use]
+    pub fn tint(&self) -> Color {
+        self.tint
+    }
+
+    #[must_use]
+    pub fn visible(&self) -> bool {
+        self.visible
+    }
+
+    fn cell(&self, x: u32, y: u32) -> Option<usize> {
+        (x < self.width && y < self.height).then(|| y as usize * self.width as usize + x as usize)
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+struct Chunk {
+    dirty: bool,
+    /// Whether the chunk showed an animated tile when it was last built.
+    animated: bool,
+}
+
+/// Layers of tiles drawn as one object per chunk of `chunk_size` x `chunk_size` tiles, so
+/// editing a tile only rebuilds its chunk. Added to the scene with
+/// [`crate::app::State::add_tilemap`], which keeps the chunk objects up to date every
+/// [`crate::app::State::tick`]. Layers are drawn in order, later ones on top.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Tilemap {
+    tilesets: Vec<Tileset>,
+    layers: Vec<TileLayer>,
+    tile_size: [f32; 2],
+    chunk_size: u32,
+    position: [f32; 2],
+    /// Per layer, row by row.
+    chunks: Vec<Vec<Chunk>>,
+    positions_dirty: bool,
+    clock: f32,
+    animation_states: Vec<Vec<(u32, u32)>>,
+}
+impl Tilemap {
+    /// Empty map whose tiles are `tile_size` world units large.
+    #[must_use]
+    pub fn new(tile_size: [f32; 2]) -> Self {
+        Self {
+            tilesets: Vec::new(),
+            layers: Vec::new(),
+            tile_size,
+            chunk_size: DEFAULT_CHUNK_SIZE,
+            position: [0.0, 0.0],
+            chunks: Vec::new(),
+            positions_dirty: true,
+            clock: 0.0,
+            animation_states: Vec::new(),
+        }
+    }
+
+    /// Only takes effect before layers are added.
+    #[must_use]
+    pub fn with_chunk_size(mut self, chunk_size: u32) -> Self {
+        if self.layers.is_empty() {
+            self.chunk_size = chunk_size.max(1);
+        } else {
+            log::warn!("Chunk size can't change once the map has layers");
+        }
+        self
+    }
+
+    /// Top left corner of the map in world units.
+    #[must_use]
+    pub fn with_position(mut self, position: [f32; 2]) -> Self {
+        self.set_position(position);
+        self
+    }
+
+    #[must_use]
+    pub fn with_tileset(mut self, tileset: Tileset) -> Self {
+        self.add_tileset(tileset);
+        self
+    }
+
+    #[must_use]
+    pub fn with_layer(mut self, layer: TileLayer) -> Self {
+        self.add_layer(layer);
+        self
+    }
+
+    pub fn add_tileset(&mut self, tileset: Tileset) -> usize {
+        self.animation_states
+            .push(tileset.animation_state(self.clock));
+        self.tilesets.push(tileset);
+        self.tilesets.len() - 1
+    }
+
+    /// Adds `layer` on top of the others.
+    pub fn add_layer(&mut self, layer: TileLayer) -> usize {
+        if layer.tileset >= self.tilesets.len() {
+            log::warn!(
+                "Layer {} uses missing tileset {}",
+                layer.name,
+                layer.tileset
+            );
+        }
+        let (columns, rows) = chunk_grid(layer.size(), self.chunk_size);
+        self.chunks.push(vec![
+            Chunk {
+                dirty: true,
+                animated: false,
+            };
+            (columns * rows) as usize
+        ]);
+        self.layers.push(layer);
+        self.layers.len() - 1
+    }
+
+    #[must_use]
+    pub fn tile_size(&self) -> [f32; 2] {
+        self.tile_size
+    }
+
+    #[must_use]
+    pub fn chunk_size(&self) -> u32 {
+        self.chunk_size
+    }
+
+    #[must_use]
+    pub fn position(&self) -> [f32; 2] {
+        self.position
+    }
+
+    pub fn set_position(&mut self, position: [f32; 2]) {
+        self.position = position;
+        self.positions_dirty = true;
+    }
+
+    #[must_use]
+    pub fn tileset(&self, index: usize) -> Option<&Tileset> {
+        self.tilesets.get(index)
+    }
+
+    #[must_use]
+    pub fn tilesets(&self) -> &[Tileset] {
+        &self.tilesets
+    }
+
+    #[must_use]
+    pub fn layer(&self, index: usize) -> Option<&TileLayer> {
+        self.layers.get(index)
+    }
+
+    #[must_use]
+    pub fn layers(&self) -> &[TileLayer] {
+        &self.layers
+    }
+
+    /// Index of the first layer called `name`.
+    #[must_use]
+    pub fn layer_index(&self, name: &str) -> Option<usize> {
+        self.layers.iter().position(|layer| layer.name == name)
+    }
+
+    #[must_use]
+    pub fn tile(&self, layer: usize, x: u32, y: u32) -> Option<Tile> {
+        self.layers.get(layer)?.tile(x, y)
+    }
+
+    /// Replaces a cell, only its chunk is rebuilt. Returns `false` outside of the layer.
+    pub fn set_tile(&mut self, layer: usize, x: u32, y: u32, tile: Option<Tile>) -> bool {
+        let Some(cell) = self.layers.get(layer).and_then(|l| l.cell(x, y)) else {
+            return false;
+        };
+        if self.layers[layer].tiles[cell] != tile {
+            self.layers[layer].tiles[cell] = tile;
+            let chunk = self.chunk_of(layer, x, y);
+            self.chunks[layer][chunk].dirty = true;
+        }
+        true
+    }
+
+    pub fn set_layer_visible(&mut self, layer: usize, visible: bool) {
+        if let Some(l) = self.layers.get_mut(layer)
+            && l.visible != visible
+        {
+            l.visible = visible;
+            self.mark_layer_dirty(layer);
+        }
+    }
+
+    pub fn set_layer_tint(&mut self, layer: usize, tint: Color) {
+        if let Some(l) = self.layers.get_mut(layer)
+            && l.tint != tint
+        {
+            l.tint = tint;
+            self.mark_layer_dirty(layer);
+        }
+    }
+
+    pub fn set_layer_offset(&mut self, layer: usize, offset: [f32; 2]) {
+        if let Some(l) = self.layers.get_mut(layer) {
+            l.offset = offset;
+            self.positions_dirty = true;
+        }
+    }
+
+    /// Cell of `layer` at `point` in world units, `None` outside of the layer.
+    #[must_use]
+    pub fn cell_at(&self, layer: usize, [x, y]: [f32; 2]) -> Option<(u32, u32)> {
+        let l = self.layers.get(layer)?;
+        let column = (x - self.position[0] - l.offset[0]) / self.tile_size[0];
+        let row = (self.position[1] + l.offset[1] - y) / self.tile_size[1];
+        if column < 0.0 || row < 0.0 {
+            return None;
+        }
+        let (column, row) = (column as u32, row as u32);
+        l.cell(column, row).map(|_| (column, row))
+    }
+
+    /// Columns and rows of chunks `layer` is split into.
+    #[must_use]
+    pub fn chunk_grid(&self, layer: usize) -> (u32, u32) {
+        self.layers
+            .get(layer)
+            .map_or((0, 0), |l| chunk_grid(l.size(), self.chunk_size))
+    }
+
+    /// Moves the animations `seconds` ahead, marking the chunks showing a tile whose frame
+    /// changed.
+    pub(crate) fn advance(&mut self, seconds: f32) {
+        self.clock += seconds;
+        for (index, tileset) in self.tilesets.iter().enumerate() {
+            if tileset.animations.is_empty() {
+                continue;
+            }
+            let state = tileset.animation_state(self.clock);
+            if state == self.animation_states[index] {
+                continue;
+            }
+            self.animation_states[index] = state;
+            for (layer, chunks) in self.layers.iter().zip(&mut self.chunks) {
+                if layer.tileset == index {
+                    for chunk in chunks.iter_mut().filter(|chunk| chunk.animated) {
+                        chunk.dirty = true;
+                    }
+                }
+            }
+        }
+    }
+
+    /// `(layer, chunk)` of every chunk edited since the last call, chunks being numbered row
+    /// by row.
+    pub(crate) fn take_dirty_chunks(&mut self) -> Vec<(usize, usize)> {
+        let mut dirty = Vec::new();
+        for (layer, chunks) in self.chunks.iter_mut().enumerate() {
+            for (index, chunk) in chunks.iter_mut().enumerate() {
+                if std::mem::take(&mut chunk.dirty) {
+                    dirty.push((layer, index));
+                }
+            }
+        }
+        dirty
+    }
+
+    /// Whether the map or a layer moved since the last call.
+    pub(crate) fn take_positions_dirty(&mut self) -> bool {
+        std::mem::take(&mut self.positions_dirty)
+    }
+
+    /// Top left corner of a chunk in world units.
+    #[must_use]
+    pub fn chunk_position(&self, layer: usize, chunk: usize) -> [f32; 2] {
+        let (columns, _) = self.chunk_grid(layer);
+        let offset = self.layers.get(layer).map_or([0.0, 0.0], |l| l.offset);
+        let (column, row) = (chunk as u32 % columns.max(1), chunk as u32 / columns.max(1));
+        [
+            self.position[0] + offset[0] + (column * self.chunk_size) as f32 * self.tile_size[0],
+            self.position[1] + offset[1] - (row * self.chunk_size) as f32 * self.tile_size[1],
+        ]
+    }
+
+    /// Quads of a chunk's visible tiles relative to [`Tilemap::chunk_position`], and the
+    /// texture they sample.
+    pub(crate) fn build_chunk(
+        &mut self,
+        layer: usize,
+        chunk: usize,
+    ) -> (Vec<Vertex>, Vec<u32>, Option<TextureId>) {
+        let Some(l) = self.layers.get(layer) else {
+            return (Vec::new(), Vec::new(), None);
+        };
+        let Some(tileset) = self.tilesets.get(l.tileset) else {
+            return (Vec::new(), Vec::new(), None);
+        };
+        let (columns, _) = chunk_grid(l.size(), self.chunk_size);
+        let first_x = chunk as u32 % columns.max(1) * self.chunk_size;
+        let first_y = chunk as u32 / columns.max(1) * self.chunk_size;
+        let last_x = (first_x + self.chunk_size).min(l.width);
+        let last_y = (first_y + self.chunk_size).min(l.height);
+
+        let mut vertices = Vec::new();
+        let mut indices = Vec::new();
+        let mut animated = false;
+        let [tile_width, tile_height] = self.tile_size;
+        for y in first_y..last_y {
+            for x in first_x..last_x {
+                let Some(tile) = l.tile(x, y).filter(|_| l.visible) else {
+                    continue;
+                };
+                animated |= tileset.is_animated(tile.index);
+                let uv = tileset.uv(tileset.frame_at(tile.index, self.clock));
+
+                let left = (x - first_x) as f32 * tile_width;
+                let top = -((y - first_y) as f32) * tile_height;
+                let corner = |u: f32, v: f32| {
+                    Vertex::new([left + u * tile_width, top - v * tile_height], l.tint)
+                        .with_uv(uv.map(sample_point(tile, [u, v])))
+                };
+                let first = vertices.len() as u32;
+                vertices.extend([
+                    corner(0.0, 0.0),
+                    corner(0.0, 1.0),
+                    corner(1.0, 1.0),
+                    corner(1.0, 0.0),
+                ]);
+                indices.extend([0, 1, 2, 0, 2, 3].map(|index| first + index));
+            }
+        }
+        self.chunks[layer][chunk].animated = animated;
+
+        (vertices, indices, Some(tileset.texture))
+    }
+
+    fn chunk_of(&self, layer: usize, x: u32, y: u32) -> usize {
+        let (columns, _) = self.chunk_grid(layer);
+        ((y / self.chunk_size) * columns + x / self.chunk_size) as usize
+    }
+
+    fn mark_layer_dirty(&mut self, layer: usize) {
+        for chunk in &mut self.chunks[layer] {
+            chunk.dirty = true;
+        }
+    }
+}
+
+fn chunk_grid((width, height): (u32, u32), chunk_size: u32) -> (u32, u32) {
+    (width.div_ceil(chunk_size), height.div_ceil(chunk_size))
+}
+
+/// Point of the tile's image shown at `[u, v]` of its cell, undoing the flips in reverse.
+fn sample_point(tile: Tile, [mut u, mut v]: [f32; 2]) -> [f32; 2] {
+    if tile.flip_y {
+        v = 1.0 - v;
+    }
+    if tile.flip_x {
+        u = 1.0 - u;
+    }
+    if tile.flip_diagonal { [v, u] } else { [u, v] }
+}
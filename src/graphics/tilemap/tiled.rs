@@ -0,0 +1,495 @@
+use std::{path::Path, str::FromStr};
+
+use anyhow::Context;
+use serde::Deserialize;
+
+use crate::graphics::tilemap::{
+    Tile, TileFrame,
+    import::{LayerData, MapData, TilesetData, decode_tile_data},
+};
+
+const FLIP_X: u32 = 0x8000_0000;
+const FLIP_Y: u32 = 0x4000_0000;
+const FLIP_DIAGONAL: u32 = 0x2000_0000;
+/// Hexagonal maps only, ignored along with them.
+const ROTATE_120: u32 = 0x1000_0000;
+
+/// What a layer takes over from the groups it's in.
+#[derive(Clone, Copy, Debug)]
+struct Inherited {
+    offset: [f32; 2],
+    opacity: f32,
+    visible: bool,
+}
+impl Inherited {
+    const ROOT: Self = Self {
+        offset: [0.0, 0.0],
+        opacity: 1.0,
+        visible: true,
+    };
+
+    fn child(self, offset: [f32; 2], opacity: f32, visible: bool) -> Self {
+        Self {
+            offset: [self.offset[0] + offset[0], self.offset[1] + offset[1]],
+            opacity: self.opacity * opacity,
+            visible: self.visible && visible,
+        }
+    }
+}
+
+pub(super) fn load_tmx(path: &Path) -> anyhow::Result<MapData> {
+    let text = std::fs::read_to_string(path)
+        .with_context(|| format!("Unable to read {}", path.display()))?;
+    let document = roxmltree::Document::parse(&text)
+        .with_context(|| format!("Unable to parse {}", path.display()))?;
+    let map = document.root_element();
+    if !map.has_tag_name("map") {
+        anyhow::bail!("{} isn't a Tiled map", path.display());
+    }
+    check_map(
+        map.attribute("orientation").unwrap_or("orthogonal"),
+        map.attribute("infinite") == Some("1"),
+    )?;
+    let directory = path.parent().unwrap_or(Path::new(""));
+
+    let mut first_ids = Vec::new();
+    let mut tilesets = Vec::new();
+    for node in map.children().filter(|node| node.has_tag_name("tileset")) {
+        first_ids.push(required(node, "firstgid")?);
+        tilesets.push(match node.attribute("source") {
+            Some(source) => load_tsx(&directory.join(source))?,
+            None => xml_tileset(node, directory)?,
+        });
+    }
+
+    let mut layers = Vec::new();
+    xml_layers(map, Inherited::ROOT, &first_ids, &mut layers)?;
+
+    Ok(MapData {
+        tile_size: (required(map, "tilewidth")?, required(map, "tileheight")?),
+        tilesets,
+        layers,
+    })
+}
+
+fn load_tsx(path: &Path) -> anyhow::Result<TilesetData> {
+    let text = std::fs::read_to_string(path)
+        .with_context(|| format!("Unable to read {}", path.display()))?;
+    let document = roxmltree::Document::parse(&text)
+        .with_context(|| format!("Unable to parse {}", path.display()))?;
+
+    xml_tileset(
+        document.root_element(),
+        path.parent().unwrap_or(Path::new("")),
+    )
+}
+
+fn xml_tileset(node: roxmltree::Node, directory: &Path) -> anyhow::Result<TilesetData> {
+    let Some(image) = node
+        .children()
+        .find(|child| child.has_tag_name("image"))
+        .and_then(|image| image.attribute("source"))
+    else {
+        anyhow::bail!(
+            "Tileset {} has no image, collections of images aren't supported",
+            node.attribute("name").unwrap_or("")
+        );
+    };
+
+    let mut animations = Vec::new();
+    for tile in node.children().filter(|child| child.has_tag_name("tile")) {
+        let Some(animation) = tile
+            .children()
+            .find(|child| child.has_tag_name("animation"))
+        else {
+            continue;
+        };
+        let frames = animation
+            .children()
+            .filter(|child| child.has_tag_name("frame"))
+            .map(|frame| {
+                Ok(TileFrame {
+                    tile: required(frame, "tileid")?,
+                    duration: required::<u32>(frame, "duration")? as f32 / 1000.0,
+                })
+            })
+            .collect::<anyhow::Result<_>>()?;
+        animations.push((required(tile, "id")?, frames));
+    }
+
+    Ok(TilesetData {
+        image: directory.join(image),
+        tile_size: (required(node, "tilewidth")?, required(node, "tileheight")?),
+        margin: optional(node, "margin")?.unwrap_or(0),
+        spacing: optional(node, "spacing")?.unwrap_or(0),
+        animations,
+    })
+}
+
+/// Tile layers below `parent` in drawing order, flattening groups.
+fn xml_layers(
+    parent: roxmltree::Node,
+    inherited: Inherited,
+    first_ids: &[u32],
+    layers: &mut Vec<LayerData>,
+) -> anyhow::Result<()> {
+    for node in parent.children().filter(roxmltree::Node::is_element) {
+        let name = node.tag_name().name();
+        if !matches!(name, "layer" | "group") {
+            if matches!(name, "objectgroup" | "imagelayer") {
+                log::debug!("Skipping Tiled {name} {:?}", node.attribute("name"));
+            }
+            continue;
+        }
+        let inherited = inherited.child(
+            [
+                optional(node, "offsetx")?.unwrap_or(0.0),
+                optional(node, "offsety")?.unwrap_or(0.0),
+            ],
+            optional(node, "opacity")?.unwrap_or(1.0),
+            node.attribute("visible") != Some("0"),
+        );
+        if name == "group" {
+            xml_layers(node, inherited, first_ids, layers)?;
+            continue;
+        }
+
+        let size = (required(node, "width")?, required(node, "height")?);
+        let Some(data) = node.children().find(|child| child.has_tag_name("data")) else {
+            anyhow::bail!("Layer {:?} has no data", node.attribute("name"));
+        };
+        if data.children().any(|child| child.has_tag_name("chunk")) {
+            anyhow::bail!("Infinite maps aren't supported");
+        }
+        let ids = match data.attribute("encoding") {
+            Some("csv") => data
+                .text()
+                .unwrap_or("")
+                .split(',')
+                .map(|id| id.trim().parse().context("Invalid tile id"))
+                .collect::<anyhow::Result<_>>()?,
+            Some("base64") => {
+                decode_tile_data(data.text().unwrap_or(""), data.attribute("compression"))?
+            }
+            None => data
+                .children()
+                .filter(|child| child.has_tag_name("tile"))
+                .map(|tile| Ok(optional(tile, "gid")?.unwrap_or(0)))
+                .collect::<anyhow::Result<_>>()?,
+            Some(encoding) => anyhow::bail!("Unsupported tile data encoding {encoding}"),
+        };
+        let name = node.attribute("name").unwrap_or("");
+        layers.extend(split_layer(name, size, &ids, first_ids, inherited));
+    }
+
+    Ok(())
+}
+
+pub(super) fn load_json(path: &Path) -> anyhow::Result<MapData> {
+    let text = std::fs::read_to_string(path)
+        .with_context(|| format!("Unable to read {}", path.display()))?;
+    let map: JsonMap = serde_json::from_str(&text)
+        .with_context(|| format!("Unable to parse {}", path.display()))?;
+    check_map(&map.orientation, map.infinite)?;
+    let directory = path.parent().unwrap_or(Path::new(""));
+
+    let mut first_ids = Vec::new();
+    let mut tilesets = Vec::new();
+    for tileset in map.tilesets {
+        first_ids.push(tileset.firstgid);
+        tilesets.push(match &tileset.source {
+            Some(source) if source.to_lowercase().ends_with(".tsx") => {
+                load_tsx(&directory.join(source))?
+            }
+            Some(source) => {
+                let path = directory.join(source);
+                let text = std::fs::read_to_string(&path)
+                    .with_context(|| format!("Unable to read {}", path.display()))?;
+                let tileset: JsonTileset = serde_json::from_str(&text)
+                    .with_context(|| format!("Unable to parse {}", path.display()))?;
+                tileset.into_data(path.parent().unwrap_or(Path::new("")))?
+            }
+            None => tileset.into_data(directory)?,
+        });
+    }
+
+    let mut layers = Vec::new();
+    json_layers(&map.layers, Inherited::ROOT, &first_ids, &mut layers)?;
+
+    Ok(MapData {
+        tile_size: (map.tilewidth, map.tileheight),
+        tilesets,
+        layers,
+    })
+}
+
+fn json_layers(
+    children: &[JsonLayer],
+    inherited: Inherited,
+    first_ids: &[u32],
+    layers: &mut Vec<LayerData>,
+) -> anyhow::Result<()> {
+    for layer in children {
+        let inherited =
+            inherited.child([layer.offsetx, layer.offsety], layer.opacity, layer.visible);
+        match layer.kind.as_str() {
+            "group" => json_layers(&layer.layers, inherited, first_ids, layers)?,
+            "tilelayer" => {
+                if !layer.chunks.is_empty() {
+                    anyhow::bail!("Infinite maps aren't supported");
+                }
+                let ids = match &layer.data {
+                    Some(JsonData::Ids(ids)) => ids.clone(),
+                    Some(JsonData::Encoded(text)) => {
+                        decode_tile_data(text, layer.compression.as_deref())?
+                    }
+                    None => Vec::new(),
+                };
+                let size = (layer.width, layer.height);
+                layers.extend(split_layer(&layer.name, size, &ids, first_ids, inherited));
+            }
+            kind => log::debug!("Skipping Tiled {kind} {}", layer.name),
+        }
+    }
+
+    Ok(())
+}
+
+fn check_map(orientation: &str, infinite: bool) -> anyhow::Result<()> {
+    if infinite {
+        anyhow::bail!("Infinite maps aren't supported");
+    }
+    if !orientation.is_empty() && orientation != "orthogonal" {
+        log::warn!("{orientation} map is drawn as orthogonal");
+    }
+
+    Ok(())
+}
+
+/// One layer per tileset `ids` use, as a [`crate::graphics::TileLayer`] draws from only one.
+fn split_layer(
+    name: &str,
+    size: (u32, u32),
+    ids: &[u32],
+    first_ids: &[u32],
+    inherited: Inherited,
+) -> Vec<LayerData> {
+    let cell_count = size.0 as usize * size.1 as usize;
+    if ids.len() != cell_count {
+        log::warn!(
+            "Layer {name} has {} tiles, expected {cell_count}",
+            ids.len()
+        );
+    }
+
+    let mut layers: Vec<LayerData> = Vec::new();
+    for (cell, &id) in ids.iter().enumerate().take(cell_count) {
+        let index = id & !(FLIP_X | FLIP_Y | FLIP_DIAGONAL | ROTATE_120);
+        // the tileset with the highest first id up to it, 0 being no tile, in whatever order
+        // the file lists them
+        let Some((tileset, _)) = first_ids
+            .iter()
+            .enumerate()
+            .filter(|&(_, &first)| first <= index)
+            .max_by_key(|&(_, &first)| first)
+        else {
+            continue;
+        };
+        let layer = match layers.iter().position(|layer| layer.tileset == tileset) {
+            Some(layer) => layer,
+            None => {
+                let mut layer = LayerData::new(name, tileset, size);
+                layer.offset = inherited.offset;
+                layer.opacity = inherited.opacity;
+                layer.visible = inherited.visible;
+                layers.push(layer);
+                layers.len() - 1
+            }
+        };
+        layers[layer].tiles[cell] = Some(
+            Tile::new(index - first_ids[tileset])
+                .with_flip(id & FLIP_X != 0, id & FLIP_Y != 0)
+                .with_flip_diagonal(id & FLIP_DIAGONAL != 0),
+        );
+    }
+    layers.sort_by_key(|layer| layer.tileset);
+
+    layers
+}
+
+fn optional<T: FromStr>(node: roxmltree::Node, name: &str) -> anyhow::Result<Option<T>> {
+    node.attribute(name)
+        .map(|value| {
+            value
+                .parse()
+                .map_err(|_| anyhow::anyhow!("Invalid {name} {value:?}"))
+        })
+        .transpose()
+}
+
+fn required<T: FromStr>(node: roxmltree::Node, name: &str) -> anyhow::Result<T> {
+    optional(node, name)?
+        .ok_or_else(|| anyhow::anyhow!("<{}> is missing {name}", node.tag_name().name()))
+}
+
+#[derive(Deserialize)]
+struct JsonMap {
+    #[serde(default)]
+    orientation: String,
+    #[serde(default)]
+    infinite: bool,
+    tilewidth: u32,
+    tileheight: u32,
+    #[serde(default)]
+    tilesets: Vec<JsonTileset>,
+    #[serde(default)]
+    layers: Vec<JsonLayer>,
+}
+
+#[derive(Default, Deserialize)]
+#[serde(default)]
+struct JsonTileset {
+    firstgid: u32,
+    source: Option<String>,
+    name: String,
+    image: Option<String>,
+    tilewidth: u32,
+    tileheight: u32,
+    margin: u32,
+    spacing: u32,
+    tiles: Vec<JsonTile>,
+}
+impl JsonTileset {
+    fn into_data(self, directory: &Path) -> anyhow::Result<TilesetData> {
+        let Some(image) = self.image else {
+            anyhow::bail!(
+                "Tileset {} has no image, collections of images aren't supported",
+                self.name
+            );
+        };
+
+        Ok(TilesetData {
+            image: directory.join(image),
+            tile_size: (self.tilewidth, self.tileheight),
+            margin: self.margin,
+            spacing: self.spacing,
+            animations: self
+                .tiles
+                .into_iter()
+                .filter(|tile| !tile.animation.is_empty())
+                .map(|tile| {
+                    let frames = tile
+                        .animation
+                        .iter()
+                        .map(|frame| TileFrame {
+                            tile: frame.tileid,
+                            duration: frame.duration as f32 / 1000.0,
+                        })
+                        .collect();
+                    (tile.id, frames)
+                })
+                .collect(),
+        })
+    }
+}
+
+#[derive(Default, Deserialize)]
+#[serde(default)]
+struct JsonTile {
+    id: u32,
+    animation: Vec<JsonFrame>,
+}
+
+#[derive(Deserialize)]
+struct JsonFrame {
+    tileid: u32,
+    /// Milliseconds.
+    duration: u32,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum JsonData {
+    Ids(Vec<u32>),
+    Encoded(String),
+}
+
+#[derive(Deserialize)]
+#[serde(default)]
+struct JsonLayer {
+    #[serde(rename = "type")]
+    kind: String,
+    name: String,
+    width: u32,
+    height: u32,
+    data: Option<JsonData>,
+    compression: Option<String>,
+    chunks: Vec<serde_json::Value>,
+    offsetx: f32,
+    offsety: f32,
+    opacity: f32,
+    visible: bool,
+    layers: Vec<JsonLayer>,
+}
+impl Default for JsonLayer {
+    fn default() -> Self {
+        Self {
+            kind: String::new(),
+            name: String::new(),
+            width: 0,
+            height: 0,
+            data: None,
+            compression: None,
+            chunks: Vec::new(),
+            offsetx: 0.0,
+            offsety: 0.0,
+            opacity: 1.0,
+            visible: true,
+            layers: Vec::new(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn flip_flags_are_decoded_and_stripped() {
+        let ids = [
+            0,
+            1 | FLIP_X,
+            2 | FLIP_Y,
+            3 | FLIP_DIAGONAL,
+            4 | FLIP_X | FLIP_Y | FLIP_DIAGONAL | ROTATE_120,
+        ];
+        let layers = split_layer("ground", (5, 1), &ids, &[1], Inherited::ROOT);
+
+        assert_eq!(layers.len(), 1);
+        assert_eq!(
+            layers[0].tiles,
+            [
+                None,
+                Some(Tile::new(0).with_flip(true, false)),
+                Some(Tile::new(1).with_flip(false, true)),
+                Some(Tile::new(2).with_flip_diagonal(true)),
+                Some(Tile::new(3).with_flip(true, true).with_flip_diagonal(true)),
+            ]
+        );
+    }
+
+    #[test]
+    fn tiles_split_by_tileset_in_any_order() {
+        // the second tileset in the file starts first
+        let layers = split_layer("ground", (3, 1), &[1, 11, 5], &[10, 1], Inherited::ROOT);
+
+        assert_eq!(layers.len(), 2);
+        assert_eq!(layers[0].tileset, 0);
+        assert_eq!(layers[0].tiles, [None, Some(Tile::new(1)), None]);
+        assert_eq!(layers[1].tileset, 1);
+        assert_eq!(
+            layers[1].tiles,
+            [Some(Tile::new(0)), None, Some(Tile::new(4))]
+        );
+    }
+}
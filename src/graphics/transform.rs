@@ -1,23 +1,26 @@
 use bytemuck::{Pod, Zeroable};
 use num_traits::AsPrimitive;
 
-use crate::math::to_radians;
+use crate::math::{rotated_2d, to_radians};
 
+/// Laid out like the WGSL `TransformUniform`, where `scale` is 8-byte aligned.
 #[repr(C)]
 #[derive(Clone, Copy, Pod, Zeroable)]
 pub struct Transform {
     pub position: [f32; 2],
     pub rotation: f32,
+    _rotation_padding: f32,
     pub scale: [f32; 2],
-    _padding: [f32; 3],
+    _padding: [f32; 2],
 }
 impl Default for Transform {
     fn default() -> Self {
         Self {
             position: [0.0, 0.0],
             rotation: 0.0,
+            _rotation_padding: 0.0,
             scale: [1.0, 1.0],
-            _padding: [0.0; 3],
+            _padding: [0.0; 2],
         }
     }
 }
@@ -30,20 +33,12 @@ impl Transform {
         TransformBuilder::default()
     }
 
-    pub fn bind_group_layout(device: &wgpu::Device) -> wgpu::BindGroupLayout {
-        device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
-            label: Some("Transform Uniform Bind Group Layout"),
-            entries: &[wgpu::BindGroupLayoutEntry {
-                binding: 0,
-                visibility: wgpu::ShaderStages::VERTEX,
-                ty: wgpu::BindingType::Buffer {
-                    ty: wgpu::BufferBindingType::Uniform,
-                    has_dynamic_offset: false,
-                    min_binding_size: std::num::NonZeroU64::new(size_of::<Transform>() as u64),
-                },
-                count: None,
-            }],
-        })
+    /// Mesh point placed in the world the way `vs_main` in `basic.wgsl` does: scaled, rotated,
+    /// then moved.
+    #[must_use]
+    pub fn apply(&self, [x, y]: [f32; 2]) -> [f32; 2] {
+        let [x, y] = rotated_2d([x * self.scale[0], y * self.scale[1]], self.rotation);
+        [x as f32 + self.position[0], y as f32 + self.position[1]]
     }
 }
 
@@ -67,24 +62,6 @@ impl TransformBuilder {
         self
     }
 
-    pub fn physical_position<T: AsPrimitive<f64>, U: AsPrimitive<f64>>(
-        mut self,
-        physical_position: winit::dpi::PhysicalPosition<T>,
-        physical_size: winit::dpi::PhysicalSize<U>,
-    ) -> Self {
-        let half_width = physical_size.width.as_() * 0.5;
-        let half_height = physical_size.height.as_() * 0.5;
-
-        let mut x = physical_position.x.as_();
-        let mut y = physical_position.y.as_();
-
-        x = (x - half_width) / half_width;
-        y = -(y - half_height) / half_height;
-
-        self.position = [x, y];
-        self
-    }
-
     pub fn rotation<T: AsPrimitive<f64>>(mut self, rad: T) -> Self {
         self.rotation = rad.as_();
         self
@@ -104,8 +81,9 @@ impl TransformBuilder {
         Transform {
             position: [self.position[0] as f32, self.position[1] as f32],
             rotation: self.rotation as f32,
+            _rotation_padding: 0.0,
             scale: [self.scale[0] as f32, self.scale[1] as f32],
-            _padding: [0.0; 3],
+            _padding: [0.0; 2],
         }
     }
 }
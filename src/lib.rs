@@ -0,0 +1,5 @@
+#![allow(dead_code)] // TODO: disallow dead_code when ready
+
+pub mod app;
+pub mod graphics;
+mod math;
@@ -1,10 +1,10 @@
-#![allow(dead_code)] // TODO: disallow dead_code when ready
-
-mod app;
-mod graphics;
-mod math;
+use unnamed_engine::{app, graphics};
 
 fn main() -> anyhow::Result<()> {
     env_logger::init();
-    app::App::run()
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match args.split_first() {
+        Some((command, args)) if command == "pack-atlas" => graphics::pack_atlas(args),
+        _ => app::App::run(),
+    }
 }
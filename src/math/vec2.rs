@@ -1,5 +1,4 @@
 use num_traits::AsPrimitive;
-use winit::dpi::{PhysicalPosition, PhysicalSize};
 
 use crate::math::to_radians;
 
@@ -16,15 +15,6 @@ impl Vec2 {
         }
     }
 
-    pub fn from_physical_position<T: AsPrimitive<f64>, U: AsPrimitive<f64>>(
-        physical_position: PhysicalPosition<T>,
-        surface_size: PhysicalSize<U>,
-    ) {
-        let (width, height) = (surface_size.width, surface_size.height);
-        
-
-    }
-
     pub fn rotate<T: AsPrimitive<f64>>(&mut self, rad: T) {
         let c = rad.as_().cos();
         let s = rad.as_().sin();